@@ -0,0 +1,162 @@
+use std::{fs, io, io::Write};
+
+use crate::eval::{
+    evaluator::create_typed_err,
+    objects::{new_rc_object, BuildinParam, Object, ResultObj, ERR_ARCHIVO},
+};
+
+use super::{
+    internal::{Caller, InternalFnPointer},
+    native_fns, string_args, StringArg,
+};
+
+// Miembros del modulo nativo `archivo`
+pub fn module() -> Vec<(String, ResultObj)> {
+    let functions: [(&str, &[BuildinParam], Box<dyn InternalFnPointer>); 7] = [
+        ("leer", &[BuildinParam::new("ruta")], Box::new(leer)),
+        (
+            "escribir",
+            &[BuildinParam::new("ruta"), BuildinParam::new("contenido")],
+            Box::new(escribir),
+        ),
+        (
+            "agregar",
+            &[BuildinParam::new("ruta"), BuildinParam::new("contenido")],
+            Box::new(agregar),
+        ),
+        ("existe", &[BuildinParam::new("ruta")], Box::new(existe)),
+        ("eliminar", &[BuildinParam::new("ruta")], Box::new(eliminar)),
+        ("lineas", &[BuildinParam::new("ruta")], Box::new(lineas)),
+        ("listar", &[BuildinParam::new("ruta")], Box::new(listar)),
+    ];
+
+    native_fns(functions)
+}
+
+fn io_err(action: &str, path: &StringArg, err: io::Error) -> ResultObj {
+    let reason = match err.kind() {
+        io::ErrorKind::NotFound => "no existe".to_owned(),
+        io::ErrorKind::PermissionDenied => "permiso denegado".to_owned(),
+        io::ErrorKind::AlreadyExists => "ya existe".to_owned(),
+        io::ErrorKind::InvalidData => "el contenido no es texto UTF-8 valido".to_owned(),
+        _ => err.to_string(),
+    };
+    ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+        ERR_ARCHIVO,
+        format!("No se pudo {} `{}`, {}", action, path.value, reason),
+        path.line,
+        path.col,
+    ))))
+}
+
+fn string_obj(string: String) -> ResultObj {
+    ResultObj::Ref(new_rc_object(Object::String(string)))
+}
+
+// Retorna todo el contenido del archivo como una cadena
+pub fn leer(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "leer", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    match fs::read_to_string(&args[0].value) {
+        Ok(content) => string_obj(content),
+        Err(err) => io_err("leer el archivo", &args[0], err),
+    }
+}
+
+// Crea o reemplaza el archivo con el contenido dado
+pub fn escribir(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "escribir", 2) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    match fs::write(&args[0].value, &args[1].value) {
+        Ok(_) => ResultObj::Copy(Object::Void),
+        Err(err) => io_err("escribir el archivo", &args[0], err),
+    }
+}
+
+// Agrega el contenido al final del archivo, si no existe lo crea
+pub fn agregar(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "agregar", 2) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let res = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&args[0].value)
+        .and_then(|mut file| file.write_all(args[1].value.as_bytes()));
+    match res {
+        Ok(_) => ResultObj::Copy(Object::Void),
+        Err(err) => io_err("agregar al archivo", &args[0], err),
+    }
+}
+
+pub fn existe(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "existe", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    ResultObj::Copy(Object::Boolean(
+        std::path::Path::new(&args[0].value).exists(),
+    ))
+}
+
+// Elimina un archivo o un directorio vacio
+pub fn eliminar(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "eliminar", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let path = std::path::Path::new(&args[0].value);
+    let res = if path.is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match res {
+        Ok(_) => ResultObj::Copy(Object::Void),
+        Err(err) => io_err("eliminar", &args[0], err),
+    }
+}
+
+// Retorna una lista con cada linea del archivo, sin los saltos de linea
+pub fn lineas(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "lineas", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    match fs::read_to_string(&args[0].value) {
+        Ok(content) => ResultObj::Ref(new_rc_object(Object::List(
+            content
+                .lines()
+                .map(|line| string_obj(line.to_owned()))
+                .collect(),
+        ))),
+        Err(err) => io_err("leer el archivo", &args[0], err),
+    }
+}
+
+// Retorna los nombres de los elementos del directorio, ordenados
+pub fn listar(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "listar", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let entries = fs::read_dir(&args[0].value).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.file_name().to_string_lossy().to_string()))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    match entries {
+        Ok(mut names) => {
+            names.sort();
+            ResultObj::Ref(new_rc_object(Object::List(
+                names.into_iter().map(string_obj).collect(),
+            )))
+        }
+        Err(err) => io_err("listar el directorio", &args[0], err),
+    }
+}
@@ -1,13 +1,21 @@
-use std::io::Write;
+use std::{io::Write, rc::Rc};
 
 use crate::eval::{
-    environment::RcEnvironment,
-    evaluator::Evaluator,
-    objects::{new_rc_object, Object, ResultObj},
+    evaluator::{create_err, obj_text},
+    objects::{new_rc_object, BuildinFnObj, BuildinParam, Object, ResultObj},
 };
-use crate::{parser::expression::FnParams, types::Numeric};
+use crate::types::Numeric;
 
-pub trait InternalFnPointer: Fn(&mut Evaluator, FnParams, &RcEnvironment) -> ResultObj {
+// Llama a una funcion de Pana desde una funcion interna, sus errores toman la
+// posicion `line`, `col`. El evaluador y la maquina virtual llaman a las
+// funciones de forma distinta
+pub type Caller<'a> = dyn FnMut(ResultObj, Vec<ResultObj>, usize, usize) -> ResultObj + 'a;
+
+// Las funciones internas reciben sus argumentos ya evaluados y la posicion
+// de cada uno, para los errores
+pub trait InternalFnPointer:
+    Fn(&mut Caller, Vec<ResultObj>, &[(usize, usize)]) -> ResultObj
+{
     fn clone_box<'a>(&self) -> Box<dyn 'a + InternalFnPointer>
     where
         Self: 'a;
@@ -15,7 +23,7 @@ pub trait InternalFnPointer: Fn(&mut Evaluator, FnParams, &RcEnvironment) -> Res
 
 impl<F> InternalFnPointer for F
 where
-    F: Fn(&mut Evaluator, FnParams, &RcEnvironment) -> ResultObj + Clone,
+    F: Fn(&mut Caller, Vec<ResultObj>, &[(usize, usize)]) -> ResultObj + Clone,
 {
     fn clone_box<'a>(&self) -> Box<dyn 'a + InternalFnPointer>
     where
@@ -31,20 +39,43 @@ impl<'a> Clone for Box<dyn 'a + InternalFnPointer> {
     }
 }
 
+// Funcion interna por su nombre, ej. `imprimir`
+pub fn get_buildin(name: &str) -> Option<ResultObj> {
+    let (params, func): (Vec<BuildinParam>, Box<dyn InternalFnPointer>) = match name {
+        "longitud" => (vec![BuildinParam::new("valor")], Box::new(longitud)),
+        "tipo" => (vec![BuildinParam::new("valor")], Box::new(tipo)),
+        "imprimir" => (vec![BuildinParam::rest("valores")], Box::new(imprimir)),
+        "leer" => (
+            vec![BuildinParam::optional("mensaje", Object::Null)],
+            Box::new(leer),
+        ),
+        "cadena" => (vec![BuildinParam::new("valor")], Box::new(cadena)),
+        "es_instancia" => (
+            vec![BuildinParam::new("valor"), BuildinParam::new("clase")],
+            Box::new(es_instancia),
+        ),
+        _ => return None,
+    };
+    Some(ResultObj::Copy(Object::BuildinFn(Box::new(BuildinFnObj {
+        name: name.to_owned(),
+        params: Rc::from(params),
+        func,
+    }))))
+}
+
 // Funcion que retorna la longitud de un string o array
-pub fn longitud(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> ResultObj {
+pub fn longitud(_: &mut Caller, mut args: Vec<ResultObj>, _: &[(usize, usize)]) -> ResultObj {
     if args.len() != 1 {
-        return ResultObj::Copy(Object::Error(format!(
+        return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
             "Se encontro {} argumentos de 1",
             args.len()
-        )));
+        )))));
     }
-    let arg_obj = eval.eval_expression(args.get(0).unwrap().clone(), env);
-    match arg_obj {
-        ResultObj::Copy(obj) => ResultObj::Copy(Object::Error(format!(
+    match args.remove(0) {
+        ResultObj::Copy(obj) => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
             "Se espera un tipo de dato cadena, no {}",
             obj.get_type()
-        ))),
+        ))))),
         ResultObj::Ref(obj) => match &*obj.borrow() {
             Object::List(objs) => ResultObj::Copy(Object::Numeric(Numeric::Int(objs.len() as i64))),
             Object::Dictionary(pairs) => {
@@ -53,43 +84,43 @@ pub fn longitud(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> Re
             Object::String(string) => {
                 ResultObj::Copy(Object::Numeric(Numeric::Int(string.len() as i64)))
             }
-            obj => ResultObj::Copy(Object::Error(format!(
+            obj => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
                 "Se espera un tipo de dato cadena, no {}",
                 obj.get_type()
-            ))),
+            ))))),
         },
     }
 }
 
 // Funcion que imprime en una linea objetos en pantalla
-pub fn imprimir(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> ResultObj {
-    if !args.is_empty() {
-        let objs = args
-            .iter()
-            .map(|arg| eval.eval_expression(arg.clone(), env))
-            .collect::<Vec<_>>();
-        let string = objs
-            .iter()
-            .map(|obj| obj.to_string())
-            .collect::<Vec<_>>()
-            .join("");
-        println!("{}", string);
-        return ResultObj::Copy(Object::Void);
+pub fn imprimir(
+    call: &mut Caller,
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+) -> ResultObj {
+    let mut string = String::new();
+    for (obj, (line, col)) in args.iter().zip(positions) {
+        let (line, col) = (*line, *col);
+        match obj_text(obj, line, col, &mut |callee, args| {
+            call(callee, args, line, col)
+        }) {
+            Ok(text) => string.push_str(&text),
+            Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+        }
     }
-    println!();
+    println!("{}", string);
     ResultObj::Copy(Object::Void)
 }
 
 // Funcion que retorna el tipo de dato del objeto
-pub fn tipo(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> ResultObj {
+pub fn tipo(_: &mut Caller, mut args: Vec<ResultObj>, _: &[(usize, usize)]) -> ResultObj {
     if args.len() != 1 {
-        return ResultObj::Copy(Object::Error(format!(
+        return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
             "Se encontro {} argumentos de 1",
             args.len()
-        )));
+        )))));
     }
-    let arg_obj = eval.eval_expression(args.get(0).unwrap().clone(), env);
-    match arg_obj {
+    match args.remove(0) {
         ResultObj::Copy(obj) => ResultObj::Ref(new_rc_object(Object::String(obj.get_type()))),
         ResultObj::Ref(obj) => {
             ResultObj::Ref(new_rc_object(Object::String(obj.borrow().get_type())))
@@ -97,57 +128,84 @@ pub fn tipo(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> Result
     }
 }
 
+// Verdadero si `obj` es una instancia de la clase o de alguna que herede de ella
+pub fn es_instancia(_: &mut Caller, values: Vec<ResultObj>, _: &[(usize, usize)]) -> ResultObj {
+    if values.len() != 2 {
+        return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+            "Se encontro {} argumentos de 2",
+            values.len()
+        )))));
+    }
+    let class = match &values[1] {
+        ResultObj::Copy(Object::Class(class)) => class,
+        obj => {
+            return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+                "El segundo argumento de `es_instancia` debe ser una clase, no {}",
+                obj.get_type()
+            )))))
+        }
+    };
+    let is_instance = match &values[0] {
+        ResultObj::Ref(obj) => match &*obj.borrow() {
+            Object::Instance(instance) => instance.class.inherits(class),
+            _ => false,
+        },
+        ResultObj::Copy(_) => false,
+    };
+    ResultObj::Copy(Object::Boolean(is_instance))
+}
+
 // Funcion que permite un input desde el terminal
-pub fn leer(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> ResultObj {
+pub fn leer(_: &mut Caller, mut args: Vec<ResultObj>, _: &[(usize, usize)]) -> ResultObj {
     match args.len() {
-        0 => {
-            let mut output = String::new();
-            std::io::stdin().read_line(&mut output).unwrap();
-            ResultObj::Ref(new_rc_object(Object::String(output)))
-        }
-        1 => {
-            let arg_obj = eval.eval_expression(args.get(0).unwrap().clone(), env);
-            return match arg_obj {
-                ResultObj::Copy(obj) => ResultObj::Copy(Object::Error(format!(
+        1 => match args.remove(0) {
+            // Sin mensaje
+            ResultObj::Copy(Object::Null) => {
+                let mut output = String::new();
+                std::io::stdin().read_line(&mut output).unwrap();
+                ResultObj::Ref(new_rc_object(Object::String(output)))
+            }
+            ResultObj::Copy(obj) => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+                "Se espera un tipo de dato cadena, no {}",
+                obj.get_type()
+            ))))),
+            ResultObj::Ref(obj) => match &*obj.borrow() {
+                Object::String(promp) => {
+                    let mut output = String::new();
+                    print!("{}", promp);
+                    std::io::stdout().flush().unwrap();
+                    std::io::stdin().read_line(&mut output).unwrap();
+                    ResultObj::Ref(new_rc_object(Object::String(output.trim_end().to_owned())))
+                }
+                _ => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
                     "Se espera un tipo de dato cadena, no {}",
-                    obj.get_type()
-                ))),
-                ResultObj::Ref(obj) => match &*obj.borrow() {
-                    Object::String(promp) => {
-                        let mut output = String::new();
-                        print!("{}", promp);
-                        std::io::stdout().flush().unwrap();
-                        std::io::stdin().read_line(&mut output).unwrap();
-                        return ResultObj::Ref(new_rc_object(Object::String(
-                            output.trim_end().to_owned(),
-                        )));
-                    }
-                    _ => ResultObj::Copy(Object::Error(format!(
-                        "Se espera un tipo de dato cadena, no {}",
-                        obj.borrow().get_type()
-                    ))),
-                },
-            };
-        }
-        _ => ResultObj::Copy(Object::Error(format!(
+                    obj.borrow().get_type()
+                ))))),
+            },
+        },
+        _ => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
             "Se encontro {} argumentos de 1",
             args.len()
-        ))),
+        ))))),
     }
 }
 
-pub fn cadena(eval: &mut Evaluator, args: FnParams, env: &RcEnvironment) -> ResultObj {
+pub fn cadena(
+    call: &mut Caller,
+    mut args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+) -> ResultObj {
     if args.len() != 1 {
-        return ResultObj::Copy(Object::Error(format!(
+        return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
             "Se encontro {} argumentos de 1",
             args.len()
-        )));
+        )))));
     }
-    let arg_obj = eval.eval_expression(args.get(0).unwrap().clone(), env);
-    match arg_obj {
-        ResultObj::Copy(obj) => ResultObj::Ref(new_rc_object(Object::String(obj.to_string()))),
-        ResultObj::Ref(obj) => {
-            ResultObj::Ref(new_rc_object(Object::String(obj.borrow().to_string())))
-        }
+    let (line, col) = positions[0];
+    match obj_text(&args.remove(0), line, col, &mut |callee, args| {
+        call(callee, args, line, col)
+    }) {
+        Ok(text) => ResultObj::Ref(new_rc_object(Object::String(text))),
+        Err(err) => ResultObj::Copy(Object::Error(Box::new(err))),
     }
 }
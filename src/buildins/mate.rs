@@ -0,0 +1,420 @@
+use std::cmp::Ordering;
+
+use num_bigint::BigInt;
+use num_traits::Signed;
+
+use crate::{
+    eval::{
+        evaluator::{create_err, create_msg_err, create_typed_err},
+        objects::{BuildinParam, Object, ResultObj, ERR_DOMINIO},
+    },
+    types::Numeric,
+};
+
+use super::{
+    internal::{Caller, InternalFnPointer},
+    native_fns, numeric_args, NumericArg,
+};
+
+// Miembros del modulo nativo `mate`
+pub fn module() -> Vec<(String, ResultObj)> {
+    let functions: [(&str, &[BuildinParam], Box<dyn InternalFnPointer>); 20] = [
+        ("raiz", &[BuildinParam::new("x")], Box::new(raiz)),
+        (
+            "potencia",
+            &[BuildinParam::new("base"), BuildinParam::new("exponente")],
+            Box::new(potencia),
+        ),
+        ("abs", &[BuildinParam::new("x")], Box::new(abs)),
+        ("piso", &[BuildinParam::new("x")], Box::new(piso)),
+        ("techo", &[BuildinParam::new("x")], Box::new(techo)),
+        ("redondear", &[BuildinParam::new("x")], Box::new(redondear)),
+        ("sen", &[BuildinParam::new("x")], Box::new(sen)),
+        ("cos", &[BuildinParam::new("x")], Box::new(cos)),
+        ("tan", &[BuildinParam::new("x")], Box::new(tan)),
+        ("asen", &[BuildinParam::new("x")], Box::new(asen)),
+        ("acos", &[BuildinParam::new("x")], Box::new(acos)),
+        ("atan", &[BuildinParam::new("x")], Box::new(atan)),
+        (
+            "atan2",
+            &[BuildinParam::new("y"), BuildinParam::new("x")],
+            Box::new(atan2),
+        ),
+        ("exp", &[BuildinParam::new("x")], Box::new(exp)),
+        ("ln", &[BuildinParam::new("x")], Box::new(ln)),
+        ("log10", &[BuildinParam::new("x")], Box::new(log10)),
+        ("log2", &[BuildinParam::new("x")], Box::new(log2)),
+        (
+            "log",
+            &[BuildinParam::new("x"), BuildinParam::new("base")],
+            Box::new(log),
+        ),
+        ("min", &[BuildinParam::new("lista")], Box::new(min)),
+        ("max", &[BuildinParam::new("lista")], Box::new(max)),
+    ];
+
+    let mut members = native_fns(functions);
+    members.push((
+        "PI".to_owned(),
+        ResultObj::Copy(Object::Numeric(Numeric::Float(std::f64::consts::PI))),
+    ));
+    members.push((
+        "E".to_owned(),
+        ResultObj::Copy(Object::Numeric(Numeric::Float(std::f64::consts::E))),
+    ));
+    members
+}
+
+fn domain_err(name: &str, expected: &str, arg: &NumericArg) -> ResultObj {
+    ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+        ERR_DOMINIO,
+        format!(
+            "Dominio invalido para `{}`: se espera {}, se encontro {}",
+            name, expected, arg.value
+        ),
+        arg.line,
+        arg.col,
+    ))))
+}
+
+// Bits que puede tener el resultado entero de `potencia`, unos 300 000
+// digitos. Mas alla el calculo tarda demasiado y agota la memoria
+const MAX_POW_BITS: f64 = 1_048_576.0;
+
+// Estima los bits del resultado como e * log2|b|
+fn int_pow_bits(base: &Numeric, exponent: u32) -> f64 {
+    let bits = match base {
+        Numeric::Int(int) => (int.unsigned_abs() as f64).log2(),
+        Numeric::BigInt(int) => int.bits() as f64,
+        Numeric::Float(_) => unreachable!("La base es entera"),
+    };
+    bits * exponent as f64
+}
+
+// Solo usa BigInt si el resultado no cabe en i64
+fn int_pow(base: &Numeric, exponent: u32) -> Numeric {
+    match base {
+        Numeric::Int(int) => match int.checked_pow(exponent) {
+            Some(int) => Numeric::Int(int),
+            None => Numeric::from(BigInt::from(*int).pow(exponent)),
+        },
+        Numeric::BigInt(int) => Numeric::from(int.pow(exponent)),
+        Numeric::Float(_) => unreachable!("La base es entera"),
+    }
+}
+
+fn float_obj(float: f64) -> ResultObj {
+    ResultObj::Copy(Object::Numeric(Numeric::Float(float)))
+}
+
+// Valida el argumento y describe lo que se esperaba
+type Domain = (fn(f64) -> bool, &'static str);
+
+// Aplica una funcion de flotantes a un solo argumento, validando su dominio
+fn unary_float(
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+    name: &str,
+    func: fn(f64) -> f64,
+    domain: Option<Domain>,
+) -> ResultObj {
+    let args = match numeric_args(args, positions, name, 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let arg = &args[0];
+    let x = arg.value.to_f64();
+    if let Some((valid, expected)) = domain {
+        if !valid(x) {
+            return domain_err(name, expected, arg);
+        }
+    }
+    float_obj(func(x))
+}
+
+// Convierte el flotante a entero para piso, techo y redondear
+fn round_with(
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+    name: &str,
+    func: fn(f64) -> f64,
+) -> ResultObj {
+    let args = match numeric_args(args, positions, name, 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let arg = &args[0];
+    match arg.value {
+        Numeric::Float(float) => match Numeric::from_f64_int(func(float)) {
+            Some(int) => ResultObj::Copy(Object::Numeric(int)),
+            None => domain_err(name, "un numero finito", arg),
+        },
+        ref int => ResultObj::Copy(Object::Numeric(int.clone())),
+    }
+}
+
+pub fn raiz(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "raiz",
+        f64::sqrt,
+        Some((|x| x >= 0.0, "un numero mayor o igual a 0")),
+    )
+}
+
+pub fn potencia(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "potencia", 2) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let (base, exponent) = (&args[0], &args[1]);
+    if base.value.to_f64() == 0.0 && exponent.value.to_f64() < 0.0 {
+        return domain_err(
+            "potencia",
+            "un exponente positivo cuando la base es 0",
+            exponent,
+        );
+    }
+    match (&base.value, &exponent.value) {
+        (b @ (Numeric::Int(_) | Numeric::BigInt(_)), Numeric::Int(e)) if *e >= 0 => {
+            match u32::try_from(*e) {
+                Ok(e) if e < 2 || int_pow_bits(b, e) <= MAX_POW_BITS => {
+                    ResultObj::Copy(Object::Numeric(int_pow(b, e)))
+                }
+                _ => ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+                    ERR_DOMINIO,
+                    format!(
+                        "El resultado de `potencia` excede el rango de los enteros: {} elevado a {}",
+                        b, e
+                    ),
+                    exponent.line,
+                    exponent.col,
+                )))),
+            }
+        }
+        (b, e) => {
+            let result = b.to_f64().powf(e.to_f64());
+            if result.is_nan() {
+                return domain_err(
+                    "potencia",
+                    "un exponente entero cuando la base es negativa",
+                    exponent,
+                );
+            }
+            float_obj(result)
+        }
+    }
+}
+
+pub fn abs(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "abs", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let arg = &args[0];
+    match arg.value {
+        Numeric::Int(int) => match int.checked_abs() {
+            Some(int) => ResultObj::Copy(Object::Numeric(Numeric::Int(int))),
+            None => ResultObj::Copy(Object::Numeric(Numeric::from(BigInt::from(int).abs()))),
+        },
+        Numeric::BigInt(ref int) => ResultObj::Copy(Object::Numeric(Numeric::from(int.abs()))),
+        Numeric::Float(float) => float_obj(float.abs()),
+    }
+}
+
+pub fn piso(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    round_with(args, positions, "piso", f64::floor)
+}
+
+pub fn techo(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    round_with(args, positions, "techo", f64::ceil)
+}
+
+pub fn redondear(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    round_with(args, positions, "redondear", f64::round)
+}
+
+pub fn sen(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(args, positions, "sen", f64::sin, None)
+}
+
+pub fn cos(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(args, positions, "cos", f64::cos, None)
+}
+
+pub fn tan(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(args, positions, "tan", f64::tan, None)
+}
+
+pub fn asen(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "asen",
+        f64::asin,
+        Some((|x| (-1.0..=1.0).contains(&x), "un numero entre -1 y 1")),
+    )
+}
+
+pub fn acos(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "acos",
+        f64::acos,
+        Some((|x| (-1.0..=1.0).contains(&x), "un numero entre -1 y 1")),
+    )
+}
+
+pub fn atan(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(args, positions, "atan", f64::atan, None)
+}
+
+pub fn atan2(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "atan2", 2) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    float_obj(args[0].value.to_f64().atan2(args[1].value.to_f64()))
+}
+
+pub fn exp(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(args, positions, "exp", f64::exp, None)
+}
+
+pub fn ln(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "ln",
+        f64::ln,
+        Some((|x| x > 0.0, "un numero mayor a 0")),
+    )
+}
+
+pub fn log10(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "log10",
+        f64::log10,
+        Some((|x| x > 0.0, "un numero mayor a 0")),
+    )
+}
+
+pub fn log2(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    unary_float(
+        args,
+        positions,
+        "log2",
+        f64::log2,
+        Some((|x| x > 0.0, "un numero mayor a 0")),
+    )
+}
+
+// Logaritmo con base arbitraria: log(x, base)
+pub fn log(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "log", 2) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let (x, base) = (&args[0], &args[1]);
+    if x.value.to_f64() <= 0.0 {
+        return domain_err("log", "un numero mayor a 0", x);
+    }
+    let b = base.value.to_f64();
+    if b <= 0.0 || b == 1.0 {
+        return domain_err("log", "una base mayor a 0 y distinta de 1", base);
+    }
+    float_obj(x.value.to_f64().log(b))
+}
+
+pub fn min(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    extreme(args, positions, "min", Ordering::Less)
+}
+
+pub fn max(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    extreme(args, positions, "max", Ordering::Greater)
+}
+
+// Busca el menor o mayor numero de una lista, conservando su tipo
+fn extreme(
+    mut args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+    name: &str,
+    wanted: Ordering,
+) -> ResultObj {
+    if args.len() != 1 {
+        return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+            "`{}` espera 1 argumentos, se encontro {}",
+            name,
+            args.len()
+        )))));
+    }
+    let (line, col) = positions[0];
+    let obj = match args.remove(0) {
+        ResultObj::Ref(obj) => obj,
+        obj => {
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "`{}` espera un tipo de dato lista, no {}",
+                    name,
+                    obj.get_type()
+                ),
+                line,
+                col,
+            ))))
+        }
+    };
+    let obj = obj.borrow();
+    let list = match &*obj {
+        Object::List(list) => list,
+        obj => {
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "`{}` espera un tipo de dato lista, no {}",
+                    name,
+                    obj.get_type()
+                ),
+                line,
+                col,
+            ))))
+        }
+    };
+
+    let mut result: Option<Numeric> = None;
+    for item in list {
+        let value = match item {
+            ResultObj::Copy(Object::Numeric(value)) => value,
+            item => {
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!(
+                        "`{}` solo acepta listas de numeros, se encontro un tipo de dato {}",
+                        name,
+                        item.get_type()
+                    ),
+                    line,
+                    col,
+                ))))
+            }
+        };
+        let replace = match &result {
+            // Compara los enteros sin pasarlos a flotante, asi no pierden precision
+            Some(current) => value.partial_cmp(current) == Some(wanted),
+            None => true,
+        };
+        if replace {
+            result = Some(value.clone());
+        }
+    }
+
+    match result {
+        Some(value) => ResultObj::Copy(Object::Numeric(value)),
+        None => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!("`{}` no puede operar con una lista vacia", name),
+            line,
+            col,
+        )))),
+    }
+}
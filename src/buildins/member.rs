@@ -1,61 +1,123 @@
+use std::cmp::Ordering;
+
 use crate::{
+    buildins::internal::Caller,
     eval::{
-        environment::RcEnvironment,
-        evaluator::{create_msg_err, Evaluator},
-        objects::{new_rc_object, Object, ResultObj},
+        evaluator::{call_operator, check_dict_key, check_iteration_lock, create_msg_err},
+        objects::{new_rc_object, Object, RcObject, ResultObj},
     },
     lexer::Lexer,
-    parser::expression::FnParams,
     token::TokenType,
     types::Numeric,
 };
 
+// Funciones miembro que modifican al objeto, no se pueden usar mientras
+// un bucle `para` lo recorre
+const MUTATING_MEMBERS: [&str; 10] = [
+    "eliminar",
+    "limpiar",
+    "insertar",
+    "invertir",
+    "agregar",
+    "ordenar",
+    "concatenar",
+    "eliminar_indice",
+    "reemplazar",
+    "recortar",
+];
+
+// Funciones miembro de las listas que reciben una funcion de Pana
+const CALLBACK_MEMBERS: [&str; 6] = ["mapear", "filtrar", "reducir", "cada", "alguno", "todos"];
+
+// Llama a una funcion con argumentos ya evaluados. El evaluador y la maquina
+// virtual llaman a las funciones de forma distinta
+pub type CallFn<'a> = dyn FnMut(ResultObj, Vec<ResultObj>) -> ResultObj + 'a;
+
+// `ordenar` sin funcion tambien llama a Pana si compara instancias con `menor`
+pub fn is_callback_member(identifier: &str) -> bool {
+    CALLBACK_MEMBERS.contains(&identifier) || identifier == "ordenar"
+}
+
+// `locks` son las colecciones que estan siendo recorridas
+pub fn member_lock_err(
+    locks: &[RcObject],
+    identifier: &str,
+    target: &ResultObj,
+    target_line: usize,
+    target_col: usize,
+) -> Option<ResultObj> {
+    if !MUTATING_MEMBERS.contains(&identifier) {
+        return None;
+    }
+    match target {
+        ResultObj::Ref(obj) => check_iteration_lock(locks, obj, target_line, target_col),
+        ResultObj::Copy(_) => None,
+    }
+}
+
+// Los argumentos ya vienen evaluados, `positions` es la posicion de cada uno.
+// Quien llama revisa antes `member_lock_err`
 pub fn match_member_fn(
-    eval: &mut Evaluator,
+    call: &mut Caller,
     identifier: String,
-    args: FnParams,
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
+    if is_callback_member(&identifier) {
+        let callback_pos = positions
+            .first()
+            .copied()
+            .unwrap_or((target_line, target_col));
+        let (line, col) = callback_pos;
+        return callback_member(
+            &identifier,
+            args,
+            target,
+            target_line,
+            target_col,
+            callback_pos,
+            &mut |callee, args| call(callee, args, line, col),
+        );
+    }
     match identifier.as_ref() {
         // Mixto
-        "eliminar" => eliminar(eval, args, target, target_line, target_col, env),
+        "eliminar" => eliminar(args, positions, target, target_line, target_col),
         "limpiar" => limpiar(args, target, target_line, target_col),
-        "buscar" => buscar(eval, args, target, target_line, target_col, env),
-        "insertar" => insertar(eval, args, target, target_line, target_col, env),
+        "buscar" => buscar(args, target, target_line, target_col),
+        "insertar" => insertar(args, target, target_line, target_col),
         "vacio" => vacio(args, target, target_line, target_col),
         "invertir" => invertir(args, target, target_line, target_col),
 
         // Funciones miembro de las listas
-        "agregar" => agregar(eval, args, target, target_line, target_col, env),
-        "indice" => indice(eval, args, target, target_line, target_col, env),
-        "ordenar" => ordenar(eval, args, target, target_line, target_col, env),
-        "concatenar" => concatenar(eval, args, target, target_line, target_col, env),
-        "eliminar_indice" => eliminar_indice(eval, args, target, target_line, target_col, env),
-        "juntar" => juntar(eval, args, target, target_line, target_col, env),
+        "agregar" => agregar(args, target, target_line, target_col),
+        "indice" => indice(args, target, target_line, target_col),
+        "concatenar" => concatenar(args, target, target_line, target_col),
+        "eliminar_indice" => eliminar_indice(args, target, target_line, target_col),
+        "juntar" => juntar(args, target, target_line, target_col),
 
         // Funciones miembro de los dicccionarios
         "llaves" => llaves(args, target, target_line, target_col),
         "valores" => valores(args, target, target_line, target_col),
 
         // Funciones miembro de las cadenas
-        "separar" => separar(eval, args, target, target_line, target_col, env),
-        "caracter" => caracter(eval, args, target, target_line, target_col, env),
+        "separar" => separar(args, target, target_line, target_col),
+        "caracter" => caracter(args, target, target_line, target_col),
         "caracteres" => caracteres(args, target, target_line, target_col),
         "es_alfabetico" => es_alfabetico(args, target, target_line, target_col),
         "es_numerico" => es_numerico(args, target, target_line, target_col),
         "es_alfanumerico" => es_alfanumerico(args, target, target_line, target_col),
-        "inicia_con" => inicia_con(eval, args, target, target_line, target_col, env),
-        "termina_con" => termina_con(eval, args, target, target_line, target_col, env),
+        "inicia_con" => inicia_con(args, target, target_line, target_col),
+        "termina_con" => termina_con(args, target, target_line, target_col),
         "a_mayusculas" => a_mayusculas(args, target, target_line, target_col),
         "a_minusculas" => a_minusculas(args, target, target_line, target_col),
-        "reemplazar" => reemplazar(eval, args, target, target_line, target_col, env),
+        "reemplazar" => reemplazar(args, target, target_line, target_col),
         "recortar" => recortar(args, target, target_line, target_col),
-        "subcadena" => subcadena(eval, args, target, target_line, target_col, env),
+        "subcadena" => subcadena(args, target, target_line, target_col),
         "a_numerico" => a_numerico(args, target, target_line, target_col),
-        _ => ResultObj::Copy(Object::Error(create_msg_err(
+        _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
             format!(
                 "El tipo de dato {} no posee el miembro `{}`",
                 target.get_type(),
@@ -63,19 +125,19 @@ pub fn match_member_fn(
             ),
             target_line,
             target_col + 2,
-        ))),
+        )))),
     }
 }
 
 fn missmatch_type(name: &str, obj_type: &str, target_line: usize, target_col: usize) -> ResultObj {
-    ResultObj::Copy(Object::Error(create_msg_err(
+    ResultObj::Copy(Object::Error(Box::new(create_msg_err(
         format!(
             "El tipo de dato {} no posee el miembro `{}`",
             obj_type, name
         ),
         target_line,
         target_col + 2,
-    )))
+    ))))
 }
 
 fn missmatch_type_arg(
@@ -84,11 +146,11 @@ fn missmatch_type_arg(
     target_line: usize,
     target_col: usize,
 ) -> ResultObj {
-    ResultObj::Copy(Object::Error(create_msg_err(
+    ResultObj::Copy(Object::Error(Box::new(create_msg_err(
         format!("Se espera un tipo de dato {}, no {}.", name, obj_type),
         target_line,
         target_col + name.len(),
-    )))
+    ))))
 }
 
 fn missmatch_args(
@@ -98,112 +160,154 @@ fn missmatch_args(
     target_line: usize,
     target_col: usize,
 ) -> ResultObj {
-    ResultObj::Copy(Object::Error(create_msg_err(
+    ResultObj::Copy(Object::Error(Box::new(create_msg_err(
         format!("Se encontro {} argumentos de {}", len, max),
         target_line,
         target_col + name_len + 3 + len,
-    )))
+    ))))
 }
 
-fn quick_sort(slice: &mut [ResultObj]) -> Option<ResultObj> {
-    let len = slice.len();
-    _quick_sort(slice, 0, (len - 1) as isize)
+// Ordenamiento estable por mezcla. La comparacion puede fallar, por ejemplo
+// si la funcion de Pana que compara retorna un error, y se detiene en el
+// primer error. No se usa `sort_by` porque este entra en panico si la
+// comparacion no es un orden total, algo que un programa de Pana no garantiza
+fn merge_sort<T, F>(mut items: Vec<T>, cmp: &mut F) -> Result<Vec<T>, ResultObj>
+where
+    F: FnMut(&T, &T) -> Result<Ordering, ResultObj>,
+{
+    if items.len() < 2 {
+        return Ok(items);
+    }
+    let right = items.split_off(items.len() / 2);
+    let left = merge_sort(items, cmp)?;
+    let right = merge_sort(right, cmp)?;
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        // Con elementos iguales va primero el de la izquierda
+        let next = if cmp(l, r)? == Ordering::Greater {
+            right.next()
+        } else {
+            left.next()
+        };
+        merged.extend(next);
+    }
+    merged.extend(left);
+    merged.extend(right);
+    Ok(merged)
 }
 
-fn _quick_sort(slice: &mut [ResultObj], low: isize, high: isize) -> Option<ResultObj> {
-    if low < high {
-        match partition(slice, low, high) {
-            Ok(p) => {
-                let mut res = _quick_sort(slice, low, p - 1);
-                if res.is_some() {
-                    return res;
-                }
-                res = _quick_sort(slice, p + 1, high);
-                if res.is_some() {
-                    return res;
-                }
-            }
-            Err(res) => return Some(res),
-        }
+// Compara como `<`: si el primero es una instancia con el metodo `menor`, se
+// llama en ambos sentidos para distinguir menor, igual y mayor
+fn compare_default(
+    a: &ResultObj,
+    b: &ResultObj,
+    line: usize,
+    col: usize,
+    call: &mut CallFn,
+) -> Result<Ordering, ResultObj> {
+    let Some(less) = instance_less(a, b, line, col, call) else {
+        return compare_objs(a, b, line, col);
+    };
+    if less? {
+        return Ok(Ordering::Less);
     }
-    None
+    let greater = match instance_less(b, a, line, col, call) {
+        Some(greater) => greater?,
+        None => false,
+    };
+    Ok(if greater {
+        Ordering::Greater
+    } else {
+        Ordering::Equal
+    })
 }
 
-fn partition(slice: &mut [ResultObj], low: isize, high: isize) -> Result<isize, ResultObj> {
-    let pivot = high as usize;
-    let mut store_index = low - 1;
-    let mut last_index = high;
-    loop {
-        store_index += 1;
-        loop {
-            match slice[store_index as usize].partial_cmp(&slice[pivot]) {
-                Some(ord) => match ord {
-                    std::cmp::Ordering::Less => store_index += 1,
-                    _ => break,
-                },
-                None => todo!("Error no se puede comparar"),
-            }
-        }
-
-        last_index -= 1;
-
-        while last_index >= 0 {
-            match slice[last_index as usize].partial_cmp(&slice[pivot]) {
-                Some(ord) => match ord {
-                    std::cmp::Ordering::Greater => last_index -= 1,
-                    _ => break,
-                },
-                None => todo!("Error no se puede comparar"),
-            }
-        }
+// None si `a` no es una instancia con el metodo `menor`
+fn instance_less(
+    a: &ResultObj,
+    b: &ResultObj,
+    line: usize,
+    col: usize,
+    call: &mut CallFn,
+) -> Option<Result<bool, ResultObj>> {
+    Some(match call_operator(a, b, &TokenType::Lt, call)? {
+        ResultObj::Copy(Object::Boolean(less)) => Ok(less),
+        err @ ResultObj::Copy(Object::Error(_)) => Err(err),
+        obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "El metodo `menor` debe retornar un valor logico, no {}",
+                obj.get_type()
+            ),
+            line,
+            col,
+        ))))),
+    })
+}
 
-        if store_index >= last_index {
-            break;
-        } else {
-            slice.swap(store_index as usize, last_index as usize);
-        }
-    }
-    slice.swap(store_index as usize, pivot as usize);
-    Ok(store_index)
+fn compare_objs(
+    a: &ResultObj,
+    b: &ResultObj,
+    line: usize,
+    col: usize,
+) -> Result<Ordering, ResultObj> {
+    a.partial_cmp(b).ok_or_else(|| {
+        ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "No se puede comparar el tipo de dato {} con {}",
+                a.get_type(),
+                b.get_type()
+            ),
+            line,
+            col,
+        ))))
+    })
 }
 
 // TODO sumar el numero de caracteres a las columnas
 
 pub fn eliminar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "eliminar".len(), target_line, target_col);
     }
-    let obj_to_remove = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&obj_to_remove) {
-        return obj_to_remove;
-    }
+    let (arg_line, arg_col) = positions[0];
+    let obj_to_remove = args.remove(0);
     match target {
         ResultObj::Copy(obj) => {
             missmatch_type("eliminar", &obj.get_type(), target_line, target_col)
         }
-        ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
-            Object::List(ref mut list) => match list.iter().position(|obj| *obj == obj_to_remove) {
-                Some(index) => list.remove(index),
-                None => ResultObj::Copy(Object::Null),
-            },
-            Object::Dictionary(ref mut dict) => match dict.remove(&obj_to_remove) {
-                Some(obj) => obj,
-                None => ResultObj::Copy(Object::Null),
-            },
-            ref obj => missmatch_type("eliminar", &obj.get_type(), target_line, target_col),
-        },
+        ResultObj::Ref(ref_obj) => {
+            if let Object::Dictionary(_) = *ref_obj.borrow() {
+                if let Err(err) = check_dict_key(&obj_to_remove, arg_line, arg_col) {
+                    return ResultObj::Copy(Object::Error(Box::new(err)));
+                }
+            }
+            match *ref_obj.as_ref().borrow_mut() {
+                Object::List(ref mut list) => {
+                    match list.iter().position(|obj| *obj == obj_to_remove) {
+                        Some(index) => list.remove(index),
+                        None => ResultObj::Copy(Object::Null),
+                    }
+                }
+                Object::Dictionary(ref mut dict) => match dict.shift_remove(&obj_to_remove) {
+                    Some(obj) => obj,
+                    None => ResultObj::Copy(Object::Null),
+                },
+                ref obj => missmatch_type("eliminar", &obj.get_type(), target_line, target_col),
+            }
+        }
     }
 }
 
 pub fn limpiar(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -232,20 +336,15 @@ pub fn limpiar(
 }
 
 pub fn buscar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "buscar".len(), target_line, target_col);
     }
-    let find_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&find_obj) {
-        return find_obj;
-    }
+    let find_obj = args.remove(0);
     match target {
         ResultObj::Copy(obj) => missmatch_type("buscar", &obj.get_type(), target_line, target_col),
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
@@ -271,32 +370,24 @@ pub fn buscar(
 }
 
 pub fn insertar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 2 {
         return missmatch_args(2, args.len(), "insertar".len(), target_line, target_col);
     }
-    let insert_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&insert_obj) {
-        return insert_obj;
-    }
-    let index_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&index_obj) {
-        return index_obj;
-    }
+    let insert_obj = args.remove(0);
+    let index_obj = args.remove(0);
     let index;
     if let ResultObj::Copy(Object::Numeric(Numeric::Int(int))) = index_obj {
         if int < 0 {
-            return ResultObj::Copy(Object::Error(create_msg_err(
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 "El indice debe ser un numero positivo.".into(),
                 target_line,
                 target_col,
-            )));
+            ))));
         }
         index = int;
     } else {
@@ -315,11 +406,11 @@ pub fn insertar(
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
             Object::List(ref mut list) => {
                 if (index as usize) < list.len() {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         "El indice esta fuera del rango.".into(),
                         target_line,
                         target_col,
-                    )));
+                    ))));
                 }
                 list.insert(index as usize, insert_obj);
                 ResultObj::Copy(Object::Void)
@@ -328,11 +419,11 @@ pub fn insertar(
                 ResultObj::Copy(insert_obj) => match insert_obj {
                     Object::String(string2) => {
                         if (index as usize) < string.len() {
-                            return ResultObj::Copy(Object::Error(create_msg_err(
+                            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                                 "El indice esta fuera del rango.".into(),
                                 target_line,
                                 target_col,
-                            )));
+                            ))));
                         }
                         string.insert_str(index as usize, &string2);
                         ResultObj::Copy(Object::Void)
@@ -349,7 +440,7 @@ pub fn insertar(
 }
 
 pub fn vacio(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -369,7 +460,7 @@ pub fn vacio(
 }
 
 pub fn invertir(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -396,12 +487,10 @@ pub fn invertir(
 }
 
 pub fn agregar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "agregar".len(), target_line, target_col);
@@ -410,10 +499,7 @@ pub fn agregar(
         ResultObj::Copy(obj) => missmatch_type("agregar", &obj.get_type(), target_line, target_col),
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
             Object::List(ref mut list) => {
-                let new_obj = eval.eval_expression(args.remove(0), env);
-                if eval.is_error(&new_obj) {
-                    return new_obj;
-                }
+                let new_obj = args.remove(0);
                 list.push(new_obj);
                 ResultObj::Copy(Object::Void)
             }
@@ -423,20 +509,15 @@ pub fn agregar(
 }
 
 pub fn indice(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "indice".len(), target_line, target_col);
     }
-    let find_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&find_obj) {
-        return find_obj;
-    }
+    let find_obj = args.remove(0);
     match target {
         ResultObj::Copy(obj) => missmatch_type("indice", &obj.get_type(), target_line, target_col),
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
@@ -449,65 +530,219 @@ pub fn indice(
     }
 }
 
-#[allow(dead_code, unused)]
-pub fn ordenar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+// Sin funcion se ordena con `<`, las instancias usan su metodo `menor`
+fn ordenar(
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
+    call: &mut CallFn,
 ) -> ResultObj {
-    if args.len() != 0 {
-        return missmatch_args(0, args.len(), "ordenar".len(), target_line, target_col);
+    let ResultObj::Ref(ref_obj) = target else {
+        return missmatch_type("ordenar", &target.get_type(), target_line, target_col);
+    };
+    let items = match &*ref_obj.borrow() {
+        Object::List(list) => list.clone(),
+        Object::String(string) => {
+            let mut chars = string.chars().collect::<Vec<char>>();
+            chars.sort();
+            return ResultObj::Ref(new_rc_object(Object::String(
+                chars.iter().collect::<String>(),
+            )));
+        }
+        obj => return missmatch_type("ordenar", &obj.get_type(), target_line, target_col),
+    };
+    let sorted = merge_sort(items, &mut |a, b| {
+        compare_default(a, b, target_line, target_col, call)
+    });
+    let items = match sorted {
+        Ok(items) => items,
+        Err(err) => return err,
+    };
+    if let Object::List(ref mut list) = *ref_obj.borrow_mut() {
+        *list = items;
     }
+    ResultObj::Ref(ref_obj)
+}
 
-    match target {
-        ResultObj::Copy(obj) => missmatch_type("ordenar", &obj.get_type(), target_line, target_col),
-        ResultObj::Ref(mut ref_obj) => match *ref_obj.clone().borrow_mut() {
-            Object::List(ref mut list) => {
-                // if !args.is_empty() {
-                //     let ord_func_expr = args.remove(0);
-                //     let line = ord_func_expr.line;
-                //     let col = ord_func_expr.col;
-                //     let ord_func_obj = eval.eval_call(ord_func_expr, vec![], env);
-                //     if eval.is_error(&ord_func_obj) {
-                //         return ord_func_obj;
-                //     }
-                //     return ResultObj::Ref(ref_obj);
-                // }
-                match quick_sort(list) {
-                    Some(res) => res,
-                    None => ResultObj::Ref(ref_obj),
+// Funciones miembro que reciben una funcion (`CALLBACK_MEMBERS` y `ordenar`).
+// La lista se copia antes de llamar a la funcion, ya que esta puede leerla o
+// modificarla
+pub fn callback_member(
+    identifier: &str,
+    mut args: Vec<ResultObj>,
+    target: ResultObj,
+    target_line: usize,
+    target_col: usize,
+    callback_pos: (usize, usize),
+    call: &mut CallFn,
+) -> ResultObj {
+    let (min, max) = match identifier {
+        "reducir" => (1, 2),
+        "ordenar" => (0, 1),
+        _ => (1, 1),
+    };
+    if args.len() < min || args.len() > max {
+        return missmatch_args(max, args.len(), identifier.len(), target_line, target_col);
+    }
+    if args.is_empty() {
+        return ordenar(target, target_line, target_col, call);
+    }
+    let ResultObj::Ref(ref_obj) = target else {
+        return missmatch_type(identifier, &target.get_type(), target_line, target_col);
+    };
+    let items = match &*ref_obj.borrow() {
+        Object::List(list) => list.clone(),
+        obj => return missmatch_type(identifier, &obj.get_type(), target_line, target_col),
+    };
+    let callback = args.remove(0);
+    let mut invoke = |args: Vec<ResultObj>| match call(callback.clone(), args) {
+        err @ ResultObj::Copy(Object::Error(_)) => Err(err),
+        obj => Ok(obj),
+    };
+    let res = match identifier {
+        "ordenar" => sort_with(items, &callback, &mut invoke, callback_pos).map(|sorted| {
+            if let Object::List(ref mut list) = *ref_obj.borrow_mut() {
+                *list = sorted;
+            }
+            ResultObj::Ref(ref_obj.clone())
+        }),
+        _ => apply_callback(identifier, items, args.pop(), &mut invoke, callback_pos),
+    };
+    match res {
+        Ok(obj) | Err(obj) => obj,
+    }
+}
+
+fn apply_callback(
+    identifier: &str,
+    items: Vec<ResultObj>,
+    initial: Option<ResultObj>,
+    invoke: &mut impl FnMut(Vec<ResultObj>) -> Result<ResultObj, ResultObj>,
+    (line, col): (usize, usize),
+) -> Result<ResultObj, ResultObj> {
+    match identifier {
+        "mapear" => {
+            let mut list = Vec::with_capacity(items.len());
+            for item in items {
+                list.push(invoke(vec![item])?);
+            }
+            Ok(ResultObj::Ref(new_rc_object(Object::List(list))))
+        }
+        "filtrar" => {
+            let mut list = Vec::new();
+            for item in items {
+                if callback_truthy(identifier, invoke(vec![item.clone()])?, line, col)? {
+                    list.push(item);
                 }
             }
-            Object::String(ref mut string) => {
-                let mut chars = string.chars().collect::<Vec<char>>();
-                chars.sort();
-                ResultObj::Ref(new_rc_object(Object::String(
-                    chars.iter().collect::<String>(),
-                )))
+            Ok(ResultObj::Ref(new_rc_object(Object::List(list))))
+        }
+        "reducir" => {
+            let mut items = items.into_iter();
+            let Some(mut acc) = initial.or_else(|| items.next()) else {
+                return Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "No se puede reducir una lista vacia sin un valor inicial".to_owned(),
+                    line,
+                    col,
+                )))));
+            };
+            for item in items {
+                acc = invoke(vec![acc, item])?;
             }
-            ref obj => missmatch_type("ordenar", &obj.get_type(), target_line, target_col),
-        },
+            Ok(acc)
+        }
+        "cada" => {
+            for item in items {
+                invoke(vec![item])?;
+            }
+            Ok(ResultObj::Copy(Object::Void))
+        }
+        "alguno" | "todos" => {
+            // `alguno` se detiene en el primer verdadero y `todos` en el primer falso
+            let stop_on = identifier == "alguno";
+            for item in items {
+                if callback_truthy(identifier, invoke(vec![item])?, line, col)? == stop_on {
+                    return Ok(ResultObj::Copy(Object::Boolean(stop_on)));
+                }
+            }
+            Ok(ResultObj::Copy(Object::Boolean(!stop_on)))
+        }
+        _ => unreachable!("`{}` no recibe funciones", identifier),
+    }
+}
+
+// Con una funcion de un parametro se ordena por la llave que retorna, con
+// dos parametros se usa como comparador: negativo, cero o positivo
+fn sort_with(
+    items: Vec<ResultObj>,
+    callback: &ResultObj,
+    invoke: &mut impl FnMut(Vec<ResultObj>) -> Result<ResultObj, ResultObj>,
+    (line, col): (usize, usize),
+) -> Result<Vec<ResultObj>, ResultObj> {
+    let arity = match callback {
+        ResultObj::Copy(Object::FnExpr(func)) => func.params.len(),
+        ResultObj::Copy(Object::Fn(func)) => func.params.len(),
+        ResultObj::Copy(Object::CompiledFn(func)) => func.proto.params.len(),
+        _ => 1,
+    };
+    if arity == 2 {
+        return merge_sort(
+            items,
+            &mut |a, b| match invoke(vec![a.clone(), b.clone()])? {
+                ResultObj::Copy(Object::Numeric(numeric)) => Ok(numeric
+                    .partial_cmp(&Numeric::Int(0))
+                    .unwrap_or(Ordering::Equal)),
+                obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!(
+                        "La funcion de `ordenar` debe retornar un numero, no {}",
+                        obj.get_type()
+                    ),
+                    line,
+                    col,
+                ))))),
+            },
+        );
+    }
+    let mut keyed = Vec::with_capacity(items.len());
+    for item in items {
+        keyed.push((invoke(vec![item.clone()])?, item));
+    }
+    let keyed = merge_sort(keyed, &mut |(a, _), (b, _)| compare_objs(a, b, line, col))?;
+    Ok(keyed.into_iter().map(|(_, item)| item).collect())
+}
+
+fn callback_truthy(
+    identifier: &str,
+    obj: ResultObj,
+    line: usize,
+    col: usize,
+) -> Result<bool, ResultObj> {
+    match obj {
+        ResultObj::Copy(Object::Boolean(boolean)) => Ok(boolean),
+        ResultObj::Copy(Object::Numeric(numeric)) => Ok(!numeric.is_int_zero()),
+        ResultObj::Copy(Object::Null) => Ok(false),
+        obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "La funcion de `{}` debe retornar un valor logico, no {}",
+                identifier,
+                obj.get_type()
+            ),
+            line,
+            col,
+        ))))),
     }
 }
 
 pub fn concatenar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "concatenar".len(), target_line, target_col);
     }
-    let concat_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&concat_obj) {
-        return concat_obj;
-    }
+    let concat_obj = args.remove(0);
     match target {
         ResultObj::Copy(obj) => {
             missmatch_type("concatenar", &obj.get_type(), target_line, target_col)
@@ -533,21 +768,16 @@ pub fn concatenar(
 }
 
 pub fn eliminar_indice(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "eliminar".len(), target_line, target_col);
     }
-    let index_to_remove = eval.eval_expression(args.remove(0), env);
+    let index_to_remove = args.remove(0);
     let index;
-    if eval.is_error(&index_to_remove) {
-        return index_to_remove;
-    }
     match index_to_remove {
         ResultObj::Copy(Object::Numeric(Numeric::Int(i))) => {
             index = i;
@@ -574,21 +804,16 @@ pub fn eliminar_indice(
 }
 
 pub fn juntar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "separar".len(), target_line, target_col);
     }
     let join;
-    let join_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&join_obj) {
-        return join_obj;
-    }
+    let join_obj = args.remove(0);
     match join_obj {
         ResultObj::Ref(join_ref) => match *join_ref.borrow() {
             Object::String(ref string) => {
@@ -625,7 +850,7 @@ pub fn juntar(
 }
 
 pub fn llaves(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -636,16 +861,16 @@ pub fn llaves(
     match target {
         ResultObj::Copy(obj) => missmatch_type("llaves", &obj.get_type(), target_line, target_col),
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
-            Object::Dictionary(ref dict) => ResultObj::Ref(new_rc_object(Object::List(
-                dict.keys().clone().map(|obj| obj.clone()).collect(),
-            ))),
+            Object::Dictionary(ref dict) => {
+                ResultObj::Ref(new_rc_object(Object::List(dict.keys().cloned().collect())))
+            }
             ref obj => missmatch_type("llaves", &obj.get_type(), target_line, target_col),
         },
     }
 }
 
 pub fn valores(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -657,7 +882,7 @@ pub fn valores(
         ResultObj::Copy(obj) => missmatch_type("valores", &obj.get_type(), target_line, target_col),
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
             Object::Dictionary(ref dict) => ResultObj::Ref(new_rc_object(Object::List(
-                dict.values().map(|obj| obj.clone()).collect(),
+                dict.values().cloned().collect(),
             ))),
             ref obj => missmatch_type("valores", &obj.get_type(), target_line, target_col),
         },
@@ -665,21 +890,16 @@ pub fn valores(
 }
 
 pub fn separar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "separar".len(), target_line, target_col);
     }
     let split;
-    let split_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&split_obj) {
-        return split_obj;
-    }
+    let split_obj = args.remove(0);
     match split_obj {
         ResultObj::Ref(split_ref) => match *split_ref.borrow() {
             Object::String(ref string) => {
@@ -715,28 +935,23 @@ pub fn separar(
 }
 
 pub fn caracter(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "caracter".len(), target_line, target_col);
     }
-    let index_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&index_obj) {
-        return index_obj;
-    }
+    let index_obj = args.remove(0);
     let index;
     if let ResultObj::Copy(Object::Numeric(Numeric::Int(int))) = index_obj {
         if int < 0 {
-            return ResultObj::Copy(Object::Error(create_msg_err(
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 "El indice debe ser un numero positivo.".into(),
                 target_line,
                 target_col,
-            )));
+            ))));
         }
         index = int;
     } else {
@@ -763,7 +978,7 @@ pub fn caracter(
 }
 
 pub fn caracteres(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -788,7 +1003,7 @@ pub fn caracteres(
 }
 
 pub fn es_alfabetico(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -807,16 +1022,16 @@ pub fn es_alfabetico(
             missmatch_type("es_alfabetico", &obj.get_type(), target_line, target_col)
         }
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
-            Object::String(ref string) => ResultObj::Copy(Object::Boolean(
-                string.chars().fold(true, |acc, c| c.is_alphabetic() && acc),
-            )),
+            Object::String(ref string) => {
+                ResultObj::Copy(Object::Boolean(string.chars().all(|c| c.is_alphabetic())))
+            }
             ref obj => missmatch_type("es_alfabetico", &obj.get_type(), target_line, target_col),
         },
     }
 }
 
 pub fn es_numerico(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -829,16 +1044,16 @@ pub fn es_numerico(
             missmatch_type("es_numerico", &obj.get_type(), target_line, target_col)
         }
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
-            Object::String(ref string) => ResultObj::Copy(Object::Boolean(
-                string.chars().fold(true, |acc, c| c.is_numeric() && acc),
-            )),
+            Object::String(ref string) => {
+                ResultObj::Copy(Object::Boolean(string.chars().all(|c| c.is_numeric())))
+            }
             ref obj => missmatch_type("es_numerico", &obj.get_type(), target_line, target_col),
         },
     }
 }
 
 pub fn es_alfanumerico(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -857,31 +1072,24 @@ pub fn es_alfanumerico(
             missmatch_type("es_alfanumerico", &obj.get_type(), target_line, target_col)
         }
         ResultObj::Ref(ref_obj) => match *ref_obj.as_ref().borrow_mut() {
-            Object::String(ref string) => ResultObj::Copy(Object::Boolean(
-                string
-                    .chars()
-                    .fold(true, |acc, c| c.is_alphanumeric() && acc),
-            )),
+            Object::String(ref string) => {
+                ResultObj::Copy(Object::Boolean(string.chars().all(|c| c.is_alphanumeric())))
+            }
             ref obj => missmatch_type("es_alfanumerico", &obj.get_type(), target_line, target_col),
         },
     }
 }
 
 pub fn inicia_con(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "inicia_con".len(), target_line, target_col);
     }
-    let pattern_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&pattern_obj) {
-        return pattern_obj;
-    }
+    let pattern_obj = args.remove(0);
     let pattern: String;
     if let ResultObj::Ref(ref_pattern_obj) = pattern_obj {
         if let Object::String(ref string) = *ref_pattern_obj.borrow() {
@@ -911,20 +1119,15 @@ pub fn inicia_con(
 }
 
 pub fn termina_con(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 1 {
         return missmatch_args(1, args.len(), "termina_con".len(), target_line, target_col);
     }
-    let pattern_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&pattern_obj) {
-        return pattern_obj;
-    }
+    let pattern_obj = args.remove(0);
     let pattern: String;
     if let ResultObj::Ref(ref_pattern_obj) = pattern_obj {
         if let Object::String(ref string) = *ref_pattern_obj.borrow() {
@@ -954,7 +1157,7 @@ pub fn termina_con(
 }
 
 pub fn a_mayusculas(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -976,7 +1179,7 @@ pub fn a_mayusculas(
 }
 
 pub fn a_minusculas(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -998,24 +1201,16 @@ pub fn a_minusculas(
 }
 
 pub fn reemplazar(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 2 {
         return missmatch_args(2, args.len(), "reemplazar".len(), target_line, target_col);
     }
-    let pattern_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&pattern_obj) {
-        return pattern_obj;
-    }
-    let new_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&pattern_obj) {
-        return pattern_obj;
-    }
+    let pattern_obj = args.remove(0);
+    let new_obj = args.remove(0);
 
     let pattern: String;
     let new: String;
@@ -1063,7 +1258,7 @@ pub fn reemplazar(
 }
 
 pub fn recortar(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -1086,24 +1281,16 @@ pub fn recortar(
 }
 
 pub fn subcadena(
-    eval: &mut Evaluator,
-    mut args: FnParams,
+    mut args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
-    env: &RcEnvironment,
 ) -> ResultObj {
     if args.len() != 2 {
         return missmatch_args(2, args.len(), "subcadena".len(), target_line, target_col);
     }
-    let pos_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&pos_obj) {
-        return pos_obj;
-    }
-    let len_obj = eval.eval_expression(args.remove(0), env);
-    if eval.is_error(&len_obj) {
-        return len_obj;
-    }
+    let pos_obj = args.remove(0);
+    let len_obj = args.remove(0);
 
     let pos;
     let len;
@@ -1114,11 +1301,11 @@ pub fn subcadena(
             ResultObj::Copy(Object::Numeric(Numeric::Int(l))),
         ) => {
             if p < 0 || l < 0 {
-                return ResultObj::Copy(Object::Error(create_msg_err(
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                     "El indice debe ser un numero positivo.".into(),
                     target_line,
                     target_col,
-                )));
+                ))));
             }
             pos = p;
             len = l;
@@ -1138,18 +1325,18 @@ pub fn subcadena(
         ResultObj::Ref(ref ref_obj) => match *ref_obj.as_ref().borrow_mut() {
             Object::String(ref string) => {
                 if (pos as usize) > string.len() {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         "El indice esta fuera del rango.".into(),
                         target_line,
                         target_col,
-                    )));
+                    ))));
                 }
                 if len > (string.len() as i64) - pos {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         "El indice esta fuera del rango.".into(),
                         target_line,
                         target_col,
-                    )));
+                    ))));
                 }
 
                 let sub_str: String = string
@@ -1165,7 +1352,7 @@ pub fn subcadena(
 }
 
 pub fn a_numerico(
-    args: FnParams,
+    args: Vec<ResultObj>,
     target: ResultObj,
     target_line: usize,
     target_col: usize,
@@ -1184,14 +1371,14 @@ pub fn a_numerico(
                 if let TokenType::Numeric(num) = token.r#type {
                     return ResultObj::Copy(Object::Numeric(num));
                 } else if let TokenType::Illegal(c) = token.r#type {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         format!(
                             "Se encontro un simbolo ilegal `{}` durante la conversion",
                             c
                         ),
                         target_line,
                         target_col,
-                    )));
+                    ))));
                 }
                 missmatch_type(
                     "a_numerico",
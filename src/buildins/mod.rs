@@ -1,2 +1,140 @@
+pub mod archivo;
 pub mod internal;
+pub mod mate;
 pub mod member;
+pub mod sis;
+
+use std::rc::Rc;
+
+use crate::{
+    eval::{
+        evaluator::{create_err, create_msg_err},
+        objects::{BuildinFnObj, BuildinParam, Object, ResultObj},
+    },
+    types::Numeric,
+};
+
+use internal::InternalFnPointer;
+
+// Modulos nativos que se pueden importar por su nombre: importar "mate";
+pub fn native_module(name: &str, script_args: &[String]) -> Option<Vec<(String, ResultObj)>> {
+    match name {
+        "mate" => Some(mate::module()),
+        "archivo" => Some(archivo::module()),
+        "sis" => Some(sis::module(script_args)),
+        _ => None,
+    }
+}
+
+// Convierte las funciones de un modulo nativo en sus miembros
+pub fn native_fns<const N: usize>(
+    functions: [(&str, &[BuildinParam], Box<dyn InternalFnPointer>); N],
+) -> Vec<(String, ResultObj)> {
+    functions
+        .into_iter()
+        .map(|(name, params, func)| {
+            (
+                name.to_owned(),
+                ResultObj::Copy(Object::BuildinFn(Box::new(BuildinFnObj {
+                    name: name.to_owned(),
+                    params: Rc::from(params),
+                    func,
+                }))),
+            )
+        })
+        .collect()
+}
+
+// Numero evaluado junto a la posicion del argumento, para los errores
+pub struct NumericArg {
+    pub value: Numeric,
+    pub line: usize,
+    pub col: usize,
+}
+
+pub fn numeric_args(
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+    name: &str,
+    expected: usize,
+) -> Result<Vec<NumericArg>, ResultObj> {
+    if args.len() != expected {
+        return Err(ResultObj::Copy(Object::Error(Box::new(create_err(
+            format!(
+                "`{}` espera {} argumentos, se encontro {}",
+                name,
+                expected,
+                args.len()
+            ),
+        )))));
+    }
+    let mut numbers = Vec::with_capacity(args.len());
+    for (obj, (line, col)) in args.into_iter().zip(positions.iter().copied()) {
+        match obj {
+            ResultObj::Copy(Object::Numeric(value)) => {
+                numbers.push(NumericArg { value, line, col })
+            }
+            obj => return Err(missmatch_numeric(name, &obj.get_type(), line, col)),
+        }
+    }
+    Ok(numbers)
+}
+
+fn missmatch_numeric(name: &str, obj_type: &str, line: usize, col: usize) -> ResultObj {
+    ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+        format!(
+            "`{}` espera un tipo de dato numerico, no {}",
+            name, obj_type
+        ),
+        line,
+        col,
+    ))))
+}
+
+// Cadena evaluada junto a la posicion del argumento, para los errores
+pub struct StringArg {
+    pub value: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+pub fn string_args(
+    args: Vec<ResultObj>,
+    positions: &[(usize, usize)],
+    name: &str,
+    expected: usize,
+) -> Result<Vec<StringArg>, ResultObj> {
+    if args.len() != expected {
+        return Err(ResultObj::Copy(Object::Error(Box::new(create_err(
+            format!(
+                "`{}` espera {} argumentos, se encontro {}",
+                name,
+                expected,
+                args.len()
+            ),
+        )))));
+    }
+    let mut strings = Vec::with_capacity(args.len());
+    for (obj, (line, col)) in args.into_iter().zip(positions.iter().copied()) {
+        if let ResultObj::Ref(ref obj) = obj {
+            if let Object::String(value) = &*obj.borrow() {
+                strings.push(StringArg {
+                    value: value.clone(),
+                    line,
+                    col,
+                });
+                continue;
+            }
+        }
+        return Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "`{}` espera un tipo de dato cadena, no {}",
+                name,
+                obj.get_type()
+            ),
+            line,
+            col,
+        )))));
+    }
+    Ok(strings)
+}
@@ -0,0 +1,135 @@
+use std::{
+    sync::OnceLock,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    eval::{
+        evaluator::{create_msg_err, exit_signal},
+        objects::{new_rc_object, BuildinParam, Object, ResultObj},
+    },
+    types::Numeric,
+};
+
+use super::{
+    internal::{Caller, InternalFnPointer},
+    native_fns, numeric_args, string_args,
+};
+
+// Momento en que se importo `sis` por primera vez, referencia de `reloj`
+static START: OnceLock<Instant> = OnceLock::new();
+
+// Miembros del modulo nativo `sis`
+pub fn module(script_args: &[String]) -> Vec<(String, ResultObj)> {
+    START.get_or_init(Instant::now);
+    let functions: [(&str, &[BuildinParam], Box<dyn InternalFnPointer>); 5] = [
+        ("entorno", &[BuildinParam::new("nombre")], Box::new(entorno)),
+        (
+            "salir",
+            &[BuildinParam::optional(
+                "codigo",
+                Object::Numeric(Numeric::Int(0)),
+            )],
+            Box::new(salir),
+        ),
+        ("tiempo", &[], Box::new(tiempo)),
+        ("reloj", &[], Box::new(reloj)),
+        (
+            "dormir",
+            &[BuildinParam::new("milisegundos")],
+            Box::new(dormir),
+        ),
+    ];
+
+    let mut members = native_fns(functions);
+    members.push((
+        "argumentos".to_owned(),
+        ResultObj::Ref(new_rc_object(Object::List(
+            script_args
+                .iter()
+                .map(|arg| ResultObj::Ref(new_rc_object(Object::String(arg.clone()))))
+                .collect(),
+        ))),
+    ));
+    members
+}
+
+// Retorna el valor de la variable de entorno o nulo si no existe
+pub fn entorno(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match string_args(args, positions, "entorno", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    match std::env::var(&args[0].value) {
+        Ok(value) => ResultObj::Ref(new_rc_object(Object::String(value))),
+        Err(_) => ResultObj::Copy(Object::Null),
+    }
+}
+
+// Termina el programa con el codigo dado, por defecto 0. Antes corren los
+// bloques `finalmente` pendientes
+pub fn salir(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "salir", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    let code = match args[0].value {
+        Numeric::Int(code) if i32::try_from(code).is_ok() => code as i32,
+        ref code => {
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "`salir` espera un codigo entero de 32 bits, se encontro {}",
+                    code
+                ),
+                args[0].line,
+                args[0].col,
+            ))))
+        }
+    };
+    ResultObj::Copy(Object::Error(Box::new(exit_signal(code))))
+}
+
+// Segundos desde la epoca Unix
+pub fn tiempo(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    if let Err(err) = numeric_args(args, positions, "tiempo", 0) {
+        return err;
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs_f64())
+        .unwrap_or_default();
+    ResultObj::Copy(Object::Numeric(Numeric::Float(secs)))
+}
+
+// Milisegundos desde que se importo `sis`, util para medir tiempos
+pub fn reloj(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    if let Err(err) = numeric_args(args, positions, "reloj", 0) {
+        return err;
+    }
+    let start = START.get_or_init(Instant::now);
+    ResultObj::Copy(Object::Numeric(Numeric::Float(
+        start.elapsed().as_secs_f64() * 1000.0,
+    )))
+}
+
+// Pausa el programa los milisegundos dados
+pub fn dormir(_: &mut Caller, args: Vec<ResultObj>, positions: &[(usize, usize)]) -> ResultObj {
+    let args = match numeric_args(args, positions, "dormir", 1) {
+        Ok(args) => args,
+        Err(err) => return err,
+    };
+    match args[0].value {
+        Numeric::Int(ms) if ms >= 0 => {
+            std::thread::sleep(Duration::from_millis(ms as u64));
+            ResultObj::Copy(Object::Void)
+        }
+        ref ms => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "`dormir` espera un entero positivo de milisegundos, se encontro {}",
+                ms
+            ),
+            args[0].line,
+            args[0].col,
+        )))),
+    }
+}
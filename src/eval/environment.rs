@@ -2,82 +2,210 @@ use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use super::objects::{RcObject, ResultObj};
 
-pub type StackObject = HashMap<String, ResultObj>;
 pub type RcEnvironment = Rc<RefCell<Environment>>;
 
 pub struct Environment {
-    stack: StackObject,
+    // Variables locales en orden de declaracion, las busca el evaluador con
+    // la posicion que calculo el resolver
+    slots: Vec<ResultObj>,
+    // Solo en el environment raiz: posicion de cada variable global en
+    // `globals`. El resolver reserva la posicion antes de que se declare, por
+    // eso puede estar vacia
+    global_names: HashMap<String, usize>,
+    globals: Vec<Option<ResultObj>>,
     parent: Option<RcEnvironment>,
+    // Archivo del modulo, solo lo tiene el environment raiz de un modulo o del
+    // programa principal
+    module: Option<String>,
 }
 
 impl Environment {
     pub fn new(parent: Option<RcEnvironment>) -> Self {
         Self {
-            stack: StackObject::new(),
+            slots: Vec::new(),
+            global_names: HashMap::new(),
+            globals: Vec::new(),
             parent,
+            module: None,
         }
     }
 
+    pub fn new_module(name: String) -> Self {
+        Self {
+            slots: Vec::new(),
+            global_names: HashMap::new(),
+            globals: Vec::new(),
+            parent: None,
+            module: Some(name),
+        }
+    }
+
+    pub fn set_module(&mut self, name: String) {
+        self.module = Some(name);
+    }
+
+    pub fn parent(&self) -> Option<RcEnvironment> {
+        self.parent.clone()
+    }
+
+    // Retorna el archivo del modulo al que pertenece este environment
+    pub fn module_name(&self) -> Option<String> {
+        match self.parent {
+            Some(ref env) => env.borrow().module_name(),
+            None => self.module.clone(),
+        }
+    }
+
+    // Variables propias de este environment, sin las de sus padres
+    pub fn vars(&self) -> Vec<(String, ResultObj)> {
+        let mut vars: Vec<_> = self
+            .global_names
+            .iter()
+            .filter_map(|(name, slot)| {
+                let value = self.globals[*slot].clone()?;
+                Some((name.clone(), value))
+            })
+            .collect();
+        vars.sort_by(|a, b| a.0.cmp(&b.0));
+        vars
+    }
+
     pub fn get(&self, name: &String) -> Option<ResultObj> {
-        match self.stack.get(name) {
-            Some(obj) => Some(obj.clone()),
-            None => match self.parent {
-                Some(ref env) => {
-                    let env = env.borrow();
-                    env.get(name)
-                }
-                None => None,
-            },
+        match self.parent {
+            Some(ref env) => {
+                let env = env.borrow();
+                env.get(name)
+            }
+            None => self.global(name).cloned(),
         }
     }
 
     pub fn get_ref(&self, name: &String) -> Option<RcObject> {
-        match self.stack.get(name) {
-            Some(obj) => Some(match obj {
+        match self.parent {
+            Some(ref env) => {
+                let env = env.borrow();
+                env.get_ref(name)
+            }
+            None => match self.global(name)? {
                 ResultObj::Copy(_) => panic!("No se puede referenciar a un objeto copia"),
-                ResultObj::Ref(xd) => xd.clone(),
-            }),
-            None => match self.parent {
-                Some(ref env) => {
-                    let env = env.borrow();
-                    env.get_ref(name)
-                }
-                None => None,
+                ResultObj::Ref(obj) => Some(obj.clone()),
             },
         }
     }
 
     pub fn exist(&self, name: &String) -> bool {
-        match self.stack.get(name) {
-            Some(_) => true,
-            None => match self.parent {
-                Some(ref env) => {
-                    let env = env.borrow();
-                    env.exist(name)
+        match self.parent {
+            Some(ref env) => {
+                let env = env.borrow();
+                env.exist(name)
+            }
+            None => self.global(name).is_some(),
+        }
+    }
+
+    // Declara una variable. El environment raiz (global o de un modulo) la
+    // guarda por nombre y los demas en el siguiente slot
+    pub fn declare(&mut self, name: &str, value: ResultObj) {
+        match self.parent {
+            Some(_) => self.slots.push(value),
+            None => {
+                self.set(name.to_owned(), value);
+            }
+        }
+    }
+
+    // Variable global ya declarada
+    fn global(&self, name: &str) -> Option<&ResultObj> {
+        let slot = *self.global_names.get(name)?;
+        self.globals[slot].as_ref()
+    }
+
+    // Posicion de una variable global, la reserva si aun no existe. El
+    // resolver la usa en el environment raiz antes de ejecutar el programa
+    pub fn reserve_global(&mut self, name: &str) -> usize {
+        if let Some(slot) = self.global_names.get(name) {
+            return *slot;
+        }
+        let slot = self.globals.len();
+        self.globals.push(None);
+        self.global_names.insert(name.to_owned(), slot);
+        slot
+    }
+
+    pub fn global_slot(&self, name: &str) -> Option<usize> {
+        self.global_names.get(name).copied()
+    }
+
+    // Busca la variable global en el environment raiz, None si aun no fue
+    // declarada
+    pub fn get_global(&self, slot: usize) -> Option<ResultObj> {
+        match self.parent {
+            Some(ref env) => env.borrow().get_global(slot),
+            None => self.globals.get(slot)?.clone(),
+        }
+    }
+
+    // Retorna false si la variable aun no fue declarada
+    pub fn set_global(&mut self, slot: usize, value: ResultObj) -> bool {
+        match self.parent {
+            Some(ref env) => env.borrow_mut().set_global(slot, value),
+            None => match self.globals.get_mut(slot) {
+                Some(Some(obj)) => {
+                    *obj = value;
+                    true
                 }
-                None => false,
+                _ => false,
             },
         }
     }
 
-    // Esta funcion sirve para guardar una variable o fn en el
-    // stack de environment del contexto
+    pub fn get_slot(&self, depth: usize, slot: usize) -> Option<ResultObj> {
+        if depth == 0 {
+            return self.slots.get(slot).cloned();
+        }
+        self.parent.as_ref()?.borrow().get_slot(depth - 1, slot)
+    }
+
+    // Retorna false si la variable aun no fue declarada
+    pub fn set_slot(&mut self, depth: usize, slot: usize, value: ResultObj) -> bool {
+        if depth == 0 {
+            return match self.slots.get_mut(slot) {
+                Some(obj) => {
+                    *obj = value;
+                    true
+                }
+                None => false,
+            };
+        }
+        match self.parent {
+            Some(ref env) => env.borrow_mut().set_slot(depth - 1, slot, value),
+            None => false,
+        }
+    }
+
+    // Guarda una variable o fn por nombre en el environment raiz, los demas
+    // environments solo tienen slots
     pub fn set(&mut self, name: String, value: ResultObj) -> Option<ResultObj> {
-        self.stack.insert(name.clone(), value)
+        if let Some(ref env) = self.parent {
+            return env.borrow_mut().set(name, value);
+        }
+        let slot = self.reserve_global(&name);
+        self.globals[slot].replace(value)
     }
 
     // Va a visitar todos los stacks hasta encontrar la variable o fn
     // y actulizarlo
     pub fn update(&mut self, name: &String, value: ResultObj) -> Option<ResultObj> {
-        match self.stack.get(name) {
-            Some(_) => self.stack.insert(name.clone(), value),
-            None => match self.parent {
-                Some(ref env) => {
-                    let mut env = env.borrow_mut();
-                    env.update(name, value)
-                }
-                None => None,
-            },
+        match self.parent {
+            Some(ref env) => {
+                let mut env = env.borrow_mut();
+                env.update(name, value)
+            }
+            None => {
+                let slot = *self.global_names.get(name)?;
+                let obj = self.globals[slot].as_mut()?;
+                Some(std::mem::replace(obj, value))
+            }
         }
         // match self.stack.insert(name.clone(), RcObject::new(RefCell::new(value))) {
         //     Some(obj) => Some(obj),
@@ -1,20 +1,36 @@
 use std::{
     cell::RefCell,
     collections::{HashMap, VecDeque},
+    fs,
+    path::{Path, PathBuf},
     rc::Rc,
 };
 
+use indexmap::IndexMap;
+
 use crate::buildins::{
-    internal::{cadena, imprimir, leer, longitud, tipo, InternalFnPointer},
-    member::match_member_fn,
+    internal::{get_buildin, Caller},
+    member::{match_member_fn, member_lock_err, CallFn},
+    native_module,
+};
+use crate::lexer::Lexer;
+use crate::parser::expression::{
+    format_params, ExprType, Expression, FnParams, MatchCase, Param, Pattern,
 };
-use crate::parser::expression::{ExprType, Expression, FnParams};
 use crate::parser::statement::{BlockStatement, Statement};
+use crate::parser::Parser;
+use crate::vm::bytecode::CallSite;
 use crate::{token::TokenType, types::Numeric};
 
 use super::{
     environment::{Environment, RcEnvironment},
-    objects::{new_rc_object, BuildinFnObj, FnExprObj, FnObj, Object, ResultObj},
+    iterator::new_iterator,
+    objects::{
+        format_buildin_params, new_rc_object, BuildinFnObj, BuildinParam, ClassObj, ErrorObj,
+        FnExprObj, FnObj, InstanceObj, ModuleObj, Object, RcObject, ResultObj, TailCallObj,
+        ERR_EJECUCION, ERR_MODULO, ERR_SINTAXIS, ERR_USUARIO,
+    },
+    resolver::resolve,
 };
 
 #[allow(dead_code)]
@@ -24,14 +40,40 @@ pub enum Context {
     If,
     Fn,
     Loop,
+    Try,
+}
+
+// Modulo ya evaluado o el programa de un archivo que falta evaluar
+pub enum ModuleSource {
+    Loaded(ResultObj),
+    Pending(PendingModule, BlockStatement),
+}
+
+pub struct PendingModule {
+    pub name: String,
+    pub full_path: PathBuf,
+    pub env: RcEnvironment,
 }
 
 pub struct Evaluator {
     environment: RcEnvironment,
-    buildins_internal_fn: HashMap<String, Box<dyn InternalFnPointer>>,
     stack_ctx: VecDeque<Context>,
+    // Colecciones que estan siendo recorridas por un bucle `para ... en`
+    iter_locks: Vec<RcObject>,
+    // Modulos ya evaluados, por su ruta absoluta
+    modules: HashMap<PathBuf, ResultObj>,
+    // Archivos que se estan evaluando, el ultimo es el actual
+    file_stack: Vec<PathBuf>,
+    // Argumentos extra de la linea de comandos, para `sis.argumentos`
+    script_args: Vec<String>,
+    // Llamadas a funciones de Pana en curso y el maximo permitido
+    depth: usize,
+    max_depth: usize,
 }
 
+// Maximo de llamadas anidadas por defecto
+pub const MAX_DEPTH: usize = 2000;
+
 impl Default for Evaluator {
     fn default() -> Self {
         Self::new()
@@ -42,103 +84,132 @@ impl Evaluator {
     pub fn new() -> Self {
         Self {
             environment: Rc::new(RefCell::new(Environment::new(None))),
-            buildins_internal_fn: HashMap::from([
-                (
-                    "longitud".to_owned(),
-                    Box::new(longitud) as Box<dyn InternalFnPointer>,
-                ),
-                (
-                    "tipo".to_owned(),
-                    Box::new(tipo) as Box<dyn InternalFnPointer>,
-                ),
-                (
-                    "imprimir".to_owned(),
-                    Box::new(imprimir) as Box<dyn InternalFnPointer>,
-                ),
-                (
-                    "leer".to_owned(),
-                    Box::new(leer) as Box<dyn InternalFnPointer>,
-                ),
-                (
-                    "cadena".to_owned(),
-                    Box::new(cadena) as Box<dyn InternalFnPointer>,
-                ),
-            ]),
             stack_ctx: VecDeque::new(),
+            iter_locks: Vec::new(),
+            modules: HashMap::new(),
+            file_stack: Vec::new(),
+            script_args: Vec::new(),
+            depth: 0,
+            max_depth: MAX_DEPTH,
         }
     }
 
-    pub fn eval_program(&mut self, statements: BlockStatement) -> ResultObj {
+    pub fn set_script_args(&mut self, args: Vec<String>) {
+        self.script_args = args;
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    // Archivo principal, los modulos se buscan relativos a el
+    pub fn set_main_file(&mut self, path: &Path) {
+        // Los errores del programa principal indican el archivo como se escribio
+        self.environment
+            .borrow_mut()
+            .set_module(path.display().to_string());
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.file_stack = vec![path];
+    }
+
+    // Variables globales ordenadas por nombre
+    pub fn global_vars(&self) -> Vec<(String, ResultObj)> {
+        self.environment.borrow().vars()
+    }
+
+    pub fn eval_program(&mut self, mut statements: BlockStatement) -> ResultObj {
+        if let Err(err) = resolve(&mut statements, &self.environment) {
+            return ResultObj::Copy(Object::Error(Box::new(err)));
+        }
+        // Cada programa empieza en el contexto global, aunque el anterior
+        // haya terminado con un error (ej. en el REPL)
+        self.stack_ctx.clear();
+        self.iter_locks.clear();
+        self.depth = 0;
         self.stack_ctx.push_back(Context::Global);
-        self.eval_block_statement(statements, &self.environment.clone())
+        let res_obj = self.eval_block_statement(statements, &self.environment.clone());
+        with_main_file(res_obj, &self.environment)
     }
 
-    // cambiarlo por un deqvec
-    fn eval_block_statement(
-        &mut self,
-        mut program: BlockStatement,
-        env: &RcEnvironment,
-    ) -> ResultObj {
-        // from https://github.com/Rydgel/monkey-rust/blob/master/lib/evaluator/mod.rs#L332
-        match program.len() {
-            // Optimizar con referencias
-            0 => ResultObj::Copy(Object::Void),
-            1 => self.eval_statement(program.remove(0), env),
-            _ => {
-                let res_obj = self.eval_statement(program.remove(0), env);
-                match res_obj {
-                    ResultObj::Copy(Object::Return(returned_obj)) => *returned_obj,
-                    ResultObj::Copy(Object::Error(msg)) => ResultObj::Copy(Object::Error(msg)),
-                    ResultObj::Copy(Object::Break) => res_obj,
-                    ResultObj::Copy(Object::Continue) => ResultObj::Copy(Object::Void),
-                    _ => self.eval_block_statement(program, env),
-                }
+    fn eval_block_statement(&mut self, program: BlockStatement, env: &RcEnvironment) -> ResultObj {
+        let mut res_obj = ResultObj::Copy(Object::Void);
+        for stmt in program {
+            res_obj = self.eval_statement(stmt, env);
+            // Los errores y las señales de control detienen el bloque y suben
+            // hasta la construccion que las maneja
+            if let ResultObj::Copy(
+                Object::Return(_) | Object::Error(_) | Object::Break | Object::Continue,
+            ) = res_obj
+            {
+                return res_obj;
+            }
+        }
+        res_obj
+    }
+
+    // Busca hacia atras el contexto mas cercano que cumpla `target`,
+    // sin cruzar el limite de una funcion
+    fn in_context(&self, target: Context) -> bool {
+        for ctx in self.stack_ctx.iter().rev() {
+            if *ctx == target {
+                return true;
+            }
+            if *ctx == Context::Fn {
+                return false;
             }
         }
+        false
     }
 
     fn eval_statement(&mut self, stmt: Statement, env: &RcEnvironment) -> ResultObj {
         match stmt {
             Statement::Var { name, value } => self.eval_var(&name, value, env),
             Statement::Return(expr, line, col) => {
-                while let Some(ctx) = self.stack_ctx.pop_back() {
-                    if let Context::Fn = ctx {
-                        return ResultObj::Copy(Object::Return(Box::new(
-                            self.eval_expression(expr, env),
-                        )));
+                if !self.in_context(Context::Fn) {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        "Solo se puede retornar dentro de funciones".into(),
+                        line,
+                        col,
+                    ))));
+                }
+                // Dentro de `intentar` la llamada debe terminar antes de salir del bloque
+                let obj = match expr.r#type {
+                    ExprType::Call {
+                        function,
+                        arguments,
+                    } if !self.in_context(Context::Try) => {
+                        self.eval_tail_call(*function, arguments, env)
                     }
+                    _ => self.eval_expression(expr, env),
+                };
+                if self.is_error(&obj) {
+                    return obj;
                 }
-                ResultObj::Copy(Object::Error(create_msg_err(
-                    "Solo se puede retornar dentro de funciones".into(),
-                    line,
-                    col,
-                )))
+                ResultObj::Copy(Object::Return(Box::new(obj)))
             }
             Statement::Continue(line, col) => {
-                while let Some(ctx) = self.stack_ctx.back() {
-                    if let Context::Loop = ctx {
-                        return ResultObj::Copy(Object::Continue);
-                    }
-                    self.stack_ctx.pop_back();
+                if !self.in_context(Context::Loop) {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        "Solo se puede continuar en bucles".into(),
+                        line,
+                        col,
+                    ))));
                 }
-                ResultObj::Copy(Object::Error(create_msg_err(
-                    "Solo se puede continuar en bucles".into(),
-                    line,
-                    col,
-                )))
+                ResultObj::Copy(Object::Continue)
             }
             Statement::Break(line, col) => {
-                while let Some(ctx) = self.stack_ctx.pop_back() {
-                    match ctx {
-                        Context::Loop => return ResultObj::Copy(Object::Break),
-                        _ => continue,
-                    }
+                if !self.in_context(Context::Loop) {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        "Solo se puede romper condicionales y bucles".into(),
+                        line,
+                        col,
+                    ))));
                 }
-                ResultObj::Copy(Object::Error(create_msg_err(
-                    "Solo se puede romper condicionales y bucles".into(),
-                    line,
-                    col,
-                )))
+                ResultObj::Copy(Object::Break)
             }
             Statement::Expression(expr) => self.eval_expression(expr, env),
             Statement::Fn {
@@ -159,7 +230,333 @@ impl Evaluator {
                     None => self.insert_obj(&name, obj, env),
                 }
             }
+            Statement::Try {
+                body,
+                catch_ident,
+                catch_body,
+                finally_body,
+                ..
+            } => self.eval_try(body, catch_ident, catch_body, finally_body, env),
+            Statement::Throw(expr, line, col) => self.eval_throw(expr, line, col, env),
+            Statement::Import {
+                path,
+                alias,
+                line,
+                col,
+            } => self.eval_import(path, alias, line, col, env),
+            Statement::FromImport {
+                path,
+                names,
+                line,
+                col,
+            } => self.eval_from_import(path, names, line, col, env),
+            Statement::Class {
+                name,
+                parent,
+                methods,
+                line,
+                col,
+            } => {
+                if let Some(obj) = self.get_var_value(&name, env, line, col) {
+                    return obj;
+                }
+                let parent = match parent {
+                    Some(expr) => {
+                        let (parent_line, parent_col) = (expr.line, expr.col);
+                        let obj = self.eval_expression(expr, env);
+                        if self.is_error(&obj) {
+                            return obj;
+                        }
+                        match parent_class(obj, parent_line, parent_col) {
+                            Ok(class) => Some(class),
+                            Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+                        }
+                    }
+                    None => None,
+                };
+                let methods = methods
+                    .into_iter()
+                    .map(|method| {
+                        let obj = ResultObj::Copy(Object::Fn(Box::new(FnObj {
+                            name: method.name.clone(),
+                            params: method.params,
+                            body: method.body,
+                            env: env.clone(),
+                        })));
+                        (method.name, obj)
+                    })
+                    .collect();
+                let class = ClassObj {
+                    name: name.clone(),
+                    parent,
+                    methods,
+                };
+                self.insert_obj(&name, ResultObj::Copy(Object::Class(Rc::new(class))), env)
+            }
+        }
+    }
+
+    fn eval_try(
+        &mut self,
+        body: BlockStatement,
+        catch_ident: Option<String>,
+        catch_body: Option<BlockStatement>,
+        finally_body: Option<BlockStatement>,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let outer_ctx_len = self.stack_ctx.len();
+        self.stack_ctx.push_back(Context::Try);
+        let ctx_len = self.stack_ctx.len();
+        let locks_len = self.iter_locks.len();
+        let files_len = self.file_stack.len();
+
+        let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+        let res_obj = self.eval_block_statement(body, &scope_env);
+
+        let res_obj = match (catch_body, res_obj) {
+            // `sis.salir` no se captura
+            (Some(catch_body), ResultObj::Copy(Object::Error(err))) if err.salida.is_none() => {
+                // Descartar el estado que dejo el codigo que fallo
+                self.stack_ctx.truncate(ctx_len);
+                self.iter_locks.truncate(locks_len);
+                self.file_stack.truncate(files_len);
+
+                let catch_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+                if let Some(ident) = catch_ident {
+                    self.insert_obj(
+                        &ident,
+                        ResultObj::Copy(Object::CaughtError(err)),
+                        &catch_env,
+                    );
+                }
+                self.eval_block_statement(catch_body, &catch_env)
+            }
+            (_, res_obj) => res_obj,
+        };
+
+        if let Some(finally_body) = finally_body {
+            self.stack_ctx.truncate(ctx_len);
+            self.iter_locks.truncate(locks_len);
+            self.file_stack.truncate(files_len);
+
+            let finally_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+            let finally_obj = self.eval_block_statement(finally_body, &finally_env);
+            // Un error o salto dentro de `finalmente` reemplaza al resultado anterior
+            if let ResultObj::Copy(
+                Object::Return(_) | Object::Error(_) | Object::Break | Object::Continue,
+            ) = finally_obj
+            {
+                self.stack_ctx.truncate(outer_ctx_len);
+                return finally_obj;
+            }
+        }
+        self.stack_ctx.truncate(outer_ctx_len);
+        res_obj
+    }
+
+    fn eval_throw(
+        &mut self,
+        expr: Expression,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let obj = self.eval_expression(expr, env);
+        if self.is_error(&obj) {
+            return obj;
+        }
+        throw_obj(obj, line, col)
+    }
+
+    fn eval_import(
+        &mut self,
+        path: String,
+        alias: Option<String>,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let name = match import_name(&path, alias, line, col) {
+            Ok(name) => name,
+            Err(err) => return err,
+        };
+
+        let module = self.load_module(&path, line, col);
+        if self.is_error(&module) {
+            return module;
+        }
+        if let Some(err) = self.get_var_value(&name, env, line, col) {
+            return err;
+        }
+        self.insert_obj(&name, module, env);
+        ResultObj::Copy(Object::Void)
+    }
+
+    fn eval_from_import(
+        &mut self,
+        path: String,
+        names: Vec<String>,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let module_env = match self.load_module(&path, line, col) {
+            ResultObj::Copy(Object::Module(module)) => module.env,
+            err => return err,
+        };
+        for name in names {
+            let obj = match module_env.borrow().get(&name) {
+                Some(obj) => obj,
+                None => {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        format!("El modulo \"{}\" no tiene el miembro `{}`", path, name),
+                        line,
+                        col,
+                    ))))
+                }
+            };
+            if let Some(err) = self.get_var_value(&name, env, line, col) {
+                return err;
+            }
+            self.insert_obj(&name, obj, env);
+        }
+        ResultObj::Copy(Object::Void)
+    }
+
+    // Busca el modulo relativo al archivo actual y lo evalua una sola vez
+    // en su propio environment
+    fn load_module(&mut self, path: &str, line: usize, col: usize) -> ResultObj {
+        let (pending, program) = match self.begin_module(path, line, col) {
+            Ok(ModuleSource::Loaded(module)) => return module,
+            Ok(ModuleSource::Pending(pending, program)) => (pending, program),
+            Err(err) => return err,
+        };
+        // El modulo se evalua con su propio stack de contextos, como un programa aparte
+        let outer_ctx = std::mem::replace(&mut self.stack_ctx, VecDeque::from([Context::Global]));
+        let res_obj = self.eval_block_statement(program, &pending.env.clone());
+        self.stack_ctx = outer_ctx;
+        self.end_module(pending, res_obj)
+    }
+
+    // Resuelve la ruta de un modulo. Si aun no fue evaluado retorna su programa
+    // y lo marca como el archivo actual hasta que se llame `end_module`
+    pub fn begin_module(
+        &mut self,
+        path: &str,
+        line: usize,
+        col: usize,
+    ) -> Result<ModuleSource, ResultObj> {
+        if let Some(members) = native_module(path, &self.script_args) {
+            let native_path = PathBuf::from(path);
+            if let Some(module) = self.modules.get(&native_path) {
+                return Ok(ModuleSource::Loaded(module.clone()));
+            }
+            let module_env = Rc::new(RefCell::new(Environment::new_module(path.to_owned())));
+            for (name, obj) in members {
+                module_env.borrow_mut().set(name, obj);
+            }
+            let module = ResultObj::Copy(Object::Module(Box::new(ModuleObj {
+                name: path.to_owned(),
+                env: module_env,
+            })));
+            self.modules.insert(native_path, module.clone());
+            return Ok(ModuleSource::Loaded(module));
+        }
+
+        let full_path = match self.file_stack.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        let full_path = match full_path.canonicalize() {
+            Ok(full_path) => full_path,
+            Err(_) => {
+                return Err(ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+                    ERR_MODULO,
+                    format!(
+                        "No se encontro el modulo \"{}\" en `{}`",
+                        path,
+                        full_path.display()
+                    ),
+                    line,
+                    col,
+                )))))
+            }
+        };
+
+        if let Some(module) = self.modules.get(&full_path) {
+            return Ok(ModuleSource::Loaded(module.clone()));
+        }
+
+        if let Some(pos) = self.file_stack.iter().position(|file| *file == full_path) {
+            let cycle = self.file_stack[pos..]
+                .iter()
+                .chain(std::iter::once(&full_path))
+                .map(|file| match file.file_name() {
+                    Some(name) => name.to_string_lossy().to_string(),
+                    None => file.display().to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+                ERR_MODULO,
+                format!("Importacion circular entre modulos: {}", cycle),
+                line,
+                col,
+            )))));
+        }
+
+        let source = match fs::read_to_string(&full_path) {
+            Ok(source) => source,
+            Err(_) => {
+                return Err(ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+                    ERR_MODULO,
+                    format!("No se pudo leer el modulo \"{}\"", path),
+                    line,
+                    col,
+                )))))
+            }
+        };
+
+        let mut parser = Parser::new(Lexer::new(source.chars().collect()));
+        let mut program = parser.parse();
+        if let Some(err) = parser.error {
+            let (msg, line, col) = err.parts();
+            return Err(ResultObj::Copy(Object::Error(Box::new(
+                create_typed_err(ERR_SINTAXIS, msg, line, col).with_file(path),
+            ))));
+        }
+        for warning in parser.warnings {
+            eprintln!("{} Archivo \"{}\".", warning, path);
+        }
+        let env = Rc::new(RefCell::new(Environment::new_module(path.to_owned())));
+        if let Err(err) = resolve(&mut program, &env) {
+            return Err(ResultObj::Copy(Object::Error(Box::new(
+                err.with_file(path),
+            ))));
+        }
+
+        self.file_stack.push(full_path.clone());
+        let pending = PendingModule {
+            name: path.to_owned(),
+            full_path,
+            env,
+        };
+        Ok(ModuleSource::Pending(pending, program))
+    }
+
+    // Registra el modulo con el resultado de evaluar su programa
+    pub fn end_module(&mut self, pending: PendingModule, res_obj: ResultObj) -> ResultObj {
+        self.file_stack.pop();
+        if let ResultObj::Copy(Object::Error(err)) = res_obj {
+            return ResultObj::Copy(Object::Error(Box::new(err.with_file(&pending.name))));
         }
+
+        let module = ResultObj::Copy(Object::Module(Box::new(ModuleObj {
+            name: pending.name,
+            env: pending.env,
+        })));
+        self.modules.insert(pending.full_path, module.clone());
+        module
     }
 
     pub fn eval_expression(&mut self, expr: Expression, env: &RcEnvironment) -> ResultObj {
@@ -178,10 +575,26 @@ impl Evaluator {
                 alternative,
             } => self.eval_if(*condition, consequence, alternative, env),
             ExprType::Identifier(ident) => self.eval_identifier(ident, env, expr.line, expr.col),
+            ExprType::Local { name, depth, slot } => {
+                match env.borrow().get_slot(depth, slot) {
+                    Some(obj) => obj,
+                    // Una funcion llamada antes de que se declare la variable
+                    None => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        format!("El identicador `{}` no existe", name),
+                        expr.line,
+                        expr.col,
+                    )))),
+                }
+            }
+            ExprType::Global { name, slot } => match env.borrow().get_global(slot) {
+                Some(obj) => obj,
+                // Sin declarar aun, el nombre puede ser una funcion interna
+                None => self.eval_identifier(name.to_string(), env, expr.line, expr.col),
+            },
             ExprType::FnLiteral { params, body } => {
                 ResultObj::Copy(Object::FnExpr(Box::new(FnExprObj {
                     params,
-                    body,
+                    body: Rc::new(body),
                     env: env.clone(),
                 })))
             }
@@ -189,10 +602,14 @@ impl Evaluator {
                 function,
                 arguments,
             } => self.eval_call(*function, arguments, env),
+            ExprType::NamedArgument { .. } => {
+                ResultObj::Copy(Object::Error(Box::new(named_arg_err(expr.line, expr.col))))
+            }
             ExprType::Assignment { left, right } => self.set_var(*left, *right, env),
             ExprType::StringLiteral(string) => {
                 ResultObj::Ref(new_rc_object(Object::String(string)))
             }
+            ExprType::InterpolatedString { parts } => self.eval_interpolated_string(parts, env),
             ExprType::ListLiteral { elements } => self.eval_list_literal(elements, env),
             ExprType::Index { left, index } => {
                 self.eval_index_expression(*left, *index, None, env).clone()
@@ -205,6 +622,33 @@ impl Evaluator {
                 arguments,
                 body,
             } => self.eval_for_range(ident, arguments, body, expr.line, expr.col, env),
+            ExprType::ForIn {
+                idents,
+                iterable,
+                body,
+            } => self.eval_for_in(idents, *iterable, body, expr.line, expr.col, env),
+            ExprType::For {
+                ident,
+                init,
+                condition,
+                update,
+                body,
+            } => self.eval_for(
+                ident, *init, *condition, *update, body, expr.line, expr.col, env,
+            ),
+            ExprType::Match { value, cases } => self.eval_match(*value, cases, env),
+            ExprType::Super {
+                method,
+                instance,
+                parent,
+            } => {
+                let instance = self.eval_expression(*instance, env);
+                let parent = self.eval_expression(*parent, env);
+                match super_method(&parent, &instance, &method, expr.line, expr.col) {
+                    Ok(obj) => obj,
+                    Err(err) => ResultObj::Copy(Object::Error(Box::new(err))),
+                }
+            }
         }
     }
 
@@ -215,11 +659,12 @@ impl Evaluator {
         alternative: BlockStatement,
         env: &RcEnvironment,
     ) -> ResultObj {
+        let ctx_len = self.stack_ctx.len();
         self.stack_ctx.push_back(Context::If);
         let condition = self.eval_expression(condition, env);
         let condition_res = {
             match condition {
-                ResultObj::Copy(Object::Numeric(numeric)) => numeric != Numeric::Int(0),
+                ResultObj::Copy(Object::Numeric(numeric)) => !numeric.is_int_zero(),
                 ResultObj::Copy(Object::Boolean(b)) => b,
                 ResultObj::Copy(Object::Null) => false,
                 obj => {
@@ -228,140 +673,57 @@ impl Evaluator {
             }
         };
         let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
-        if condition_res {
-            return self.eval_block_statement(consequence, &scope_env);
-        }
-        let obj = self.eval_block_statement(alternative, &scope_env);
-        if let Some(Context::If) = self.stack_ctx.back() {
-            self.stack_ctx.pop_back();
-        }
+        let obj = if condition_res {
+            self.eval_block_statement(consequence, &scope_env)
+        } else {
+            self.eval_block_statement(alternative, &scope_env)
+        };
+        self.stack_ctx.truncate(ctx_len);
         obj
     }
 
-    fn eval_prefix(
+    fn eval_match(
         &mut self,
-        operator: TokenType,
-        right: Expression,
+        value: Expression,
+        cases: Vec<MatchCase>,
         env: &RcEnvironment,
     ) -> ResultObj {
-        let right = self.eval_expression(right, env);
-        match operator {
-            TokenType::Plus => right,
-            TokenType::Minus => match right {
-                ResultObj::Copy(Object::Numeric(numeric)) => {
-                    ResultObj::Copy(Object::Numeric(-numeric))
-                }
-                ResultObj::Copy(Object::Boolean(b)) => {
-                    ResultObj::Copy(Object::Numeric(Numeric::Int(-(b as i64))))
-                }
-                _ => ResultObj::Copy(Object::Null),
-            },
-            TokenType::Bang => match right {
-                ResultObj::Copy(Object::Numeric(int)) => {
-                    ResultObj::Copy(Object::Boolean(int == Numeric::Int(0)))
+        let value = self.eval_expression(value, env);
+        if self.is_error(&value) {
+            return value;
+        }
+        for case in cases {
+            let Some(bindings) = match_case(&case.patterns, &value) else {
+                continue;
+            };
+            let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+            for (name, obj) in bindings {
+                scope_env.borrow_mut().declare(&name, obj);
+            }
+            // La condicion se evalua igual que la de `si`
+            if let Some(guard) = case.guard {
+                match self.eval_expression(guard, &scope_env) {
+                    ResultObj::Copy(
+                        Object::Numeric(Numeric::Int(0)) | Object::Boolean(false) | Object::Null,
+                    ) => continue,
+                    ResultObj::Copy(Object::Numeric(_) | Object::Boolean(true)) => {}
+                    obj => return obj,
                 }
-                ResultObj::Copy(Object::Boolean(b)) => ResultObj::Copy(Object::Boolean(!b)),
-                ResultObj::Copy(Object::Null) => ResultObj::Copy(Object::Boolean(true)),
-                _ => ResultObj::Copy(Object::Null),
-            },
-            _ => ResultObj::Copy(Object::Null),
+            }
+            return self.eval_block_statement(case.body, &scope_env);
         }
+        // Ningun caso coincide
+        ResultObj::Copy(Object::Null)
     }
 
-    fn match_infix_ops(
+    fn eval_prefix(
         &mut self,
-        left: ResultObj,
-        right: ResultObj,
         operator: TokenType,
+        right: Expression,
+        env: &RcEnvironment,
     ) -> ResultObj {
-        match (left, right) {
-            (ResultObj::Copy(Object::Numeric(a)), ResultObj::Copy(Object::Numeric(b))) => {
-                self.eval_infix_numeric_operation(a, b, operator)
-            }
-            (ResultObj::Copy(Object::Numeric(a)), ResultObj::Copy(Object::Boolean(b))) => {
-                self.eval_infix_numeric_operation(a, Numeric::Int(b as i64), operator)
-            }
-            (ResultObj::Copy(Object::Boolean(a)), ResultObj::Copy(Object::Numeric(b))) => {
-                self.eval_infix_numeric_operation(Numeric::Int(a as i64), b, operator)
-            }
-            (ResultObj::Copy(Object::Boolean(a)), ResultObj::Copy(Object::Boolean(b))) => self
-                .eval_infix_numeric_operation(
-                    Numeric::Int(a as i64),
-                    Numeric::Int(b as i64),
-                    operator,
-                ),
-            // (ResultObj::Copy(Object::String(a)), ResultObj::Copy(Object::Numeric(b))) => {
-            //     self.eval_infix_string_int_operation(&a, b, operator)
-            // }
-            // (ResultObj::Copy(Object::Numeric(a)), ResultObj::Copy(Object::String(b))) => {
-            //     self.eval_infix_string_int_operation(&b, a, operator)
-            // }
-            (ResultObj::Ref(a), ResultObj::Ref(b)) => match (&*a.borrow(), &*b.borrow()) {
-                (Object::String(a), Object::String(b)) => {
-                    self.eval_infix_string_operation(a, b, operator)
-                }
-                (Object::List(ref a), Object::List(ref b)) => {
-                    self.eval_infix_list_operation(a, b, operator)
-                }
-                _ => panic!("Ok, no se ocurre como llamar este error."),
-            },
-            (ResultObj::Copy(Object::Numeric(a)), ResultObj::Ref(b)) => match &*b.borrow() {
-                Object::List(b) => self.eval_infix_list_int_operation(b, a, operator),
-                Object::String(b) => self.eval_infix_string_int_operation(b, a, operator),
-                _ => panic!("Ok, no se ocurre como llamar este error."),
-            },
-            (ResultObj::Ref(a), ResultObj::Copy(Object::Numeric(b))) => match &*a.borrow() {
-                Object::List(a) => self.eval_infix_list_int_operation(a, b, operator),
-                Object::String(a) => self.eval_infix_string_int_operation(a, b, operator),
-                _ => panic!("Ok, no se ocurre como llamar este error."),
-            },
-
-            (ResultObj::Copy(Object::Return(a)), ResultObj::Ref(b)) => match (&*b.borrow(), *a) {
-                (Object::List(b), ResultObj::Copy(Object::Numeric(a))) => {
-                    self.eval_infix_list_int_operation(b, a, operator)
-                }
-                (Object::List(b), ResultObj::Ref(a)) => {
-                    if let Object::List(a) = &*a.borrow() {
-                        self.eval_infix_list_operation(a, b, operator)
-                    } else {
-                        panic!("Ok, no se ocurre como llamar este error.")
-                    }
-                }
-                _ => panic!("Ok, no se ocurre como llamar este error."),
-            },
-            (ResultObj::Ref(b), ResultObj::Copy(Object::Return(a))) => match (&*b.borrow(), *a) {
-                (Object::List(b), ResultObj::Copy(Object::Numeric(a))) => {
-                    self.eval_infix_list_int_operation(b, a, operator)
-                }
-                (Object::List(b), ResultObj::Ref(a)) => {
-                    if let Object::List(a) = &*a.borrow() {
-                        self.eval_infix_list_operation(b, a, operator)
-                    } else {
-                        panic!("Ok, no se ocurre como llamar este error.")
-                    }
-                }
-                _ => panic!("Ok, no se ocurre como llamar este error."),
-            },
-            (ResultObj::Copy(Object::Error(msg)), _) => {
-                ResultObj::Copy(Object::Error("^".to_string() + &msg))
-            }
-            (_, ResultObj::Copy(Object::Error(msg))) => {
-                ResultObj::Copy(Object::Error("^".to_string() + &msg))
-            }
-            (ResultObj::Copy(Object::Null), ResultObj::Copy(Object::Null)) => {
-                self.eval_infix_null_operation(operator)
-            }
-            (ResultObj::Copy(Object::Null), _) => self.eval_infix_null_object_operation(operator),
-            (_, ResultObj::Copy(Object::Null)) => self.eval_infix_null_object_operation(operator),
-            (ResultObj::Copy(Object::Return(a)), b) => self.match_infix_ops(*a, b, operator),
-            (a, ResultObj::Copy(Object::Return(b))) => self.match_infix_ops(a, *b, operator),
-            (a, b) => ResultObj::Copy(Object::Error(format!(
-                "No se soporta operaciones {} {} {}",
-                self.get_type(&a),
-                operator,
-                self.get_type(&b)
-            ))),
-        }
+        let right = self.eval_expression(right, env);
+        eval_prefix_obj(&operator, right)
     }
 
     fn eval_member_ops(
@@ -372,6 +734,17 @@ impl Evaluator {
         left_col: usize,
         env: &RcEnvironment,
     ) -> ResultObj {
+        if let ResultObj::Copy(Object::Module(module)) = left {
+            return self.eval_module_member(right, *module, env);
+        }
+        if is_instance(&left) {
+            return self.eval_instance_member(right, left, env);
+        }
+        if let (ResultObj::Copy(Object::CaughtError(err)), ExprType::Identifier(name)) =
+            (&left, &right.r#type)
+        {
+            return eval_error_member(err, name, right.line, right.col);
+        }
         match right.r#type {
             ExprType::Call {
                 function,
@@ -381,160 +754,215 @@ impl Evaluator {
                     if self.is_error(&left) {
                         return left;
                     }
-                    match_member_fn(self, identifier, arguments, left, left_line, left_col, env)
+                    let named = arguments
+                        .iter()
+                        .find(|arg| matches!(arg.r#type, ExprType::NamedArgument { .. }));
+                    if let Some(arg) = named {
+                        return ResultObj::Copy(Object::Error(Box::new(named_arg_err(
+                            arg.line, arg.col,
+                        ))));
+                    }
+                    let (args, site) =
+                        match self.eval_arguments(arguments, function.line, function.col, env) {
+                            Ok(evaluated) => evaluated,
+                            Err(err) => return err,
+                        };
+                    if let Some(err) =
+                        member_lock_err(&self.iter_locks, &identifier, &left, left_line, left_col)
+                    {
+                        return err;
+                    }
+                    match_member_fn(
+                        &mut |callee, args, line, col| self.call_values(callee, args, line, col),
+                        identifier,
+                        args,
+                        &site.args,
+                        left,
+                        left_line,
+                        left_col,
+                    )
                 }
-                _ => ResultObj::Copy(Object::Error(create_msg_err(
+                _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                     "El operador de acceso de miembro espera un identicador o llamada".into(),
                     left_line,
                     left_col,
-                ))),
+                )))),
             },
-            _ => ResultObj::Copy(Object::Error(create_msg_err(
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 format!(
                     "El operador de acceso de miembro no puede operar con una expresion {}",
                     right.r#type.get_type()
                 ),
                 right.line,
                 right.col,
-            ))),
+            )))),
         }
     }
 
-    fn eval_infix(
+    fn eval_module_member(
         &mut self,
-        operator: TokenType,
-        left: Expression,
         right: Expression,
+        module: ModuleObj,
         env: &RcEnvironment,
     ) -> ResultObj {
-        let line = left.line;
-        let col = left.col;
-        let left = self.eval_expression(left, env);
-
-        if operator == TokenType::Dot {
-            return self.eval_member_ops(right, left, line, col, env);
-        }
-
-        let right = self.eval_expression(right, env);
-
-        // match err
-        match self.match_infix_ops(left, right, operator) {
-            ResultObj::Copy(Object::Error(err)) => {
-                ResultObj::Copy(Object::Error(create_msg_err(err, line, col)))
+        let get_member = |name: &String, line: usize, col: usize| -> ResultObj {
+            match module.env.borrow().get(name) {
+                Some(obj) => obj,
+                None => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!(
+                        "El modulo \"{}\" no tiene el miembro `{}`",
+                        module.name, name
+                    ),
+                    line,
+                    col,
+                )))),
             }
-            obj => obj,
-        }
-    }
-
-    fn get_type(&self, obj: &ResultObj) -> String {
-        match obj {
-            ResultObj::Copy(obj) => obj.get_type().to_string(),
-            ResultObj::Ref(obj) => obj.borrow().get_type().to_string(),
-        }
-    }
-
-    fn eval_infix_numeric_operation(&self, a: Numeric, b: Numeric, op: TokenType) -> ResultObj {
-        // TODO: Hay que ver si hay overflow!!! y mandar un mensaje de error adecuado
-        match op {
-            TokenType::Plus => ResultObj::Copy(Object::Numeric(a + b)),
-            TokenType::Minus => ResultObj::Copy(Object::Numeric(a - b)),
-            TokenType::Slash => ResultObj::Copy(Object::Numeric(a / b)),
-            TokenType::Asterisk => ResultObj::Copy(Object::Numeric(a * b)),
-            TokenType::Percent => ResultObj::Copy(Object::Numeric(a % b)),
-            TokenType::Eq => ResultObj::Copy(Object::Boolean(a == b)),
-            TokenType::NotEq => ResultObj::Copy(Object::Boolean(a != b)),
-            TokenType::Lt => ResultObj::Copy(Object::Boolean(a < b)),
-            TokenType::Gt => ResultObj::Copy(Object::Boolean(a > b)),
-            TokenType::LtEq => ResultObj::Copy(Object::Boolean(a <= b)),
-            TokenType::GtEq => ResultObj::Copy(Object::Boolean(a >= b)),
-            _ => ResultObj::Copy(Object::Null),
-        }
-    }
-
-    fn eval_infix_string_operation(&self, a: &String, b: &String, op: TokenType) -> ResultObj {
-        match op {
-            TokenType::Plus => ResultObj::Ref(new_rc_object(Object::String(format!("{}{}", a, b)))),
-            TokenType::Eq => ResultObj::Copy(Object::Boolean(a == b)),
-            TokenType::NotEq => ResultObj::Copy(Object::Boolean(a != b)),
-            _ => ResultObj::Copy(Object::Null),
+        };
+        match right.r#type {
+            ExprType::Identifier(name) => get_member(&name, right.line, right.col),
+            ExprType::Call {
+                function,
+                arguments,
+            } => match function.r#type {
+                ExprType::Identifier(name) => {
+                    let obj = get_member(&name, function.line, function.col);
+                    if self.is_error(&obj) {
+                        return obj;
+                    }
+                    match self.eval_arguments(arguments, function.line, function.col, env) {
+                        Ok((args, site)) => self.call_obj(obj, args, &site),
+                        Err(err) => err,
+                    }
+                }
+                _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "El operador de acceso de miembro espera un identicador o llamada".into(),
+                    function.line,
+                    function.col,
+                )))),
+            },
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "El operador de acceso de miembro no puede operar con una expresion {}",
+                    right.r#type.get_type()
+                ),
+                right.line,
+                right.col,
+            )))),
         }
     }
 
-    fn eval_infix_string_int_operation(&self, a: &str, b: Numeric, op: TokenType) -> ResultObj {
-        if let Numeric::Int(int) = b {
-            return match op {
-                TokenType::Asterisk => {
-                    ResultObj::Ref(new_rc_object(Object::String(a.repeat(int as usize))))
+    fn eval_instance_member(
+        &mut self,
+        right: Expression,
+        instance: ResultObj,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        match right.r#type {
+            ExprType::Identifier(name) => {
+                match instance_member(&instance, &name, right.line, right.col) {
+                    Ok(obj) => obj,
+                    Err(err) => ResultObj::Copy(Object::Error(Box::new(err))),
                 }
-                _ => ResultObj::Copy(Object::Null),
-            };
+            }
+            ExprType::Call {
+                function,
+                arguments,
+            } => match function.r#type {
+                ExprType::Identifier(name) => {
+                    let obj = match instance_member(&instance, &name, function.line, function.col) {
+                        Ok(obj) => obj,
+                        Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+                    };
+                    match self.eval_arguments(arguments, function.line, function.col, env) {
+                        Ok((args, site)) => self.call_obj(obj, args, &site),
+                        Err(err) => err,
+                    }
+                }
+                _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "El operador de acceso de miembro espera un identicador o llamada".into(),
+                    function.line,
+                    function.col,
+                )))),
+            },
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "El operador de acceso de miembro no puede operar con una expresion {}",
+                    right.r#type.get_type()
+                ),
+                right.line,
+                right.col,
+            )))),
         }
-        ResultObj::Copy(Object::Error(
-            "No se puede hacer operaciones de indexacion con numeros flotantes".to_owned(),
-        ))
     }
 
-    fn eval_infix_list_operation(
-        &self,
-        a: &Vec<ResultObj>,
-        b: &Vec<ResultObj>,
-        op: TokenType,
+    fn eval_infix(
+        &mut self,
+        operator: TokenType,
+        left: Expression,
+        right: Expression,
+        env: &RcEnvironment,
     ) -> ResultObj {
-        match op {
-            TokenType::Plus => ResultObj::Ref(new_rc_object(Object::List(
-                [a.as_slice(), b.as_slice()].concat(),
-            ))),
-            TokenType::Eq => ResultObj::Copy(Object::Boolean(a == b)),
-            TokenType::NotEq => ResultObj::Copy(Object::Boolean(a != b)),
-            TokenType::Lt => ResultObj::Copy(Object::Boolean(a.len() < b.len())),
-            TokenType::Gt => ResultObj::Copy(Object::Boolean(a.len() > b.len())),
-            TokenType::LtEq => ResultObj::Copy(Object::Boolean(a.len() <= b.len())),
-            TokenType::GtEq => ResultObj::Copy(Object::Boolean(a.len() >= b.len())),
-            _ => ResultObj::Copy(Object::Null),
+        let line = left.line;
+        let col = left.col;
+        let left = self.eval_expression(left, env);
+
+        if operator == TokenType::Dot {
+            return self.eval_member_ops(right, left, line, col, env);
         }
-    }
 
-    fn eval_infix_list_int_operation(
-        &self,
-        a: &Vec<ResultObj>,
-        b: Numeric,
-        op: TokenType,
-    ) -> ResultObj {
-        if let Numeric::Int(int) = b {
-            match op {
-                TokenType::Asterisk => {
-                    let mut objs = Vec::new();
-                    objs.reserve(int as usize);
-                    for _ in 0..int {
-                        objs.extend(a.to_owned());
-                    }
-                    return ResultObj::Ref(new_rc_object(Object::List(objs)));
-                }
-                _ => return ResultObj::Copy(Object::Null),
-            };
+        if operator == TokenType::And || operator == TokenType::Or {
+            return self.eval_logical_ops(left, right, operator, line, col, env);
+        }
+
+        if self.is_error(&left) {
+            return left;
+        }
+        let right = self.eval_expression(right, env);
+        if self.is_error(&right) {
+            return right;
         }
-        ResultObj::Copy(Object::Error(
-            "No se puede hacer operaciones con numeros flotantes en listas".to_owned(),
-        ))
-    }
 
-    fn eval_infix_null_operation(&self, operator: TokenType) -> ResultObj {
-        match operator {
-            TokenType::Eq => ResultObj::Copy(Object::Boolean(true)),
-            TokenType::NotEq => ResultObj::Copy(Object::Boolean(false)),
-            _ => ResultObj::Copy(Object::Error(
-                "El objeto nulo solo puede hacer operacciones logicas de igualdad".to_owned(),
-            )),
+        let obj = call_operator(&left, &right, &operator, &mut |callee, args| {
+            self.call_values(callee, args, line, col)
+        });
+        let obj = match obj {
+            Some(obj) => obj,
+            None => match_infix_ops(left, right, operator),
+        };
+        match obj {
+            ResultObj::Copy(Object::Error(err)) => {
+                ResultObj::Copy(Object::Error(Box::new(err.with_position(line, col))))
+            }
+            obj => obj,
         }
     }
 
-    fn eval_infix_null_object_operation(&self, operator: TokenType) -> ResultObj {
-        match operator {
-            TokenType::Eq => ResultObj::Copy(Object::Boolean(false)),
-            TokenType::NotEq => ResultObj::Copy(Object::Boolean(true)),
-            _ => ResultObj::Copy(Object::Error(
-                "El objeto nulo solo puede hacer operacciones logicas de igualdad".to_owned(),
-            )),
+    // Los operadores logicos evaluan el lado derecho solo si el izquierdo
+    // no decide el resultado
+    fn eval_logical_ops(
+        &mut self,
+        left: ResultObj,
+        right: Expression,
+        operator: TokenType,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let left = match eval_logical_operand(left, &operator, line, col) {
+            Ok(b) => b,
+            Err(err) => return err,
+        };
+        match (&operator, left) {
+            (TokenType::And, false) => return ResultObj::Copy(Object::Boolean(false)),
+            (TokenType::Or, true) => return ResultObj::Copy(Object::Boolean(true)),
+            _ => {}
+        }
+        let right_line = right.line;
+        let right_col = right.col;
+        let right = self.eval_expression(right, env);
+        match eval_logical_operand(right, &operator, right_line, right_col) {
+            Ok(b) => ResultObj::Copy(Object::Boolean(b)),
+            Err(err) => err,
         }
     }
 
@@ -547,14 +975,14 @@ impl Evaluator {
     }
 
     fn set_var(&mut self, left: Expression, right: Expression, env: &RcEnvironment) -> ResultObj {
-        return match &left.r#type {
+        match &left.r#type {
             ExprType::Identifier(ident) => {
                 if !self.exist_var(ident, env) {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         format!("El no existe referencias hacia `{}`", ident),
                         left.line,
                         left.col,
-                    )));
+                    ))));
                 }
 
                 let obj = self.eval_expression(right, env);
@@ -563,6 +991,32 @@ impl Evaluator {
                 env_ref.update(ident, obj.clone());
                 obj
             }
+            ExprType::Local { name, depth, slot } => {
+                if env.borrow().get_slot(*depth, *slot).is_none() {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        format!("El no existe referencias hacia `{}`", name),
+                        left.line,
+                        left.col,
+                    ))));
+                }
+
+                let obj = self.eval_expression(right, env);
+                env.borrow_mut().set_slot(*depth, *slot, obj.clone());
+                obj
+            }
+            ExprType::Global { name, slot } => {
+                if env.borrow().get_global(*slot).is_none() {
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        format!("El no existe referencias hacia `{}`", name),
+                        left.line,
+                        left.col,
+                    ))));
+                }
+
+                let obj = self.eval_expression(right, env);
+                env.borrow_mut().set_global(*slot, obj.clone());
+                obj
+            }
             ExprType::Index { left, index } => {
                 let right_obj = self.eval_expression(right, env);
                 if self.is_error(&right_obj) {
@@ -576,15 +1030,36 @@ impl Evaluator {
                     env,
                 )
             }
-            _ => ResultObj::Copy(Object::Error(create_msg_err(
+            ExprType::Infix {
+                left: target,
+                operator: TokenType::Dot,
+                right: member,
+            } if matches!(member.r#type, ExprType::Identifier(_)) => {
+                let ExprType::Identifier(ref name) = member.r#type else {
+                    unreachable!();
+                };
+                let (target_line, target_col) = (target.line, target.col);
+                let target = self.eval_expression(*target.to_owned(), env);
+                if self.is_error(&target) {
+                    return target;
+                }
+                match self.eval_var_value(right, env) {
+                    Ok(value) => match set_member(&target, name, value, target_line, target_col) {
+                        Ok(obj) => obj,
+                        Err(err) => ResultObj::Copy(Object::Error(Box::new(err))),
+                    },
+                    Err(err) => err,
+                }
+            }
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 format!(
                     "No se puede realizar operaciones de asignacion a {}",
                     left.r#type
                 ),
                 left.line,
                 left.col,
-            ))),
-        };
+            )))),
+        }
     }
 
     fn get_var_value(
@@ -596,11 +1071,11 @@ impl Evaluator {
     ) -> Option<ResultObj> {
         let env_ref = RefCell::borrow(env);
         env_ref.get(name).map(|_| -> ResultObj {
-            ResultObj::Copy(Object::Error(create_msg_err(
+            ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 format!("El identificador `{}` ya habia sido declarado", name),
                 line,
                 col,
-            )))
+            ))))
         })
     }
 
@@ -610,29 +1085,37 @@ impl Evaluator {
     }
 
     fn insert_var(&mut self, name: &str, value: Expression, env: &RcEnvironment) -> ResultObj {
+        match self.eval_var_value(value, env) {
+            Ok(obj) => self.insert_obj(name, obj, env),
+            Err(err) => err,
+        }
+    }
+
+    // Evalua el valor que se va a guardar en una variable
+    fn eval_var_value(
+        &mut self,
+        value: Expression,
+        env: &RcEnvironment,
+    ) -> Result<ResultObj, ResultObj> {
         let line = value.line;
         let col = value.col;
-        let mut value_obj = self.eval_expression(value, env);
-        if let ResultObj::Copy(ref obj) = value_obj {
-            match obj {
-                Object::Error(_) => return value_obj,
-                Object::Return(ref returned_obj) => value_obj = *returned_obj.clone(),
-                Object::Void => {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
-                        "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
-                        line,
-                        col,
-                    )));
-                }
-                _ => {}
+        match self.eval_expression(value, env) {
+            ResultObj::Copy(Object::Error(err)) => Err(ResultObj::Copy(Object::Error(err))),
+            ResultObj::Copy(Object::Return(obj)) => Ok(*obj),
+            ResultObj::Copy(Object::Void) => {
+                Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                    line,
+                    col,
+                )))))
             }
+            obj => Ok(obj),
         }
-        self.insert_obj(name, value_obj, env)
     }
 
     fn insert_obj(&mut self, name: &str, obj: ResultObj, env: &RcEnvironment) -> ResultObj {
         let mut env_ref = RefCell::borrow_mut(env);
-        env_ref.set(name.to_owned(), obj.clone());
+        env_ref.declare(name, obj.clone());
         obj
     }
 
@@ -646,17 +1129,14 @@ impl Evaluator {
         match env.borrow().get(&ident) {
             Some(obj) => obj,
             None => {
-                if let Some(func) = self.buildins_internal_fn.get(&ident) {
-                    return ResultObj::Copy(Object::BuildinFn(Box::new(BuildinFnObj {
-                        name: ident,
-                        func: func.clone_box(),
-                    })));
+                if let Some(func) = get_buildin(&ident) {
+                    return func;
                 }
-                ResultObj::Copy(Object::Error(create_msg_err(
-                    format!("El identicador `{}` no existe.", ident),
+                ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!("El identicador `{}` no existe", ident),
                     line,
                     col,
-                )))
+                ))))
             }
         }
     }
@@ -670,71 +1150,301 @@ impl Evaluator {
         let line = function.line;
         let col = function.col;
         let obj = self.eval_expression(function, env);
+        if self.is_error(&obj) {
+            return obj;
+        }
+        match self.eval_arguments(arguments, line, col, env) {
+            Ok((args, site)) => self.call_obj(obj, args, &site),
+            Err(err) => err,
+        }
+    }
+
+    // Igual que `eval_call`, pero si se llama a una funcion del programa no
+    // la ejecuta, la devuelve para que la funcion actual la ejecute al retornar
+    fn eval_tail_call(
+        &mut self,
+        function: Expression,
+        arguments: Vec<Expression>,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let line = function.line;
+        let col = function.col;
+        let obj = self.eval_expression(function, env);
+        if self.is_error(&obj) {
+            return obj;
+        }
+        let (args, site) = match self.eval_arguments(arguments, line, col, env) {
+            Ok(call) => call,
+            Err(err) => return err,
+        };
+        match obj {
+            callee @ ResultObj::Copy(Object::Fn(_) | Object::FnExpr(_)) => {
+                ResultObj::Copy(Object::TailCall(Box::new(TailCallObj {
+                    callee,
+                    args,
+                    site,
+                })))
+            }
+            obj => self.call_obj(obj, args, &site),
+        }
+    }
+
+    // Evalua los argumentos de una llamada una sola vez, de izquierda a
+    // derecha y en el environment de quien llama
+    pub fn eval_arguments(
+        &mut self,
+        arguments: FnParams,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> Result<(Vec<ResultObj>, CallSite), ResultObj> {
+        let mut site = CallSite {
+            line,
+            col,
+            target: (line, col),
+            args: Vec::with_capacity(arguments.len()),
+            names: Vec::with_capacity(arguments.len()),
+        };
+        let mut values = Vec::with_capacity(arguments.len());
+        for arg in arguments {
+            let (name, value) = split_named_arg(arg);
+            site.args.push((value.line, value.col));
+            site.names.push(name);
+            match self.eval_expression(value, env) {
+                err @ ResultObj::Copy(Object::Error(_)) => return Err(err),
+                ResultObj::Copy(Object::Return(obj)) => values.push(*obj),
+                obj => values.push(obj),
+            }
+        }
+        Ok((values, site))
+    }
+
+    // Llama a una funcion con sus argumentos ya evaluados. `site` tiene la
+    // posicion de la llamada y el nombre y la posicion de cada argumento
+    pub fn call_obj(&mut self, obj: ResultObj, args: Vec<ResultObj>, site: &CallSite) -> ResultObj {
+        let (line, col) = (site.line, site.col);
         match obj {
             ResultObj::Copy(Object::FnExpr(fn_expr)) => self.eval_fn_expr(
-                arguments,
+                None,
+                args,
+                site,
                 fn_expr.params,
-                fn_expr.body,
+                Rc::unwrap_or_clone(fn_expr.body),
                 &fn_expr.env,
-                line,
-                col,
             ),
             ResultObj::Copy(Object::Fn(fn_expr)) => self.eval_fn_expr(
-                arguments,
+                Some(&fn_expr.name),
+                args,
+                site,
                 fn_expr.params,
                 fn_expr.body,
                 &fn_expr.env,
-                line,
-                col,
             ),
             ResultObj::Copy(Object::BuildinFn(f)) => {
-                let func = f.func;
-                func(self, arguments, env)
+                call_buildin(&f, args, site, &mut |callee, args, line, col| {
+                    self.call_values(callee, args, line, col)
+                })
+            }
+            ResultObj::Copy(Object::Class(class)) => {
+                let instance = new_instance(&class);
+                match class.find_method("iniciar") {
+                    Some((owner, init)) => {
+                        let init = bind_method(&init, &instance, &owner);
+                        let obj = self.call_obj(init, args, site);
+                        if self.is_error(&obj) {
+                            return obj;
+                        }
+                    }
+                    None if !args.is_empty() => {
+                        return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                            format!(
+                                "Se encontro {} argumentos, se espera {}()",
+                                args.len(),
+                                class.name
+                            ),
+                            line,
+                            col,
+                        ))));
+                    }
+                    None => {}
+                }
+                instance
             }
-            // TODO(Retornar errores previo)
-            _ => ResultObj::Copy(Object::Error(create_msg_err(
+            // El error de la expresion que se llama, ej. un identificador que no existe
+            err @ ResultObj::Copy(Object::Error(_)) => err,
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 "La operacion de llamada solo puede ser aplicada a objetos que sean funciones"
                     .to_owned(),
                 line,
                 col,
-            ))),
+            )))),
         }
     }
 
+    // Llama a una funcion con argumentos sin nombre, todos en la posicion
+    // `line`, `col`. Por ejemplo, las funciones que recibe `mapear`
+    pub fn call_values(
+        &mut self,
+        obj: ResultObj,
+        args: Vec<ResultObj>,
+        line: usize,
+        col: usize,
+    ) -> ResultObj {
+        let site = CallSite {
+            line,
+            col,
+            target: (line, col),
+            args: vec![(line, col); args.len()],
+            names: vec![None; args.len()],
+        };
+        self.call_obj(obj, args, &site)
+    }
+
+    // Cuenta la llamada, si pasa del maximo retorna un error en vez de
+    // agotar la pila
     pub fn eval_fn_expr(
         &mut self,
-        arguments: FnParams,
-        params: FnParams,
+        name: Option<&str>,
+        args: Vec<ResultObj>,
+        site: &CallSite,
+        params: Vec<Param>,
         body: BlockStatement,
         env: &RcEnvironment,
-        line: usize,
-        col: usize,
     ) -> ResultObj {
-        self.stack_ctx.push_back(Context::Fn);
-        let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
-        if arguments.len() != params.len() {
-            return ResultObj::Copy(Object::Error(create_msg_err(
-                format!(
-                    "Se encontro {} argumentos, de {}.",
-                    arguments.len(),
-                    params.len()
+        if self.depth >= self.max_depth {
+            return ResultObj::Copy(Object::Error(Box::new(max_depth_err(
+                self.max_depth,
+                site.line,
+                site.col,
+            ))));
+        }
+        self.depth += 1;
+        let mut res_obj = self.eval_fn_body(name, args, site, params, body, env);
+        // Las llamadas en posicion de cola reemplazan a la llamada actual, asi
+        // la recursion de cola no aumenta la profundidad
+        while let ResultObj::Copy(Object::TailCall(call)) = res_obj {
+            let TailCallObj { callee, args, site } = *call;
+            res_obj = match callee {
+                ResultObj::Copy(Object::FnExpr(fn_expr)) => self.eval_fn_body(
+                    None,
+                    args,
+                    &site,
+                    fn_expr.params,
+                    Rc::unwrap_or_clone(fn_expr.body),
+                    &fn_expr.env,
                 ),
-                line,
-                col,
-            )));
+                ResultObj::Copy(Object::Fn(fn_expr)) => self.eval_fn_body(
+                    Some(&fn_expr.name),
+                    args,
+                    &site,
+                    fn_expr.params,
+                    fn_expr.body,
+                    &fn_expr.env,
+                ),
+                callee => self.call_obj(callee, args, &site),
+            };
         }
-        for (arg, param) in arguments.iter().zip(params) {
-            if let ExprType::Identifier(param_name) = param.r#type {
-                self.insert_var(&param_name, arg.clone(), &scope_env);
+        self.depth -= 1;
+        res_obj
+    }
+
+    // El cuerpo se evalua en un environment nuevo, hijo del de la funcion
+    // (`env`). Los valores por defecto se evaluan en el nuevo, despues de los
+    // parametros anteriores
+    fn eval_fn_body(
+        &mut self,
+        name: Option<&str>,
+        args: Vec<ResultObj>,
+        site: &CallSite,
+        params: Vec<Param>,
+        body: BlockStatement,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let mut named_args = Vec::with_capacity(args.len());
+        for ((arg, name), (line, col)) in args.into_iter().zip(&site.names).zip(&site.args) {
+            if let ResultObj::Copy(Object::Void) = arg {
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                    *line,
+                    *col,
+                ))));
             }
+            named_args.push((name.clone(), arg));
         }
-        let res_obj = self.eval_block_statement(body, &scope_env);
-        if let Some(Context::Fn) = self.stack_ctx.back() {
-            self.stack_ctx.pop_back();
+        let bound = match bind_args(
+            &fn_param_kinds(&params),
+            named_args,
+            &|| fn_signature(name, &params),
+            site.line,
+            site.col,
+        ) {
+            Ok(bound) => bound,
+            Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+        };
+        let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+        for (param, arg) in params.iter().zip(bound) {
+            let obj = match arg {
+                BoundArg::Value(obj) => obj,
+                BoundArg::Rest(objs) => ResultObj::Ref(new_rc_object(Object::List(objs))),
+                // Solo falta un parametro si tiene valor por defecto
+                BoundArg::Missing => match param.default.clone() {
+                    Some(default) => match self.eval_var_value(default, &scope_env) {
+                        Ok(obj) => obj,
+                        Err(err) => return err,
+                    },
+                    None => ResultObj::Copy(Object::Null),
+                },
+            };
+            self.insert_obj(&param.name, obj, &scope_env);
+        }
+        let ctx_len = self.stack_ctx.len();
+        self.stack_ctx.push_back(Context::Fn);
+        let mut res_obj = self.eval_block_statement(body, &scope_env);
+        self.stack_ctx.truncate(ctx_len);
+        // El retorno termina en la funcion, no debe detener el bloque de quien llama
+        if let ResultObj::Copy(Object::Return(returned_obj)) = res_obj {
+            res_obj = *returned_obj;
+        }
+        // Los errores dentro de funciones de un modulo indican su archivo
+        if let ResultObj::Copy(Object::Error(err)) = res_obj {
+            return match env.borrow().module_name() {
+                Some(file) => ResultObj::Copy(Object::Error(Box::new(err.with_file(&file)))),
+                None => ResultObj::Copy(Object::Error(err)),
+            };
         }
         res_obj
     }
 
+    fn eval_interpolated_string(
+        &mut self,
+        parts: Vec<Expression>,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        let mut string = String::new();
+        for expr in parts {
+            let line = expr.line;
+            let col = expr.col;
+            let obj = self.eval_expression(expr, env);
+            if self.is_error(&obj) {
+                return obj;
+            }
+            if let ResultObj::Copy(Object::Void) = obj {
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "No se puede interpolar el tipo de dato vacio".to_owned(),
+                    line,
+                    col,
+                ))));
+            }
+            match obj_text(&obj, line, col, &mut |callee, args| {
+                self.call_values(callee, args, line, col)
+            }) {
+                Ok(text) => string.push_str(&text),
+                Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+            }
+        }
+        ResultObj::Ref(new_rc_object(Object::String(string)))
+    }
+
     fn eval_list_literal(&mut self, elements: Vec<Expression>, env: &RcEnvironment) -> ResultObj {
         let mut objs = Vec::new();
         for expr in elements {
@@ -763,66 +1473,39 @@ impl Evaluator {
         if self.is_error(&index_obj) {
             return index_obj;
         }
-        match left_obj {
-            ResultObj::Copy(obj) => match obj {
-                Object::Error(msg) => ResultObj::Copy(Object::Error(msg)),
-                _ => ResultObj::Copy(Object::Error(create_msg_err(
-                    "Solo se puede usar el operador de indexar en listas y dicccionarios"
-                        .to_owned(),
-                    line,
-                    col,
-                ))),
-            },
-            ResultObj::Ref(obj) => match *obj.borrow_mut() {
-                Object::List(ref mut objs) => {
-                    if let ResultObj::Copy(Object::Numeric(Numeric::Int(index))) = index_obj {
-                        if let Some(new_value) = new_value {
-                            if (index as usize) < objs.len() {
-                                objs[index as usize] = new_value.clone();
-                                return new_value;
-                            }
-                            return ResultObj::Copy(Object::Null);
-                        }
-                        return match objs.get(index as usize) {
-                            Some(obj) => obj.clone(),
-                            None => ResultObj::Copy(Object::Null),
-                        };
-                    }
-                    ResultObj::Copy(Object::Error(create_msg_err(
-                        "El operador de indexar solo opera con enteros".to_owned(),
-                        index_line,
-                        index_col,
-                    )))
-                }
-                Object::Dictionary(ref pairs) => match pairs.get(&index_obj) {
-                    Some(obj) => obj.clone(),
-                    None => ResultObj::Copy(Object::Error(create_msg_err(
-                        format!("Llave invalida {}", index_obj.get_type()),
-                        index_line,
-                        index_col,
-                    ))),
-                },
-                _ => ResultObj::Copy(Object::Error(create_msg_err(
-                    "Solo se puede usar el operador de indexar en listas y dicccionarios"
-                        .to_owned(),
-                    line,
-                    col,
-                ))),
-            },
-        }
+        eval_index_obj(
+            &self.iter_locks,
+            left_obj,
+            index_obj,
+            new_value,
+            line,
+            col,
+            index_line,
+            index_col,
+        )
     }
 
+    #[allow(clippy::mutable_key_type)]
     fn eval_dictionary_expression(
         &mut self,
-        expr_pairs: HashMap<Expression, Expression>,
+        expr_pairs: Vec<(Expression, Expression)>,
         env: &RcEnvironment,
     ) -> ResultObj {
-        let mut pairs = HashMap::new();
+        let mut pairs = IndexMap::new();
         for (k, v) in expr_pairs {
+            let (key_line, key_col) = (k.line, k.col);
             let obj_key = self.eval_expression(k, env);
             if self.is_error(&obj_key) {
                 return obj_key;
             }
+            if let Err(err) = check_dict_key(&obj_key, key_line, key_col) {
+                return ResultObj::Copy(Object::Error(Box::new(err)));
+            }
+            if pairs.contains_key(&obj_key) {
+                return ResultObj::Copy(Object::Error(Box::new(repeated_key_err(
+                    &obj_key, key_line, key_col,
+                ))));
+            }
             let obj_value = self.eval_expression(v, env);
             if self.is_error(&obj_value) {
                 return obj_value;
@@ -845,12 +1528,13 @@ impl Evaluator {
         body: BlockStatement,
         env: &RcEnvironment,
     ) -> ResultObj {
+        let ctx_len = self.stack_ctx.len();
         self.stack_ctx.push_back(Context::Loop);
         let condition_ref = Rc::new(RefCell::new(condition));
         let condition_obj = self.eval_expression(condition_ref.borrow().clone(), env);
         let mut condition_res = {
             match condition_obj {
-                ResultObj::Copy(Object::Numeric(numeric)) => numeric != Numeric::Int(0),
+                ResultObj::Copy(Object::Numeric(numeric)) => !numeric.is_int_zero(),
                 ResultObj::Copy(Object::Boolean(b)) => b,
                 ResultObj::Copy(Object::Null) => false,
                 obj => {
@@ -875,7 +1559,7 @@ impl Evaluator {
             let condition_obj = self.eval_expression(condition_ref.borrow().clone(), env);
             condition_res = {
                 match condition_obj {
-                    ResultObj::Copy(Object::Numeric(numeric)) => numeric != Numeric::Int(0),
+                    ResultObj::Copy(Object::Numeric(numeric)) => !numeric.is_int_zero(),
                     ResultObj::Copy(Object::Boolean(b)) => b,
                     ResultObj::Copy(Object::Null) => false,
                     obj => {
@@ -884,9 +1568,7 @@ impl Evaluator {
                 }
             };
         }
-        if let Some(Context::Loop) = self.stack_ctx.back() {
-            self.stack_ctx.pop_back();
-        }
+        self.stack_ctx.truncate(ctx_len);
         ResultObj::Copy(Object::Void)
     }
 
@@ -901,25 +1583,25 @@ impl Evaluator {
             ResultObj::Copy(obj) => match obj {
                 Object::Numeric(Numeric::Int(int)) => Ok(int),
                 Object::Error(err) => Err(ResultObj::Copy(Object::Error(err))),
-                obj => Err(ResultObj::Copy(Object::Error(create_msg_err(
+                obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                     format!(
                         "No se soporta operaciones de rango con el tipo de dato `{}`",
                         obj.get_type()
                     ),
                     line,
                     col,
-                )))),
+                ))))),
             },
             ResultObj::Ref(obj) => {
                 let obj = obj.borrow();
-                Err(ResultObj::Copy(Object::Error(create_msg_err(
+                Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                     format!(
                         "No se soporta operaciones de rango con el tipo de dato `{}`",
                         obj.get_type()
                     ),
                     line,
                     col,
-                ))))
+                )))))
             }
         }
     }
@@ -934,12 +1616,13 @@ impl Evaluator {
         env: &RcEnvironment,
     ) -> ResultObj {
         if self.exist_var(&ident, env) {
-            return ResultObj::Copy(Object::Error(create_msg_err(
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 format!("Ya existe referencias hacia la variable `{}`", ident),
                 line,
                 col,
-            )));
+            ))));
         }
+        let ctx_len = self.stack_ctx.len();
         self.stack_ctx.push_back(Context::Loop);
 
         let iter_obj: ResultObj;
@@ -992,25 +1675,25 @@ impl Evaluator {
                 }
             }
             _ => {
-                return ResultObj::Copy(Object::Error(format!(
+                return ResultObj::Copy(Object::Error(Box::new(create_err(format!(
                     "Se encontro {} argumentos de 1..3",
                     arguments.len()
-                )));
+                )))));
             }
         }
 
         if end < 0 || steps < 0 {
-            return ResultObj::Copy(Object::Error(create_msg_err(
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 "No se puede hacer operaciones de rango con numeros negativos".into(),
                 line,
                 col,
-            )));
+            ))));
         } else if steps == 0 {
-            return ResultObj::Copy(Object::Error(create_msg_err(
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                 "Los pasos del rango debe ser mayor que 0".into(),
                 line,
                 col,
-            )));
+            ))));
         }
 
         // let body = Rc::new(RefCell::new(body));
@@ -1019,11 +1702,11 @@ impl Evaluator {
             ResultObj::Copy(obj) => match obj {
                 Object::Numeric(Numeric::Int(begin)) => {
                     if begin < 0 {
-                        return ResultObj::Copy(Object::Error(create_msg_err(
+                        return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                             "No se puede hacer operaciones de rango con numeros negativos".into(),
                             line,
                             col,
-                        )));
+                        ))));
                     }
                     let range = if end != 0 {
                         0..end as usize
@@ -1031,19 +1714,12 @@ impl Evaluator {
                         0..begin as usize
                     };
                     for i in range.step_by(steps as usize) {
+                        // La variable ya se reviso antes del bucle
                         let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
-                        let elem_obj = self.eval_var(
+                        scope_env.borrow_mut().declare(
                             &ident,
-                            Expression::new(
-                                ExprType::NumericLiteral(Numeric::Int(i as i64)),
-                                line,
-                                col + 1,
-                            ),
-                            &scope_env,
+                            ResultObj::Copy(Object::Numeric(Numeric::Int(i as i64))),
                         );
-                        if self.is_error(&elem_obj) {
-                            return elem_obj;
-                        }
                         let res_obj = self.eval_block_statement(*body.clone(), &scope_env);
                         if self.is_error(&res_obj) {
                             return res_obj;
@@ -1058,42 +1734,1219 @@ impl Evaluator {
                     }
                 }
                 obj => {
-                    return ResultObj::Copy(Object::Error(create_msg_err(
+                    return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                         format!(
                             "No se soporta operaciones de rango con el tipo de dato `{}`",
                             obj.get_type()
                         ),
                         line,
                         col,
-                    )))
+                    ))))
                 }
             },
             ResultObj::Ref(obj) => {
                 let obj = obj.borrow_mut().to_owned();
-                return ResultObj::Copy(Object::Error(create_msg_err(
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
                     format!(
                         "No se soporta operaciones de rango con el tipo de dato `{}`",
                         obj.get_type()
                     ),
                     line,
                     col,
-                )));
+                ))));
             }
         }
 
-        if let Some(Context::Loop) = self.stack_ctx.back() {
-            self.stack_ctx.pop_back();
+        self.stack_ctx.truncate(ctx_len);
+        ResultObj::Copy(Object::Void)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn eval_for(
+        &mut self,
+        ident: String,
+        init: Expression,
+        condition: Expression,
+        update: Expression,
+        body: BlockStatement,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        if self.exist_var(&ident, env) {
+            return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!("Ya existe referencias hacia la variable `{}`", ident),
+                line,
+                col,
+            ))));
+        }
+
+        // La variable del bucle solo existe dentro del bucle
+        let loop_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+        let init_obj = self.eval_var(&ident, init, &loop_env);
+        if self.is_error(&init_obj) {
+            return init_obj;
+        }
+
+        let ctx_len = self.stack_ctx.len();
+        self.stack_ctx.push_back(Context::Loop);
+        let obj = self.eval_for_body(condition, update, body, ctx_len, &loop_env);
+        self.stack_ctx.truncate(ctx_len);
+        obj
+    }
+
+    fn eval_for_body(
+        &mut self,
+        condition: Expression,
+        update: Expression,
+        body: BlockStatement,
+        ctx_len: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        loop {
+            let condition_obj = self.eval_expression(condition.clone(), env);
+            let condition_res = match for_condition(condition_obj, condition.line, condition.col) {
+                Ok(b) => b,
+                Err(err) => return err,
+            };
+            if !condition_res {
+                break;
+            }
+
+            let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+            let res_obj = self.eval_block_statement(body.clone(), &scope_env);
+            if self.is_error(&res_obj) {
+                return res_obj;
+            }
+            if let ResultObj::Copy(obj) = res_obj {
+                match obj {
+                    Object::Break => break,
+                    Object::Return(_) => return ResultObj::Copy(obj),
+                    _ => {}
+                }
+            }
+            // Descartar los contextos que dejo el cuerpo del bucle
+            self.stack_ctx.truncate(ctx_len + 1);
+
+            let update_obj = self.eval_expression(update.clone(), env);
+            if self.is_error(&update_obj) {
+                return update_obj;
+            }
         }
         ResultObj::Copy(Object::Void)
     }
+
+    fn eval_for_in(
+        &mut self,
+        idents: Vec<String>,
+        iterable: Expression,
+        body: BlockStatement,
+        line: usize,
+        col: usize,
+        env: &RcEnvironment,
+    ) -> ResultObj {
+        for ident in idents.iter() {
+            if self.exist_var(ident, env) {
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!("Ya existe referencias hacia la variable `{}`", ident),
+                    line,
+                    col,
+                ))));
+            }
+        }
+
+        let iterable_line = iterable.line;
+        let iterable_col = iterable.col;
+        let iterable_obj = match self.eval_expression(iterable, env) {
+            ResultObj::Copy(Object::Return(obj)) => *obj,
+            obj => obj,
+        };
+        if self.is_error(&iterable_obj) {
+            return iterable_obj;
+        }
+        let mut iter = match new_iterator(&iterable_obj) {
+            Some(iter) => iter,
+            None => {
+                return ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    format!(
+                        "No se puede iterar el tipo de dato {}",
+                        iterable_obj.get_type()
+                    ),
+                    iterable_line,
+                    iterable_col,
+                ))))
+            }
+        };
+
+        let locks_len = self.iter_locks.len();
+        if let ResultObj::Ref(ref obj) = iterable_obj {
+            self.iter_locks.push(obj.clone());
+        }
+        let ctx_len = self.stack_ctx.len();
+        self.stack_ctx.push_back(Context::Loop);
+
+        let mut res = ResultObj::Copy(Object::Void);
+        while let Some(item) = iter.next_item() {
+            let scope_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+            if idents.len() == 1 {
+                self.insert_obj(&idents[0], item.value, &scope_env);
+            } else {
+                self.insert_obj(&idents[0], item.pair.0, &scope_env);
+                self.insert_obj(&idents[1], item.pair.1, &scope_env);
+            }
+
+            let res_obj = self.eval_block_statement(body.clone(), &scope_env);
+            if self.is_error(&res_obj) {
+                res = res_obj;
+                break;
+            }
+            if let ResultObj::Copy(obj) = res_obj {
+                match obj {
+                    Object::Break => break,
+                    Object::Return(_) => {
+                        res = ResultObj::Copy(obj);
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            self.stack_ctx.truncate(ctx_len + 1);
+        }
+
+        self.stack_ctx.truncate(ctx_len);
+        self.iter_locks.truncate(locks_len);
+        res
+    }
 }
 
-pub fn create_msg_err(msg: String, line: usize, col: usize) -> String {
-    if msg.chars().nth(0).unwrap() == '^' {
-        return msg[1..].to_string();
+// Retorna un error si la coleccion esta siendo recorrida por un bucle.
+// `locks` son las colecciones de los bucles `para ... en` en curso
+pub fn check_iteration_lock(
+    locks: &[RcObject],
+    obj: &RcObject,
+    line: usize,
+    col: usize,
+) -> Option<ResultObj> {
+    if !locks.iter().any(|lock| Rc::ptr_eq(lock, obj)) {
+        return None;
+    }
+    Some(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+        format!(
+            "No se puede modificar el tipo de dato {} mientras se recorre con un bucle `para`",
+            obj.borrow().get_type()
+        ),
+        line,
+        col,
+    )))))
+}
+
+// Lee o, si hay `new_value`, asigna el elemento de una lista o diccionario
+#[allow(clippy::too_many_arguments)]
+pub fn eval_index_obj(
+    locks: &[RcObject],
+    left_obj: ResultObj,
+    index_obj: ResultObj,
+    new_value: Option<ResultObj>,
+    line: usize,
+    col: usize,
+    index_line: usize,
+    index_col: usize,
+) -> ResultObj {
+    match left_obj {
+        ResultObj::Copy(obj) => match obj {
+            Object::Error(msg) => ResultObj::Copy(Object::Error(msg)),
+            _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                "Solo se puede usar el operador de indexar en listas y dicccionarios".to_owned(),
+                line,
+                col,
+            )))),
+        },
+        ResultObj::Ref(obj) => {
+            if new_value.is_some() {
+                if let Some(err) = check_iteration_lock(locks, &obj, line, col) {
+                    return err;
+                }
+            }
+            // Se revisa antes de tomar el diccionario, la llave podria ser el mismo
+            if let Object::Dictionary(_) = *obj.borrow() {
+                if let Err(err) = check_dict_key(&index_obj, index_line, index_col) {
+                    return ResultObj::Copy(Object::Error(Box::new(err)));
+                }
+            }
+            match *obj.borrow_mut() {
+                Object::List(ref mut objs) => {
+                    if let ResultObj::Copy(Object::Numeric(Numeric::Int(index))) = index_obj {
+                        if let Some(new_value) = new_value {
+                            if (index as usize) < objs.len() {
+                                objs[index as usize] = new_value.clone();
+                                return new_value;
+                            }
+                            return ResultObj::Copy(Object::Null);
+                        }
+                        return match objs.get(index as usize) {
+                            Some(obj) => obj.clone(),
+                            None => ResultObj::Copy(Object::Null),
+                        };
+                    }
+                    ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                        "El operador de indexar solo opera con enteros".to_owned(),
+                        index_line,
+                        index_col,
+                    ))))
+                }
+                Object::Dictionary(ref mut pairs) => {
+                    if let Some(new_value) = new_value {
+                        pairs.insert(index_obj, new_value.clone());
+                        return new_value;
+                    }
+                    match pairs.get(&index_obj) {
+                        Some(obj) => obj.clone(),
+                        None => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                            format!("Llave invalida {}", index_obj.get_type()),
+                            index_line,
+                            index_col,
+                        )))),
+                    }
+                }
+                _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                    "Solo se puede usar el operador de indexar en listas y dicccionarios"
+                        .to_owned(),
+                    line,
+                    col,
+                )))),
+            }
+        }
     }
-    format!(
-        "Error de ejecución. {}. Linea {}, columna {}.",
-        msg, line, col
+}
+
+pub fn match_infix_ops(left: ResultObj, right: ResultObj, operator: TokenType) -> ResultObj {
+    match (left, right) {
+        // La operacion mas comun va primero
+        (ResultObj::Copy(Object::Numeric(a)), ResultObj::Copy(Object::Numeric(b))) => {
+            match eval_infix_numeric_operation(a, b, &operator) {
+                Some(obj) => obj,
+                None => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+                    "No se soporta operaciones numerico {} numerico",
+                    operator
+                ))))),
+            }
+        }
+        (err @ ResultObj::Copy(Object::Error(_)), _) => err,
+        (_, err @ ResultObj::Copy(Object::Error(_))) => err,
+        (ResultObj::Copy(Object::Return(a)), b) => match_infix_ops(*a, b, operator),
+        (a, ResultObj::Copy(Object::Return(b))) => match_infix_ops(a, *b, operator),
+        (a, b) => match eval_infix_operation(&a, &b, &operator) {
+            Some(obj) => obj,
+            None => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+                "No se soporta operaciones {} {} {}",
+                a.get_type(),
+                operator,
+                b.get_type()
+            ))))),
+        },
+    }
+}
+
+// Retorna None si los tipos de datos no soportan el operador
+fn eval_infix_operation(
+    left: &ResultObj,
+    right: &ResultObj,
+    operator: &TokenType,
+) -> Option<ResultObj> {
+    // Los valores logicos operan como los numeros 0 y 1
+    let as_numeric = |obj: &ResultObj| match obj {
+        ResultObj::Copy(Object::Numeric(n)) => Some(n.clone()),
+        ResultObj::Copy(Object::Boolean(b)) => Some(Numeric::Int(*b as i64)),
+        _ => None,
+    };
+    let is_equality = matches!(operator, TokenType::Eq | TokenType::NotEq);
+    let equality = |eq: bool| ResultObj::Copy(Object::Boolean(eq == (*operator == TokenType::Eq)));
+
+    match (left, right) {
+        (ResultObj::Copy(Object::Null), ResultObj::Copy(Object::Null)) => {
+            Some(eval_infix_null_operation(operator))
+        }
+        (ResultObj::Copy(Object::Null), _) | (_, ResultObj::Copy(Object::Null)) => {
+            Some(eval_infix_null_object_operation(operator))
+        }
+        (ResultObj::Ref(a), ResultObj::Ref(b)) => match (&*a.borrow(), &*b.borrow()) {
+            (Object::String(a), Object::String(b)) => eval_infix_string_operation(a, b, operator),
+            (Object::List(a), Object::List(b)) => eval_infix_list_operation(a, b, operator),
+            (Object::Dictionary(a), Object::Dictionary(b)) if is_equality => Some(equality(a == b)),
+            // Las instancias solo se comparan por identidad
+            (Object::Instance(_), Object::Instance(_)) if is_equality => {
+                Some(equality(Rc::ptr_eq(a, b)))
+            }
+            _ => None,
+        },
+        (ResultObj::Copy(Object::Numeric(a)), ResultObj::Ref(b)) => match &*b.borrow() {
+            Object::List(b) => eval_infix_list_int_operation(b, a, operator),
+            Object::String(b) => eval_infix_string_int_operation(b, a, operator),
+            _ => None,
+        },
+        (ResultObj::Ref(a), ResultObj::Copy(Object::Numeric(b))) => match &*a.borrow() {
+            Object::List(a) => eval_infix_list_int_operation(a, b, operator),
+            Object::String(a) => eval_infix_string_int_operation(a, b, operator),
+            _ => None,
+        },
+        // Las funciones solo se comparan por identidad
+        (ResultObj::Copy(a), ResultObj::Copy(b))
+            if a.get_type() == "funcion" && b.get_type() == "funcion" && is_equality =>
+        {
+            Some(equality(a == b))
+        }
+        (a, b) => match (as_numeric(a), as_numeric(b)) {
+            (Some(a), Some(b)) => eval_infix_numeric_operation(a, b, operator),
+            _ => None,
+        },
+    }
+}
+
+fn eval_infix_numeric_operation(a: Numeric, b: Numeric, op: &TokenType) -> Option<ResultObj> {
+    let obj = match op {
+        TokenType::Plus => Object::Numeric(a + b),
+        TokenType::Minus => Object::Numeric(a - b),
+        TokenType::Asterisk => Object::Numeric(a * b),
+        TokenType::Slash | TokenType::Percent => {
+            let result = if *op == TokenType::Slash {
+                a.checked_div(b)
+            } else {
+                a.checked_rem(b)
+            };
+            match result {
+                Some(numeric) => Object::Numeric(numeric),
+                None => Object::Error(Box::new(create_err(
+                    "No se puede dividir entre cero".to_owned(),
+                ))),
+            }
+        }
+        TokenType::Eq => Object::Boolean(a == b),
+        TokenType::NotEq => Object::Boolean(a != b),
+        TokenType::Lt => Object::Boolean(a < b),
+        TokenType::Gt => Object::Boolean(a > b),
+        TokenType::LtEq => Object::Boolean(a <= b),
+        TokenType::GtEq => Object::Boolean(a >= b),
+        _ => return None,
+    };
+    Some(ResultObj::Copy(obj))
+}
+
+fn eval_infix_string_operation(a: &String, b: &String, op: &TokenType) -> Option<ResultObj> {
+    match op {
+        TokenType::Plus => Some(ResultObj::Ref(new_rc_object(Object::String(format!(
+            "{}{}",
+            a, b
+        ))))),
+        TokenType::Eq => Some(ResultObj::Copy(Object::Boolean(a == b))),
+        TokenType::NotEq => Some(ResultObj::Copy(Object::Boolean(a != b))),
+        _ => None,
+    }
+}
+
+fn eval_infix_string_int_operation(a: &str, b: &Numeric, op: &TokenType) -> Option<ResultObj> {
+    if *op != TokenType::Asterisk {
+        return None;
+    }
+    if let Numeric::Int(int) = b {
+        return Some(ResultObj::Ref(new_rc_object(Object::String(
+            a.repeat((*int).max(0) as usize),
+        ))));
+    }
+    Some(ResultObj::Copy(Object::Error(Box::new(create_err(
+        "No se puede hacer operaciones de indexacion con numeros flotantes".to_owned(),
+    )))))
+}
+
+fn eval_infix_list_operation(
+    a: &Vec<ResultObj>,
+    b: &Vec<ResultObj>,
+    op: &TokenType,
+) -> Option<ResultObj> {
+    let obj = match op {
+        TokenType::Plus => {
+            return Some(ResultObj::Ref(new_rc_object(Object::List(
+                [a.as_slice(), b.as_slice()].concat(),
+            ))))
+        }
+        TokenType::Eq => Object::Boolean(a == b),
+        TokenType::NotEq => Object::Boolean(a != b),
+        TokenType::Lt => Object::Boolean(a.len() < b.len()),
+        TokenType::Gt => Object::Boolean(a.len() > b.len()),
+        TokenType::LtEq => Object::Boolean(a.len() <= b.len()),
+        TokenType::GtEq => Object::Boolean(a.len() >= b.len()),
+        _ => return None,
+    };
+    Some(ResultObj::Copy(obj))
+}
+
+fn eval_infix_list_int_operation(
+    a: &Vec<ResultObj>,
+    b: &Numeric,
+    op: &TokenType,
+) -> Option<ResultObj> {
+    if *op != TokenType::Asterisk {
+        return None;
+    }
+    if let Numeric::Int(int) = b {
+        let mut objs = Vec::with_capacity((*int).max(0) as usize);
+        for _ in 0..*int {
+            objs.extend(a.to_owned());
+        }
+        return Some(ResultObj::Ref(new_rc_object(Object::List(objs))));
+    }
+    Some(ResultObj::Copy(Object::Error(Box::new(create_err(
+        "No se puede hacer operaciones con numeros flotantes en listas".to_owned(),
+    )))))
+}
+
+fn eval_infix_null_operation(operator: &TokenType) -> ResultObj {
+    match operator {
+        TokenType::Eq => ResultObj::Copy(Object::Boolean(true)),
+        TokenType::NotEq => ResultObj::Copy(Object::Boolean(false)),
+        _ => ResultObj::Copy(Object::Error(Box::new(create_err(
+            "El objeto nulo solo puede hacer operacciones logicas de igualdad".to_owned(),
+        )))),
+    }
+}
+
+fn eval_infix_null_object_operation(operator: &TokenType) -> ResultObj {
+    match operator {
+        TokenType::Eq => ResultObj::Copy(Object::Boolean(false)),
+        TokenType::NotEq => ResultObj::Copy(Object::Boolean(true)),
+        _ => ResultObj::Copy(Object::Error(Box::new(create_err(
+            "El objeto nulo solo puede hacer operacciones logicas de igualdad".to_owned(),
+        )))),
+    }
+}
+
+pub fn eval_logical_operand(
+    obj: ResultObj,
+    operator: &TokenType,
+    line: usize,
+    col: usize,
+) -> Result<bool, ResultObj> {
+    match obj {
+        ResultObj::Copy(Object::Numeric(numeric)) => Ok(!numeric.is_int_zero()),
+        ResultObj::Copy(Object::Boolean(b)) => Ok(b),
+        ResultObj::Copy(Object::Null) => Ok(false),
+        ResultObj::Copy(Object::Return(obj)) => eval_logical_operand(*obj, operator, line, col),
+        ResultObj::Copy(Object::Error(msg)) => Err(ResultObj::Copy(Object::Error(msg))),
+        obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "No se soporta el operador logico `{}` con el tipo de dato {}",
+                operator,
+                obj.get_type()
+            ),
+            line,
+            col,
+        ))))),
+    }
+}
+
+// Agrega el archivo principal, el de `env`, al error con el que termino el
+// programa
+pub fn with_main_file(res_obj: ResultObj, env: &RcEnvironment) -> ResultObj {
+    match (res_obj, env.borrow().module_name()) {
+        (ResultObj::Copy(Object::Error(err)), Some(file)) => {
+            ResultObj::Copy(Object::Error(Box::new(err.with_file(&file))))
+        }
+        (res_obj, _) => res_obj,
+    }
+}
+
+pub fn eval_prefix_obj(operator: &TokenType, right: ResultObj) -> ResultObj {
+    match operator {
+        TokenType::Plus => right,
+        TokenType::Minus => match right {
+            ResultObj::Copy(Object::Numeric(numeric)) => ResultObj::Copy(Object::Numeric(-numeric)),
+            ResultObj::Copy(Object::Boolean(b)) => {
+                ResultObj::Copy(Object::Numeric(Numeric::Int(-(b as i64))))
+            }
+            _ => ResultObj::Copy(Object::Null),
+        },
+        TokenType::Bang | TokenType::Not => match right {
+            ResultObj::Copy(Object::Numeric(int)) => {
+                ResultObj::Copy(Object::Boolean(int.is_int_zero()))
+            }
+            ResultObj::Copy(Object::Boolean(b)) => ResultObj::Copy(Object::Boolean(!b)),
+            ResultObj::Copy(Object::Null) => ResultObj::Copy(Object::Boolean(true)),
+            _ => ResultObj::Copy(Object::Null),
+        },
+        _ => ResultObj::Copy(Object::Null),
+    }
+}
+
+// Convierte el valor de `lanzar` en el error que se propaga
+pub fn throw_obj(obj: ResultObj, line: usize, col: usize) -> ResultObj {
+    match obj {
+        // Relanzar un error capturado conserva su posicion original
+        ResultObj::Copy(Object::CaughtError(err)) => ResultObj::Copy(Object::Error(err)),
+        ResultObj::Ref(ref string) => match &*string.borrow() {
+            Object::String(msg) => ResultObj::Copy(Object::Error(Box::new(create_typed_err(
+                ERR_USUARIO,
+                msg.clone(),
+                line,
+                col,
+            )))),
+            obj => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+                format!(
+                    "Solo se puede lanzar cadenas o errores, no el tipo de dato {}",
+                    obj.get_type()
+                ),
+                line,
+                col,
+            )))),
+        },
+        obj => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "Solo se puede lanzar cadenas o errores, no el tipo de dato {}",
+                obj.get_type()
+            ),
+            line,
+            col,
+        )))),
+    }
+}
+
+// Nombre con el que se declara un modulo importado, por defecto el nombre del archivo
+pub fn import_name(
+    path: &str,
+    alias: Option<String>,
+    line: usize,
+    col: usize,
+) -> Result<String, ResultObj> {
+    if let Some(alias) = alias {
+        return Ok(alias);
+    }
+    let stem = Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let valid = stem.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && stem.chars().next().is_some_and(|c| !c.is_numeric());
+    if !valid {
+        return Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "No se puede usar `{}` como nombre del modulo, usa `importar \"{}\" como nombre`",
+                stem, path
+            ),
+            line,
+            col,
+        )))));
+    }
+    Ok(stem)
+}
+
+// Miembros de un error capturado: e.mensaje, e.linea, e.columna, e.tipo y e.archivo
+pub fn eval_error_member(err: &ErrorObj, name: &str, line: usize, col: usize) -> ResultObj {
+    match name {
+        "mensaje" => ResultObj::Ref(new_rc_object(Object::String(err.mensaje.clone()))),
+        "tipo" => ResultObj::Ref(new_rc_object(Object::String(err.tipo.clone()))),
+        "linea" => ResultObj::Copy(Object::Numeric(Numeric::Int(err.linea as i64))),
+        "columna" => ResultObj::Copy(Object::Numeric(Numeric::Int(err.columna as i64))),
+        "archivo" => match &err.archivo {
+            Some(file) => ResultObj::Ref(new_rc_object(Object::String(file.clone()))),
+            None => ResultObj::Copy(Object::Null),
+        },
+        _ => ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!("El tipo de dato error no posee el miembro `{}`", name),
+            line,
+            col,
+        )))),
+    }
+}
+
+pub fn is_instance(obj: &ResultObj) -> bool {
+    match obj {
+        ResultObj::Ref(obj) => matches!(*obj.borrow(), Object::Instance(_)),
+        ResultObj::Copy(_) => false,
+    }
+}
+
+// La condicion de `para` debe ser un valor logico
+pub fn for_condition(obj: ResultObj, line: usize, col: usize) -> Result<bool, ResultObj> {
+    match obj {
+        ResultObj::Copy(Object::Boolean(b)) => Ok(b),
+        ResultObj::Copy(Object::Error(_)) => Err(obj),
+        obj => Err(ResultObj::Copy(Object::Error(Box::new(create_msg_err(
+            format!(
+                "La condicion de `para` debe ser un valor logico, no {}",
+                obj.get_type()
+            ),
+            line,
+            col,
+        ))))),
+    }
+}
+
+// Si el valor es una instancia o una lista, diccionario o instancia que
+// contiene alguna
+pub fn contains_instance(obj: &ResultObj) -> bool {
+    let ResultObj::Ref(rc) = obj else {
+        return false;
+    };
+    match &*rc.borrow() {
+        Object::Instance(_) => true,
+        Object::List(items) => items.iter().any(contains_instance),
+        Object::Dictionary(pairs) => pairs
+            .iter()
+            .any(|(key, value)| contains_instance(key) || contains_instance(value)),
+        _ => false,
+    }
+}
+
+pub fn new_instance(class: &Rc<ClassObj>) -> ResultObj {
+    ResultObj::Ref(new_rc_object(Object::Instance(Box::new(InstanceObj {
+        class: class.clone(),
+        fields: IndexMap::new(),
+    }))))
+}
+
+// Crea una copia del metodo cuyo environment tiene a `este` y, si `class`
+// hereda, a `super`. Las funciones del evaluador los buscan en los primeros
+// slots y las compiladas por nombre
+pub fn bind_method(method: &ResultObj, instance: &ResultObj, class: &ClassObj) -> ResultObj {
+    let mut vars = vec![("este", instance.clone())];
+    if let Some(parent) = &class.parent {
+        vars.push(("super", ResultObj::Copy(Object::Class(parent.clone()))));
+    }
+    match method {
+        ResultObj::Copy(Object::Fn(func)) => {
+            let mut env = Environment::new(Some(func.env.clone()));
+            for (name, value) in vars {
+                env.declare(name, value);
+            }
+            let mut func = func.clone();
+            func.env = Rc::new(RefCell::new(env));
+            ResultObj::Copy(Object::Fn(func))
+        }
+        ResultObj::Copy(Object::CompiledFn(func)) => {
+            let mut env = Environment::new(Some(func.env.clone()));
+            for (name, value) in vars {
+                env.declare(name, value);
+            }
+            let mut func = func.clone();
+            func.env = Rc::new(RefCell::new(env));
+            ResultObj::Copy(Object::CompiledFn(func))
+        }
+        _ => unreachable!("Los metodos siempre son funciones"),
+    }
+}
+
+pub fn parent_class(obj: ResultObj, line: usize, col: usize) -> Result<Rc<ClassObj>, ErrorObj> {
+    match obj {
+        ResultObj::Copy(Object::Class(class)) => Ok(class),
+        obj => Err(create_msg_err(
+            format!(
+                "Una clase solo puede heredar de otra clase, no de {}",
+                obj.get_type()
+            ),
+            line,
+            col,
+        )),
+    }
+}
+
+// Indica todas las clases en las que se busco el miembro
+fn missing_member_err(class: &ClassObj, name: &str, line: usize, col: usize) -> ErrorObj {
+    let chain = class
+        .chain()
+        .iter()
+        .map(|name| format!("`{}`", name))
+        .collect::<Vec<String>>()
+        .join(" ni en ");
+    create_msg_err(
+        format!("No se encontro el miembro `{}` en la clase {}", name, chain),
+        line,
+        col,
     )
 }
+
+// `super.metodo`: `parent` es la clase padre de la que declara el metodo actual
+pub fn super_method(
+    parent: &ResultObj,
+    instance: &ResultObj,
+    name: &str,
+    line: usize,
+    col: usize,
+) -> Result<ResultObj, ErrorObj> {
+    let ResultObj::Copy(Object::Class(parent)) = parent else {
+        unreachable!("`super` siempre es una clase");
+    };
+    match parent.find_method(name) {
+        Some((owner, method)) => Ok(bind_method(&method, instance, &owner)),
+        None => Err(missing_member_err(parent, name, line, col)),
+    }
+}
+
+// Busca primero en los campos de la instancia y despues en los metodos de su
+// clase y de sus clases padre
+pub fn instance_member(
+    instance: &ResultObj,
+    name: &str,
+    line: usize,
+    col: usize,
+) -> Result<ResultObj, ErrorObj> {
+    let ResultObj::Ref(obj) = instance else {
+        unreachable!("Las instancias siempre son referencias");
+    };
+    let class = match &*obj.borrow() {
+        Object::Instance(obj) => match obj.fields.get(name) {
+            Some(field) => return Ok(field.clone()),
+            None => obj.class.clone(),
+        },
+        _ => unreachable!("El objeto no es una instancia"),
+    };
+    match class.find_method(name) {
+        Some((owner, method)) => Ok(bind_method(&method, instance, &owner)),
+        None => Err(missing_member_err(&class, name, line, col)),
+    }
+}
+
+// Metodo especial de la clase de una instancia, ya unido a ella
+fn special_method(obj: &ResultObj, name: &str) -> Option<ResultObj> {
+    let ResultObj::Ref(rc) = obj else {
+        return None;
+    };
+    let class = match &*rc.borrow() {
+        Object::Instance(instance) => instance.class.clone(),
+        _ => return None,
+    };
+    class
+        .find_method(name)
+        .map(|(owner, method)| bind_method(&method, obj, &owner))
+}
+
+// Metodos especiales que implementan los operadores, `!=` usa `igual`
+fn operator_method_name(operator: &TokenType) -> Option<&'static str> {
+    match operator {
+        TokenType::Plus => Some("sumar"),
+        TokenType::Minus => Some("restar"),
+        TokenType::Asterisk => Some("multiplicar"),
+        TokenType::Slash => Some("dividir"),
+        TokenType::Percent => Some("modulo"),
+        TokenType::Eq | TokenType::NotEq => Some("igual"),
+        TokenType::Lt => Some("menor"),
+        TokenType::Gt => Some("mayor"),
+        TokenType::LtEq => Some("menor_igual"),
+        TokenType::GtEq => Some("mayor_igual"),
+        _ => None,
+    }
+}
+
+// Si el operando izquierdo es una instancia cuya clase define el metodo del
+// operador, lo llama con el operando derecho. None si no lo define
+pub fn call_operator(
+    left: &ResultObj,
+    right: &ResultObj,
+    operator: &TokenType,
+    call: &mut CallFn,
+) -> Option<ResultObj> {
+    let method = special_method(left, operator_method_name(operator)?)?;
+    let obj = call(method, vec![right.clone()]);
+    if *operator != TokenType::NotEq {
+        return Some(obj);
+    }
+    Some(match obj {
+        ResultObj::Copy(Object::Boolean(eq)) => ResultObj::Copy(Object::Boolean(!eq)),
+        err @ ResultObj::Copy(Object::Error(_)) => err,
+        obj => ResultObj::Copy(Object::Error(Box::new(create_err(format!(
+            "El metodo `igual` debe retornar un valor logico, no {}",
+            obj.get_type()
+        ))))),
+    })
+}
+
+// Texto con el que se muestra un objeto. Las instancias pueden definirlo con
+// el metodo `texto`, tambien dentro de listas, diccionarios y campos. Los
+// demas objetos usan `Display`
+pub fn obj_text(
+    obj: &ResultObj,
+    line: usize,
+    col: usize,
+    call: &mut CallFn,
+) -> Result<String, ErrorObj> {
+    let Some(method) = special_method(obj, "texto") else {
+        return contents_text(obj, line, col, call);
+    };
+    match call(method, Vec::new()) {
+        ResultObj::Copy(Object::Error(err)) => Err(*err),
+        ResultObj::Ref(rc) if matches!(*rc.borrow(), Object::String(_)) => {
+            Ok(rc.borrow().to_string())
+        }
+        obj => Err(create_msg_err(
+            format!(
+                "El metodo `texto` debe retornar una cadena, no {}",
+                obj.get_type()
+            ),
+            line,
+            col,
+        )),
+    }
+}
+
+// Igual que `Display`, pero cada elemento se muestra con `obj_text`. Los
+// elementos se copian antes, ya que `texto` puede modificar la coleccion
+fn contents_text(
+    obj: &ResultObj,
+    line: usize,
+    col: usize,
+    call: &mut CallFn,
+) -> Result<String, ErrorObj> {
+    let ResultObj::Ref(rc) = obj else {
+        return Ok(obj.to_string());
+    };
+    let contents = rc.borrow().clone();
+    match contents {
+        Object::List(items) => {
+            let mut texts = Vec::with_capacity(items.len());
+            for item in &items {
+                texts.push(obj_text(item, line, col, call)?);
+            }
+            Ok(format!("[{}]", texts.join(", ")))
+        }
+        Object::Dictionary(pairs) => {
+            let mut texts = Vec::with_capacity(pairs.len());
+            for (key, value) in &pairs {
+                let key = obj_text(key, line, col, call)?;
+                texts.push(format!("{}: {}", key, obj_text(value, line, col, call)?));
+            }
+            Ok(format!("{{{}}}", texts.join(", ")))
+        }
+        Object::Instance(instance) => {
+            let mut texts = Vec::with_capacity(instance.fields.len());
+            for (name, value) in &instance.fields {
+                texts.push(format!("{}: {}", name, obj_text(value, line, col, call)?));
+            }
+            Ok(format!("{} {{{}}}", instance.class.name, texts.join(", ")))
+        }
+        contents => Ok(contents.to_string()),
+    }
+}
+
+// Guarda un campo en la instancia, solo las instancias pueden tener campos
+pub fn set_member(
+    target: &ResultObj,
+    name: &str,
+    value: ResultObj,
+    line: usize,
+    col: usize,
+) -> Result<ResultObj, ErrorObj> {
+    if let ResultObj::Ref(obj) = target {
+        if let Object::Instance(instance) = &mut *obj.borrow_mut() {
+            instance.fields.insert(name.to_owned(), value.clone());
+            return Ok(value);
+        }
+    }
+    Err(create_msg_err(
+        format!(
+            "No se puede asignar el miembro `{}` al tipo de dato {}",
+            name,
+            target.get_type()
+        ),
+        line,
+        col,
+    ))
+}
+
+// Las listas y diccionarios pueden cambiar despues de insertarse, por eso
+// no se aceptan como llaves
+fn literal_obj(literal: &ExprType) -> ResultObj {
+    match literal {
+        ExprType::NumericLiteral(numeric) => ResultObj::Copy(Object::Numeric(numeric.clone())),
+        ExprType::StringLiteral(string) => {
+            ResultObj::Ref(new_rc_object(Object::String(string.clone())))
+        }
+        ExprType::BooleanLiteral(b) => ResultObj::Copy(Object::Boolean(*b)),
+        ExprType::NullLiteral => ResultObj::Copy(Object::Null),
+        _ => unreachable!("Los patrones solo tienen literales simples"),
+    }
+}
+
+// Las variables del primer patron que coincide con el valor, en el orden
+// en que las declara el resolver
+pub fn match_case(patterns: &[Pattern], obj: &ResultObj) -> Option<Vec<(String, ResultObj)>> {
+    patterns.iter().find_map(|pattern| {
+        let mut bindings = Vec::new();
+        match_pattern(pattern, obj, &mut bindings).then_some(bindings)
+    })
+}
+
+fn match_pattern(
+    pattern: &Pattern,
+    obj: &ResultObj,
+    bindings: &mut Vec<(String, ResultObj)>,
+) -> bool {
+    match pattern {
+        Pattern::Wildcard => true,
+        Pattern::Binding(name) => {
+            bindings.push((name.clone(), obj.clone()));
+            true
+        }
+        // Sin conversiones, `verdad` no coincide con `1`
+        Pattern::Literal(literal) => *obj == literal_obj(&literal.r#type),
+        Pattern::List(patterns) => {
+            let ResultObj::Ref(list) = obj else {
+                return false;
+            };
+            match &*list.borrow() {
+                Object::List(elements) => {
+                    elements.len() == patterns.len()
+                        && patterns
+                            .iter()
+                            .zip(elements)
+                            .all(|(pattern, element)| match_pattern(pattern, element, bindings))
+                }
+                _ => false,
+            }
+        }
+        Pattern::Dictionary(pairs) => {
+            let ResultObj::Ref(dict) = obj else {
+                return false;
+            };
+            match &*dict.borrow() {
+                Object::Dictionary(dict) => {
+                    pairs
+                        .iter()
+                        .all(|(key, pattern)| match dict.get(&literal_obj(&key.r#type)) {
+                            Some(value) => match_pattern(pattern, value, bindings),
+                            None => false,
+                        })
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+pub fn check_dict_key(key: &ResultObj, line: usize, col: usize) -> Result<(), ErrorObj> {
+    if let ResultObj::Ref(obj) = key {
+        if let Object::List(_) | Object::Dictionary(_) = *obj.borrow() {
+            return Err(create_msg_err(
+                format!(
+                    "No se puede usar el tipo de dato `{}` como llave de un diccionario",
+                    obj.borrow().get_type()
+                ),
+                line,
+                col,
+            ));
+        }
+    }
+    Ok(())
+}
+
+pub fn repeated_key_err(key: &ResultObj, line: usize, col: usize) -> ErrorObj {
+    create_msg_err(
+        format!("La llave `{}` esta repetida en el diccionario", key),
+        line,
+        col,
+    )
+}
+
+// Como recibe sus argumentos cada parametro de una funcion
+#[derive(Clone, Copy, PartialEq)]
+pub enum ParamKind {
+    Required,
+    Optional,
+    Rest,
+}
+
+// Argumento que le toca a cada parametro. `Missing` es un parametro
+// opcional que no se paso
+pub enum BoundArg<T> {
+    Value(T),
+    Missing,
+    Rest(Vec<T>),
+}
+
+pub fn fn_param_kinds(params: &[Param]) -> Vec<(&str, ParamKind)> {
+    params
+        .iter()
+        .map(|param| {
+            let kind = match (param.rest, &param.default) {
+                (true, _) => ParamKind::Rest,
+                (false, Some(_)) => ParamKind::Optional,
+                (false, None) => ParamKind::Required,
+            };
+            (param.name.as_str(), kind)
+        })
+        .collect()
+}
+
+pub fn buildin_param_kinds(params: &[BuildinParam]) -> Vec<(&str, ParamKind)> {
+    params
+        .iter()
+        .map(|param| {
+            let kind = match (param.rest, &param.default) {
+                (true, _) => ParamKind::Rest,
+                (false, Some(_)) => ParamKind::Optional,
+                (false, None) => ParamKind::Required,
+            };
+            (param.name, kind)
+        })
+        .collect()
+}
+
+// Firma de una funcion para los mensajes de error, ej. `f(a, b = 2)`
+pub fn fn_signature(name: Option<&str>, params: &[Param]) -> String {
+    format!("{}({})", name.unwrap_or("fn"), format_params(params))
+}
+
+// Separa el nombre de un argumento con nombre, ej. `b: 3`
+pub fn split_named_arg(arg: Expression) -> (Option<String>, Expression) {
+    match arg.r#type {
+        ExprType::NamedArgument { name, value } => (Some(name), *value),
+        _ => (None, arg),
+    }
+}
+
+pub fn named_arg_err(line: usize, col: usize) -> ErrorObj {
+    create_msg_err(
+        "Los argumentos con nombre solo se pueden usar al llamar funciones".to_owned(),
+        line,
+        col,
+    )
+}
+
+// Asigna los argumentos, en el orden de la llamada, a los parametros de una
+// funcion. Los que no tienen nombre van primero, por su posicion
+pub fn bind_args<T>(
+    params: &[(&str, ParamKind)],
+    args: Vec<(Option<String>, T)>,
+    signature: &dyn Fn() -> String,
+    line: usize,
+    col: usize,
+) -> Result<Vec<BoundArg<T>>, ErrorObj> {
+    let err =
+        |msg: String| create_msg_err(format!("{}, se espera {}", msg, signature()), line, col);
+    let argc = args.len();
+    let fixed = params
+        .iter()
+        .take_while(|(_, kind)| *kind != ParamKind::Rest)
+        .count();
+    let mut values: Vec<Option<T>> = params.iter().map(|_| None).collect();
+    let mut rest = Vec::new();
+    for (i, (name, value)) in args.into_iter().enumerate() {
+        let Some(name) = name else {
+            if i < fixed {
+                values[i] = Some(value);
+            } else if fixed < params.len() {
+                rest.push(value);
+            } else {
+                return Err(err(format!("Se encontro {} argumentos", argc)));
+            }
+            continue;
+        };
+        let Some(index) = params.iter().position(|(param, _)| *param == name) else {
+            return Err(err(format!("No existe el parametro `{}`", name)));
+        };
+        if params[index].1 == ParamKind::Rest {
+            return Err(err(format!(
+                "El parametro `...{}` no puede recibir argumentos con nombre",
+                name
+            )));
+        }
+        if values[index].is_some() {
+            return Err(err(format!(
+                "El argumento `{}` se paso mas de una vez",
+                name
+            )));
+        }
+        values[index] = Some(value);
+    }
+
+    let mut bound = Vec::with_capacity(params.len());
+    for ((name, kind), value) in params.iter().zip(values) {
+        bound.push(match (kind, value) {
+            (ParamKind::Rest, _) => BoundArg::Rest(std::mem::take(&mut rest)),
+            (_, Some(value)) => BoundArg::Value(value),
+            (ParamKind::Optional, None) => BoundArg::Missing,
+            (ParamKind::Required, None) => {
+                return Err(err(format!("Falta el argumento `{}`", name)));
+            }
+        });
+    }
+    Ok(bound)
+}
+
+// Llama a una funcion interna. `call` llama a las funciones de Pana que reciba,
+// ej. la de `texto` de una instancia que se imprime
+pub fn call_buildin(
+    f: &BuildinFnObj,
+    args: Vec<ResultObj>,
+    site: &CallSite,
+    call: &mut Caller,
+) -> ResultObj {
+    let (line, col) = (site.line, site.col);
+    let params = buildin_param_kinds(&f.params);
+    let signature = || format!("{}({})", f.name, format_buildin_params(&f.params));
+    let args = site
+        .names
+        .iter()
+        .cloned()
+        .zip(args.into_iter().zip(site.args.iter().copied()))
+        .collect();
+    let bound = match bind_args(&params, args, &signature, line, col) {
+        Ok(bound) => bound,
+        Err(err) => return ResultObj::Copy(Object::Error(Box::new(err))),
+    };
+    let mut args = Vec::new();
+    for (arg, param) in bound.into_iter().zip(f.params.iter()) {
+        match arg {
+            BoundArg::Value(arg) => args.push(arg),
+            BoundArg::Rest(rest) => args.extend(rest),
+            BoundArg::Missing => {
+                let default = param.default.clone();
+                let default = default.expect("El parametro opcional tiene valor");
+                args.push((ResultObj::Copy(default), (line, col)));
+            }
+        }
+    }
+    let (values, positions): (Vec<_>, Vec<_>) = args.into_iter().unzip();
+    match (f.func)(call, values, &positions) {
+        // Las funciones internas no conocen la posicion de la llamada
+        ResultObj::Copy(Object::Error(err)) => {
+            ResultObj::Copy(Object::Error(Box::new(err.with_position(line, col))))
+        }
+        obj => obj,
+    }
+}
+
+pub fn max_depth_err(max_depth: usize, line: usize, col: usize) -> ErrorObj {
+    create_msg_err(
+        format!(
+            "profundidad máxima de recursión excedida, se permiten {} llamadas anidadas",
+            max_depth
+        ),
+        line,
+        col,
+    )
+}
+
+pub fn create_msg_err(msg: String, line: usize, col: usize) -> ErrorObj {
+    create_typed_err(ERR_EJECUCION, msg, line, col)
+}
+
+pub fn create_typed_err(tipo: &str, msg: String, line: usize, col: usize) -> ErrorObj {
+    ErrorObj {
+        tipo: tipo.to_owned(),
+        mensaje: msg,
+        linea: line,
+        columna: col,
+        archivo: None,
+        salida: None,
+    }
+}
+
+// Termina el programa con el codigo dado
+pub fn exit_signal(code: i32) -> ErrorObj {
+    ErrorObj {
+        salida: Some(code),
+        ..create_err(format!("El programa termino con el codigo {}", code))
+    }
+}
+
+// Error sin posicion, la toma de quien lo recibe
+pub fn create_err(msg: String) -> ErrorObj {
+    create_msg_err(msg, 0, 0)
+}
@@ -0,0 +1,102 @@
+use crate::types::Numeric;
+
+use super::objects::{new_rc_object, Object, RcObject, ResultObj};
+
+// Lo que produce cada vuelta de un bucle `para ... en`. `value` se usa
+// cuando el bucle declara una variable y `pair` cuando declara dos
+pub struct IterItem {
+    pub value: ResultObj,
+    pub pair: (ResultObj, ResultObj),
+}
+
+// Protocolo de iteracion, cualquier objeto que quiera ser recorrido por
+// un bucle `para` tiene que implementarlo y registrarse en `new_iterator`
+pub trait PanaIterator {
+    fn next_item(&mut self) -> Option<IterItem>;
+}
+
+// lista: `para x en l` da los elementos y `para i, x en l` los indices y elementos
+struct ListIterator {
+    list: RcObject,
+    index: usize,
+}
+
+impl PanaIterator for ListIterator {
+    fn next_item(&mut self) -> Option<IterItem> {
+        let obj = match &*self.list.borrow() {
+            Object::List(objs) => objs.get(self.index).cloned(),
+            _ => None,
+        }?;
+        let index = ResultObj::Copy(Object::Numeric(Numeric::Int(self.index as i64)));
+        self.index += 1;
+        Some(IterItem {
+            value: obj.clone(),
+            pair: (index, obj),
+        })
+    }
+}
+
+// diccionario: `para k en d` da las llaves y `para k, v en d` las llaves y valores
+struct DictionaryIterator {
+    dict: RcObject,
+    keys: Vec<ResultObj>,
+    index: usize,
+}
+
+impl PanaIterator for DictionaryIterator {
+    fn next_item(&mut self) -> Option<IterItem> {
+        let key = self.keys.get(self.index)?.clone();
+        let value = match &*self.dict.borrow() {
+            Object::Dictionary(pairs) => pairs.get(&key).cloned(),
+            _ => None,
+        }?;
+        self.index += 1;
+        Some(IterItem {
+            value: key.clone(),
+            pair: (key, value),
+        })
+    }
+}
+
+// cadena: `para c en s` da los caracteres y `para i, c en s` los indices y caracteres
+struct StringIterator {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl PanaIterator for StringIterator {
+    fn next_item(&mut self) -> Option<IterItem> {
+        let c = self.chars.get(self.index)?;
+        let obj = ResultObj::Ref(new_rc_object(Object::String(c.to_string())));
+        let index = ResultObj::Copy(Object::Numeric(Numeric::Int(self.index as i64)));
+        self.index += 1;
+        Some(IterItem {
+            value: obj.clone(),
+            pair: (index, obj),
+        })
+    }
+}
+
+// Retorna None si el objeto no se puede iterar
+pub fn new_iterator(obj: &ResultObj) -> Option<Box<dyn PanaIterator>> {
+    let ResultObj::Ref(rc_obj) = obj else {
+        return None;
+    };
+    match &*rc_obj.borrow() {
+        Object::List(_) => Some(Box::new(ListIterator {
+            list: rc_obj.clone(),
+            index: 0,
+        })),
+        #[allow(clippy::mutable_key_type)]
+        Object::Dictionary(pairs) => Some(Box::new(DictionaryIterator {
+            dict: rc_obj.clone(),
+            keys: pairs.keys().cloned().collect(),
+            index: 0,
+        })),
+        Object::String(string) => Some(Box::new(StringIterator {
+            chars: string.chars().collect(),
+            index: 0,
+        })),
+        _ => None,
+    }
+}
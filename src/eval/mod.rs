@@ -1,3 +1,5 @@
 pub mod environment;
 pub mod evaluator;
+pub mod iterator;
 pub mod objects;
+pub mod resolver;
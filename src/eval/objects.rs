@@ -1,12 +1,15 @@
 use std::{cell::RefCell, collections::HashMap, fmt::Display, hash::Hash, rc::Rc};
 
+use indexmap::IndexMap;
+
 use crate::{
     buildins::internal::InternalFnPointer,
     parser::{
-        expression::{format_arguments, FnParams},
+        expression::{format_params, Param},
         statement::BlockStatement,
     },
     types::Numeric,
+    vm::bytecode::{CallSite, FnProto},
 };
 
 use super::environment::Environment;
@@ -18,37 +21,231 @@ pub fn new_rc_object(obj: Object) -> RcObject {
 
 #[derive(Clone)]
 pub struct FnExprObj {
-    pub params: FnParams,
-    pub body: BlockStatement,
+    pub params: Vec<Param>,
+    // Compartido entre copias de la funcion, asi se puede comparar por identidad
+    pub body: Rc<BlockStatement>,
     pub env: Rc<RefCell<Environment>>,
 }
 
+// Llamada en posicion de cola, la funcion que retorna la ejecuta en su lugar
+#[derive(Clone)]
+pub struct TailCallObj {
+    pub callee: ResultObj,
+    pub args: Vec<ResultObj>,
+    pub site: CallSite,
+}
+
 #[derive(Clone)]
 pub struct FnObj {
     pub name: String,
-    pub params: FnParams,
+    pub params: Vec<Param>,
     pub body: BlockStatement,
     pub env: Rc<RefCell<Environment>>,
 }
 
+// Funcion compilada a bytecode, la crea la maquina virtual
+#[derive(Clone)]
+pub struct CompiledFnObj {
+    pub proto: Rc<FnProto>,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+// Parametro de una funcion interna: `x`, `x = valor` o `...x`, igual que
+// los parametros de las funciones de Pana
+#[derive(Clone)]
+pub struct BuildinParam {
+    pub name: &'static str,
+    // Lo recibe la funcion si no se paso el argumento, solo valores que se
+    // copian como numeros, logicos o nulo
+    pub default: Option<Object>,
+    pub rest: bool,
+}
+
+impl BuildinParam {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            default: None,
+            rest: false,
+        }
+    }
+
+    pub fn optional(name: &'static str, default: Object) -> Self {
+        Self {
+            name,
+            default: Some(default),
+            rest: false,
+        }
+    }
+
+    pub fn rest(name: &'static str) -> Self {
+        Self {
+            name,
+            default: None,
+            rest: true,
+        }
+    }
+}
+
+impl Display for BuildinParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.default, self.rest) {
+            (_, true) => write!(f, "...{}", self.name),
+            (Some(default), false) => write!(f, "{} = {}", self.name, default),
+            (None, false) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+pub fn format_buildin_params(params: &[BuildinParam]) -> String {
+    params
+        .iter()
+        .map(|param| param.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[derive(Clone)]
 pub struct BuildinFnObj {
     pub name: String,
+    pub params: Rc<[BuildinParam]>,
     pub func: Box<dyn InternalFnPointer>,
 }
 
+// Tipos de error, accesibles desde Pana con `e.tipo`
+pub const ERR_EJECUCION: &str = "ejecucion";
+pub const ERR_SINTAXIS: &str = "sintaxis";
+pub const ERR_DOMINIO: &str = "dominio";
+pub const ERR_ARCHIVO: &str = "archivo";
+pub const ERR_MODULO: &str = "modulo";
+pub const ERR_USUARIO: &str = "usuario";
+
+// Un error de ejecucion. Si la linea es 0 aun no tiene posicion, y la
+// toma quien lo reciba (por ejemplo, la llamada de una funcion interna)
+#[derive(Clone, PartialEq, Debug)]
+pub struct ErrorObj {
+    pub tipo: String,
+    pub mensaje: String,
+    pub linea: usize,
+    pub columna: usize,
+    pub archivo: Option<String>,
+    // Codigo de `sis.salir`. Sube como un error para que corran los bloques
+    // `finalmente`, pero `capturar` no lo atrapa
+    pub salida: Option<i32>,
+}
+
+impl ErrorObj {
+    pub fn has_position(&self) -> bool {
+        self.linea != 0
+    }
+
+    pub fn with_position(mut self, line: usize, col: usize) -> Self {
+        if !self.has_position() {
+            self.linea = line;
+            self.columna = col;
+        }
+        self
+    }
+
+    // Agrega el archivo en el que ocurrio el error, si aun no lo tiene
+    pub fn with_file(mut self, file: &str) -> Self {
+        if self.archivo.is_none() {
+            self.archivo = Some(file.to_owned());
+        }
+        self
+    }
+}
+
+impl Display for ErrorObj {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.tipo == ERR_SINTAXIS {
+            write!(f, "Error de sintaxis: {}.", self.mensaje)?;
+        } else {
+            write!(f, "Error de ejecución. {}.", self.mensaje)?;
+        }
+        if self.has_position() {
+            write!(f, " Linea {}, columna {}.", self.linea, self.columna)?;
+        }
+        if let Some(file) = &self.archivo {
+            write!(f, " Archivo \"{}\".", file)?;
+        }
+        Ok(())
+    }
+}
+
+// Clase declarada con `clase`. Los metodos son funciones normales, al
+// leerse desde una instancia se les agrega `este`
+pub struct ClassObj {
+    pub name: String,
+    // Clase de la que hereda, con `hereda`
+    pub parent: Option<Rc<ClassObj>>,
+    pub methods: HashMap<String, ResultObj>,
+}
+
+impl ClassObj {
+    // Busca el metodo subiendo por las clases padre. Retorna tambien la clase
+    // que lo declara, para saber a que apunta `super` dentro del metodo
+    pub fn find_method(self: &Rc<Self>, name: &str) -> Option<(Rc<ClassObj>, ResultObj)> {
+        let mut class = self.clone();
+        loop {
+            if let Some(method) = class.methods.get(name).cloned() {
+                return Some((class, method));
+            }
+            class = class.parent.clone()?;
+        }
+    }
+
+    // Verdadero si la clase es `other` o alguna de sus clases padre lo es
+    pub fn inherits(&self, other: &ClassObj) -> bool {
+        std::ptr::eq(self, other)
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.inherits(other))
+    }
+
+    // Nombres de la clase y de sus clases padre, en orden
+    pub fn chain(&self) -> Vec<String> {
+        let mut names = vec![self.name.clone()];
+        if let Some(parent) = &self.parent {
+            names.extend(parent.chain());
+        }
+        names
+    }
+}
+
+#[derive(Clone)]
+pub struct InstanceObj {
+    pub class: Rc<ClassObj>,
+    pub fields: IndexMap<String, ResultObj>,
+}
+
+#[derive(Clone)]
+pub struct ModuleObj {
+    pub name: String,
+    pub env: Rc<RefCell<Environment>>,
+}
+
 #[derive(Clone)]
 pub enum Object {
     Numeric(Numeric),
     Boolean(bool),
-    Error(String),
+    Error(Box<ErrorObj>),
+    // Error capturado por `capturar`, es un valor y no se propaga
+    CaughtError(Box<ErrorObj>),
     String(String),
     Return(Box<ResultObj>),
+    TailCall(Box<TailCallObj>),
     List(Vec<ResultObj>),
-    Dictionary(HashMap<ResultObj, ResultObj>),
+    // Conserva el orden en que se insertaron las llaves
+    Dictionary(IndexMap<ResultObj, ResultObj>),
     FnExpr(Box<FnExprObj>),
     Fn(Box<FnObj>),
     BuildinFn(Box<BuildinFnObj>),
+    CompiledFn(Box<CompiledFnObj>),
+    Module(Box<ModuleObj>),
+    Class(Rc<ClassObj>),
+    Instance(Box<InstanceObj>),
     Void,
     Break,
     Continue,
@@ -63,17 +260,49 @@ impl PartialEq for Object {
             (Self::Numeric(l0), Self::Numeric(r0)) => l0 == r0,
             (Self::Boolean(l0), Self::Boolean(r0)) => l0 == r0,
             (Self::Error(l0), Self::Error(r0)) => l0 == r0,
+            (Self::CaughtError(l0), Self::CaughtError(r0)) => l0 == r0,
             (Self::String(l0), Self::String(r0)) => l0 == r0,
-            (Self::Return(_), Self::Return(_)) => panic!("No se peude comparar un return"),
+            (Self::Return(l0), Self::Return(r0)) => l0 == r0,
             (Self::List(l0), Self::List(r0)) => l0 == r0,
-            (Self::FnExpr { .. }, Self::FnExpr { .. }) => panic!("No se puede comparar funciones"),
+            (Self::Dictionary(l0), Self::Dictionary(r0)) => {
+                l0.len() == r0.len() && l0.iter().all(|(key, value)| r0.get(key) == Some(value))
+            }
+            (Self::FnExpr(l_obj), Self::FnExpr(r_obj)) => {
+                Rc::ptr_eq(&l_obj.body, &r_obj.body) && Rc::ptr_eq(&l_obj.env, &r_obj.env)
+            }
             (Self::Fn(l_obj), Self::Fn(r_obj)) => l_obj.name == r_obj.name,
             (Self::BuildinFn(l_obj), Self::BuildinFn(r_obj)) => l_obj.name == r_obj.name,
+            (Self::CompiledFn(l_obj), Self::CompiledFn(r_obj)) => {
+                match (&l_obj.proto.name, &r_obj.proto.name) {
+                    (Some(l_name), Some(r_name)) => l_name == r_name,
+                    _ => {
+                        Rc::ptr_eq(&l_obj.proto, &r_obj.proto) && Rc::ptr_eq(&l_obj.env, &r_obj.env)
+                    }
+                }
+            }
+            (Self::Module(l_obj), Self::Module(r_obj)) => Rc::ptr_eq(&l_obj.env, &r_obj.env),
+            (Self::Class(l_obj), Self::Class(r_obj)) => Rc::ptr_eq(l_obj, r_obj),
+            // Dos instancias solo son iguales si son la misma
+            (Self::Instance(_), Self::Instance(_)) => false,
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
 }
 
+// Solo los valores inmutables se usan como llave, el resto se distingue
+// unicamente por su tipo
+impl Hash for Object {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Object::Numeric(numeric) => numeric.hash(state),
+            Object::Boolean(boolean) => boolean.hash(state),
+            Object::String(string) => string.hash(state),
+            _ => {}
+        }
+    }
+}
+
 impl Object {
     pub fn get_type(&self) -> String {
         match self {
@@ -82,22 +311,21 @@ impl Object {
             }
             Object::Boolean(_) => "logico".to_owned(),
             Object::Error(_) => "error".to_owned(),
+            Object::CaughtError(_) => "error".to_owned(),
             Object::String(_) => "cadena".to_owned(),
-            Object::Return(obj) => {
-                match obj.as_ref() {
-                    ResultObj::Copy(obj) => obj.get_type(),
-                    ResultObj::Ref(_) => todo!(),
-                    // let a = obj.borrow();
-                    // a.get_type()
-                }
-            }
+            Object::Return(obj) => obj.get_type(),
             Object::FnExpr { .. } => "funcion".to_owned(),
             Object::Fn { .. } => "funcion".to_owned(),
             Object::BuildinFn { .. } => "funcion".to_owned(),
+            Object::CompiledFn { .. } => "funcion".to_owned(),
+            Object::Module { .. } => "modulo".to_owned(),
+            Object::Class(_) => "clase".to_owned(),
+            Object::Instance(obj) => obj.class.name.clone(),
             Object::Null => "nulo".to_owned(),
             Object::Void => "vacio".to_owned(),
             Object::List(_) => "lista".to_owned(),
             Object::Dictionary { .. } => "diccionario".to_owned(),
+            Object::TailCall(_) => unreachable!(),
             Object::Break => unreachable!(),
             Object::Continue => unreachable!(),
         }
@@ -110,18 +338,42 @@ impl Display for Object {
             Object::Numeric(int) => write!(f, "{}", int),
             Object::Boolean(b) => write!(f, "{}", bool_to_spanish(*b)),
             Object::Null => write!(f, "nulo"),
-            Object::Error(msg) => write!(f, "{}", msg),
+            Object::Error(err) => write!(f, "{}", err),
+            Object::CaughtError(err) => write!(f, "{}", err),
             Object::Return(obj) => write!(f, "{}", obj),
             Object::Fn(obj) => {
+                write!(f, "fn {}({}) {{...}}", obj.name, format_params(&obj.params))
+            }
+            Object::FnExpr(obj) => write!(f, "fn({}) {{...}}", format_params(&obj.params)),
+            Object::BuildinFn(obj) => {
                 write!(
                     f,
                     "fn {}({}) {{...}}",
                     obj.name,
-                    format_arguments(&obj.params)
+                    format_buildin_params(&obj.params)
                 )
             }
-            Object::FnExpr(obj) => write!(f, "fn({}) {{...}}", format_arguments(&obj.params)),
-            Object::BuildinFn(obj) => write!(f, "fn {}(...) {{...}}", obj.name),
+            Object::CompiledFn(obj) => match &obj.proto.name {
+                Some(name) => write!(
+                    f,
+                    "fn {}({}) {{...}}",
+                    name,
+                    format_params(&obj.proto.params)
+                ),
+                None => write!(f, "fn({}) {{...}}", format_params(&obj.proto.params)),
+            },
+            Object::Module(obj) => write!(f, "modulo \"{}\"", obj.name),
+            Object::Class(obj) => write!(f, "clase {}", obj.name),
+            Object::Instance(obj) => write!(
+                f,
+                "{} {{{}}}",
+                obj.class.name,
+                obj.fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
             Object::String(string) => write!(f, "{}", string),
             Object::Void => write!(f, ""),
             Object::List(objs) => write!(
@@ -141,6 +393,7 @@ impl Display for Object {
                     .collect::<Vec<String>>()
                     .join(", ")
             ),
+            Object::TailCall(_) => unreachable!(),
             Object::Break => unreachable!(),
             Object::Continue => unreachable!(),
         }
@@ -177,7 +430,10 @@ impl ResultObj {
 
 impl Hash for ResultObj {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        core::mem::discriminant(self).hash(state);
+        match self {
+            ResultObj::Copy(obj) => obj.hash(state),
+            ResultObj::Ref(obj) => obj.borrow().hash(state),
+        }
     }
 }
 
@@ -187,7 +443,8 @@ impl PartialEq for ResultObj {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Copy(l0), Self::Copy(r0)) => l0 == r0,
-            (Self::Ref(l0), Self::Ref(r0)) => l0 == r0,
+            // Una lista que se contiene a si misma no debe compararse sin fin
+            (Self::Ref(l0), Self::Ref(r0)) => Rc::ptr_eq(l0, r0) || l0 == r0,
             _ => false,
         }
     }
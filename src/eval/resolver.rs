@@ -0,0 +1,508 @@
+use crate::parser::{
+    expression::{ExprType, Expression, Param},
+    statement::{BlockStatement, Statement},
+};
+
+use super::{
+    environment::RcEnvironment,
+    evaluator::{create_typed_err, import_name},
+    objects::{ErrorObj, ERR_SINTAXIS},
+};
+
+// Cada scope es un environment que crea el evaluador (bloques, vueltas de
+// bucles, llamadas, intentar/capturar/finalmente). Las variables se guardan
+// en el environment en orden de declaracion, asi su posicion se conoce antes
+// de ejecutar el programa
+struct Scope {
+    // Todas las variables que declara el scope, en orden
+    names: Vec<String>,
+    // Cuantas de `names` ya fueron declaradas en el punto actual
+    declared: usize,
+}
+
+impl Scope {
+    fn new(names: Vec<String>) -> Self {
+        Self { names, declared: 0 }
+    }
+
+    fn find_declared(&self, name: &str) -> Option<usize> {
+        self.names[..self.declared].iter().position(|n| n == name)
+    }
+
+    fn find_pending(&self, name: &str) -> Option<usize> {
+        self.names[self.declared..]
+            .iter()
+            .position(|n| n == name)
+            .map(|slot| slot + self.declared)
+    }
+}
+
+// Convierte los identificadores de variables locales en `ExprType::Local` y
+// los de variables globales en `ExprType::Global`. Las funciones internas y
+// los nombres que no se declaran se siguen buscando por nombre
+struct Resolver {
+    scopes: Vec<Scope>,
+    // Primer scope de la funcion que se esta resolviendo
+    fn_start: usize,
+    // Variables declaradas en el nivel superior del programa
+    globals: Vec<String>,
+    // Environment raiz del programa o modulo, guarda la posicion de las globales
+    env: RcEnvironment,
+}
+
+pub fn resolve(program: &mut BlockStatement, env: &RcEnvironment) -> Result<(), ErrorObj> {
+    // Las globales del programa tienen posicion antes de declararse, asi las
+    // funciones pueden usar las que se declaran despues de ellas
+    for name in block_names(program) {
+        env.borrow_mut().reserve_global(&name);
+    }
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        fn_start: 0,
+        globals: Vec::new(),
+        env: env.clone(),
+    };
+    resolver.resolve_statements(program)
+}
+
+type ResolveResult = Result<(), ErrorObj>;
+
+fn resolve_err(msg: String, line: usize, col: usize) -> ErrorObj {
+    create_typed_err(ERR_SINTAXIS, msg, line, col)
+}
+
+// Nombres que una lista de sentencias declara en su propio scope
+fn block_names(block: &BlockStatement) -> Vec<String> {
+    let mut names = Vec::new();
+    for stmt in block {
+        match stmt {
+            Statement::Var { name, .. }
+            | Statement::Fn { name, .. }
+            | Statement::Class { name, .. } => names.push(name.clone()),
+            Statement::Import {
+                path,
+                alias,
+                line,
+                col,
+            } => {
+                if let Ok(name) = import_name(path, alias.clone(), *line, *col) {
+                    names.push(name);
+                }
+            }
+            Statement::FromImport {
+                names: imported, ..
+            } => names.extend(imported.iter().cloned()),
+            _ => {}
+        }
+    }
+    names
+}
+
+impl Resolver {
+    // Declara `name` en el scope actual, no puede ocultar otra variable
+    // local ni repetir una global del mismo programa
+    fn declare(&mut self, name: &str, line: usize, col: usize) -> ResolveResult {
+        let exists = match self.scopes.is_empty() {
+            true => self.globals.iter().any(|n| n == name),
+            false => self
+                .scopes
+                .iter()
+                .any(|scope| scope.find_declared(name).is_some()),
+        };
+        if exists {
+            return Err(resolve_err(
+                format!("El identificador `{}` ya habia sido declarado", name),
+                line,
+                col,
+            ));
+        }
+        self.bind(name);
+        Ok(())
+    }
+
+    // Declara sin revisar, como hace el evaluador con parametros y errores capturados
+    fn bind(&mut self, name: &str) {
+        match self.scopes.last_mut() {
+            Some(scope) => {
+                debug_assert_eq!(scope.names[scope.declared], name);
+                scope.declared += 1;
+            }
+            None => self.globals.push(name.to_owned()),
+        }
+    }
+
+    // Las variables de los bucles no pueden ocultar otra variable local
+    fn declare_loop_var(&mut self, name: &str, line: usize, col: usize) -> ResolveResult {
+        if self
+            .scopes
+            .iter()
+            .any(|scope| scope.find_declared(name).is_some())
+        {
+            return Err(resolve_err(
+                format!("Ya existe referencias hacia la variable `{}`", name),
+                line,
+                col,
+            ));
+        }
+        self.declare(name, line, col)
+    }
+
+    // Retorna la distancia y posicion de una variable local, o None si es global
+    fn lookup(
+        &self,
+        name: &str,
+        line: usize,
+        col: usize,
+    ) -> Result<Option<(usize, usize)>, ErrorObj> {
+        for (i, scope) in self.scopes.iter().enumerate().rev() {
+            let depth = self.scopes.len() - 1 - i;
+            if let Some(slot) = scope.find_declared(name) {
+                return Ok(Some((depth, slot)));
+            }
+            if let Some(slot) = scope.find_pending(name) {
+                // Una funcion puede usar variables que se declaran despues de
+                // ella, si se llama cuando ya existen
+                if i < self.fn_start {
+                    return Ok(Some((depth, slot)));
+                }
+                return Err(resolve_err(
+                    format!("El identificador `{}` se usa antes de ser declarado", name),
+                    line,
+                    col,
+                ));
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_identifier(&self, expr: &mut Expression) -> ResolveResult {
+        let ExprType::Identifier(ref name) = expr.r#type else {
+            return Ok(());
+        };
+        match self.lookup(name, expr.line, expr.col)? {
+            Some((depth, slot)) => {
+                expr.r#type = ExprType::Local {
+                    name: name.as_str().into(),
+                    depth,
+                    slot,
+                };
+                Ok(())
+            }
+            None if name == "este" => Err(resolve_err(
+                "`este` solo se puede usar dentro de los metodos de una clase".to_owned(),
+                expr.line,
+                expr.col,
+            )),
+            None if name == "super" => Err(resolve_err(
+                "`super` solo se puede usar dentro de los metodos de una clase que hereda"
+                    .to_owned(),
+                expr.line,
+                expr.col,
+            )),
+            // Las globales de programas anteriores (ej. en el REPL) tambien
+            // tienen posicion
+            None => {
+                if let Some(slot) = self.env.borrow().global_slot(name) {
+                    expr.r#type = ExprType::Global {
+                        name: name.as_str().into(),
+                        slot,
+                    };
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn with_scope(
+        &mut self,
+        names: Vec<String>,
+        f: impl FnOnce(&mut Self) -> ResolveResult,
+    ) -> ResolveResult {
+        self.scopes.push(Scope::new(names));
+        let res = f(self);
+        self.scopes.pop();
+        res
+    }
+
+    // Bloque con su propio environment
+    fn resolve_block(&mut self, block: &mut BlockStatement) -> ResolveResult {
+        let names = block_names(block);
+        self.with_scope(names, |resolver| resolver.resolve_statements(block))
+    }
+
+    fn resolve_statements(&mut self, block: &mut BlockStatement) -> ResolveResult {
+        for stmt in block.iter_mut() {
+            self.resolve_statement(stmt)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_statement(&mut self, stmt: &mut Statement) -> ResolveResult {
+        match stmt {
+            Statement::Var { name, value } => {
+                self.resolve_expression(value)?;
+                self.declare(name, value.line, value.col)
+            }
+            Statement::Return(expr, ..) | Statement::Throw(expr, ..) => {
+                self.resolve_expression(expr)
+            }
+            Statement::Expression(expr) => self.resolve_expression(expr),
+            Statement::Break(..) | Statement::Continue(..) => Ok(()),
+            Statement::Fn {
+                name,
+                params,
+                body,
+                line,
+                col,
+            } => {
+                self.declare(name, *line, *col)?;
+                self.resolve_fn(params, body)
+            }
+            Statement::Try {
+                body,
+                catch_ident,
+                catch_body,
+                finally_body,
+                ..
+            } => {
+                self.resolve_block(body)?;
+                if let Some(catch_body) = catch_body {
+                    let mut names: Vec<String> = catch_ident.iter().cloned().collect();
+                    names.extend(block_names(catch_body));
+                    self.with_scope(names, |resolver| {
+                        if let Some(ident) = catch_ident {
+                            resolver.bind(ident);
+                        }
+                        resolver.resolve_statements(catch_body)
+                    })?;
+                }
+                if let Some(finally_body) = finally_body {
+                    self.resolve_block(finally_body)?;
+                }
+                Ok(())
+            }
+            Statement::Import {
+                path,
+                alias,
+                line,
+                col,
+            } => match import_name(path, alias.clone(), *line, *col) {
+                Ok(name) => self.declare(&name, *line, *col),
+                // El error se reporta al ejecutar la importacion
+                Err(_) => Ok(()),
+            },
+            Statement::FromImport {
+                names, line, col, ..
+            } => {
+                for name in names.iter() {
+                    self.declare(name, *line, *col)?;
+                }
+                Ok(())
+            }
+            Statement::Class {
+                name,
+                parent,
+                methods,
+                line,
+                col,
+            } => {
+                if let Some(parent) = parent {
+                    self.resolve_expression(parent)?;
+                }
+                self.declare(name, *line, *col)?;
+                // Cada metodo se llama en un environment que solo tiene a
+                // `este` y, si la clase hereda, a `super`
+                let mut names = vec!["este".to_owned()];
+                if parent.is_some() {
+                    names.push("super".to_owned());
+                }
+                for method in methods.iter_mut() {
+                    self.with_scope(names.clone(), |resolver| {
+                        for name in names.iter() {
+                            resolver.bind(name);
+                        }
+                        resolver.resolve_fn(&mut method.params, &mut method.body)
+                    })?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // El cuerpo de una funcion comparte el environment con sus parametros
+    fn resolve_fn(&mut self, params: &mut [Param], body: &mut BlockStatement) -> ResolveResult {
+        let mut names: Vec<String> = params.iter().map(|param| param.name.clone()).collect();
+        names.extend(block_names(body));
+
+        let fn_start = std::mem::replace(&mut self.fn_start, self.scopes.len());
+        let res = self.with_scope(names, |resolver| {
+            // El valor por defecto puede usar los parametros anteriores
+            for param in params.iter_mut() {
+                let scope = resolver.scopes.last().expect("La funcion tiene scope");
+                if scope.find_declared(&param.name).is_some() {
+                    return Err(resolve_err(
+                        format!("El parametro `{}` esta repetido", param.name),
+                        param.line,
+                        param.col,
+                    ));
+                }
+                if let Some(default) = &mut param.default {
+                    resolver.resolve_expression(default)?;
+                }
+                resolver.bind(&param.name);
+            }
+            resolver.resolve_statements(body)
+        });
+        self.fn_start = fn_start;
+        res
+    }
+
+    fn resolve_expressions(&mut self, exprs: &mut [Expression]) -> ResolveResult {
+        for expr in exprs.iter_mut() {
+            self.resolve_expression(expr)?;
+        }
+        Ok(())
+    }
+
+    fn resolve_expression(&mut self, expr: &mut Expression) -> ResolveResult {
+        let (line, col) = (expr.line, expr.col);
+        match &mut expr.r#type {
+            ExprType::Identifier(_) => self.resolve_identifier(expr),
+            ExprType::Local { .. }
+            | ExprType::Global { .. }
+            | ExprType::NumericLiteral(_)
+            | ExprType::BooleanLiteral(_)
+            | ExprType::StringLiteral(_)
+            | ExprType::NullLiteral => Ok(()),
+            ExprType::InterpolatedString { parts } => self.resolve_expressions(parts),
+            ExprType::FnLiteral { params, body } => self.resolve_fn(params, body),
+            ExprType::ListLiteral { elements } => self.resolve_expressions(elements),
+            ExprType::DictionaryLiteral { pairs } => {
+                for (key, value) in pairs {
+                    self.resolve_expression(key)?;
+                    self.resolve_expression(value)?;
+                }
+                Ok(())
+            }
+            ExprType::Index { left, index } => {
+                self.resolve_expression(left)?;
+                self.resolve_expression(index)
+            }
+            ExprType::Prefix { right, .. } => self.resolve_expression(right),
+            ExprType::Infix {
+                left,
+                right,
+                operator,
+            } => {
+                self.resolve_expression(left)?;
+                if *operator != crate::token::TokenType::Dot {
+                    return self.resolve_expression(right);
+                }
+                // El miembro se busca en el objeto, solo los argumentos son variables
+                match &mut right.r#type {
+                    ExprType::Identifier(_) => Ok(()),
+                    ExprType::Call { arguments, .. } => self.resolve_expressions(arguments),
+                    _ => self.resolve_expression(right),
+                }
+            }
+            ExprType::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.resolve_expression(condition)?;
+                self.resolve_block(consequence)?;
+                self.resolve_block(alternative)
+            }
+            ExprType::While { condition, body } => {
+                self.resolve_expression(condition)?;
+                self.resolve_block(body)
+            }
+            ExprType::Call {
+                function,
+                arguments,
+            } => {
+                self.resolve_expression(function)?;
+                self.resolve_expressions(arguments)
+            }
+            ExprType::NamedArgument { value, .. } => self.resolve_expression(value),
+            ExprType::Assignment { left, right } => {
+                self.resolve_expression(left)?;
+                self.resolve_expression(right)
+            }
+            ExprType::ForRange {
+                ident,
+                arguments,
+                body,
+            } => {
+                self.resolve_expressions(arguments)?;
+                let mut names = vec![ident.clone()];
+                names.extend(block_names(body));
+                self.with_scope(names, |resolver| {
+                    resolver.declare_loop_var(ident, line, col)?;
+                    resolver.resolve_statements(body)
+                })
+            }
+            ExprType::ForIn {
+                idents,
+                iterable,
+                body,
+            } => {
+                self.resolve_expression(iterable)?;
+                let mut names = idents.clone();
+                names.extend(block_names(body));
+                self.with_scope(names, |resolver| {
+                    for ident in idents.iter() {
+                        resolver.declare_loop_var(ident, line, col)?;
+                    }
+                    resolver.resolve_statements(body)
+                })
+            }
+            ExprType::For {
+                ident,
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                // La variable vive en su propio environment y el cuerpo en otro
+                self.with_scope(vec![ident.clone()], |resolver| {
+                    resolver.resolve_expression(init)?;
+                    resolver.declare_loop_var(ident, line, col)?;
+                    resolver.resolve_expression(condition)?;
+                    resolver.resolve_expression(update)?;
+                    resolver.resolve_block(body)
+                })
+            }
+            ExprType::Match { value, cases } => {
+                self.resolve_expression(value)?;
+                for case in cases.iter_mut() {
+                    // Las variables de los patrones van antes que las del cuerpo
+                    let mut bindings = Vec::new();
+                    for pattern in case.patterns.iter() {
+                        pattern.bindings(&mut bindings);
+                    }
+                    let mut names = bindings.clone();
+                    names.extend(block_names(&case.body));
+                    let (line, col) = (case.line, case.col);
+                    self.with_scope(names, |resolver| {
+                        for name in bindings.iter() {
+                            resolver.declare_loop_var(name, line, col)?;
+                        }
+                        if let Some(guard) = &mut case.guard {
+                            resolver.resolve_expression(guard)?;
+                        }
+                        resolver.resolve_statements(&mut case.body)
+                    })?;
+                }
+                Ok(())
+            }
+            ExprType::Super {
+                instance, parent, ..
+            } => {
+                self.resolve_expression(parent)?;
+                self.resolve_expression(instance)
+            }
+        }
+    }
+}
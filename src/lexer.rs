@@ -1,7 +1,7 @@
 use crate::types::Numeric;
 use regex::Regex;
 
-use crate::token::{keywords_to_tokens, Token, TokenType};
+use crate::token::{keywords_to_tokens, StringPart, Token, TokenType};
 
 #[derive(PartialEq)]
 pub enum NumericType {
@@ -37,6 +37,15 @@ impl Lexer {
         }
     }
 
+    // Lexer que empieza en otra posicion del archivo, para las expresiones
+    // dentro de las cadenas interpoladas
+    pub fn new_at(input: Vec<char>, line: usize, col: usize) -> Self {
+        let mut lexer = Self::new(input);
+        lexer.line = line;
+        lexer.col = col;
+        lexer
+    }
+
     fn read_char(&mut self) {
         if let Some(c) = self.input.get(self.read_pos) {
             self.current_char = *c;
@@ -44,8 +53,9 @@ impl Lexer {
             self.current_char = '\0';
         }
 
+        // input es un vector de caracteres, no de bytes
         self.current_pos = self.read_pos;
-        self.read_pos += char::len_utf8(self.current_char);
+        self.read_pos += 1;
         self.col += 1;
     }
 
@@ -53,6 +63,10 @@ impl Lexer {
         self.input.get(self.read_pos)
     }
 
+    fn peek_nth_char(&self, n: usize) -> Option<&char> {
+        self.input.get(self.read_pos + n)
+    }
+
     fn skip_whitespace(&mut self) {
         while self.current_char == ' ' || self.current_char == '\t' || self.current_char == '\r'
         // || self.current_char == '\n'
@@ -175,13 +189,10 @@ impl Lexer {
         let mut token = Token::new(TokenType::Numeric(Numeric::Int(0)), self.line, self.col);
 
         token.r#type = match state {
-            NumericType::Integers => TokenType::Numeric(Numeric::Int(
-                self.input[start..end]
-                    .iter()
-                    .collect::<String>()
-                    .parse()
-                    .unwrap(),
-            )),
+            // Los enteros que no caben en i64 se leen como BigInt
+            NumericType::Integers => TokenType::Numeric(
+                Numeric::parse_int(&self.input[start..end].iter().collect::<String>(), 10).unwrap(),
+            ),
             NumericType::Floats => TokenType::Numeric(Numeric::Float(
                 self.input[start..end]
                     .iter()
@@ -190,40 +201,40 @@ impl Lexer {
                     .unwrap(),
             )),
             NumericType::Hexadecimal => {
-                if let Ok(int) = i64::from_str_radix(
+                if let Some(int) = Numeric::parse_int(
                     self.input[start..end]
                         .iter()
                         .collect::<String>()
                         .trim_start_matches("0x"),
                     16,
                 ) {
-                    TokenType::Numeric(Numeric::Int(int))
+                    TokenType::Numeric(int)
                 } else {
                     TokenType::IllegalMsg("Formato de numero invalido".to_owned())
                 }
             }
             NumericType::Octadecimal => {
-                if let Ok(int) = i64::from_str_radix(
+                if let Some(int) = Numeric::parse_int(
                     self.input[start..end]
                         .iter()
                         .collect::<String>()
                         .trim_start_matches("0o"),
                     8,
                 ) {
-                    TokenType::Numeric(Numeric::Int(int))
+                    TokenType::Numeric(int)
                 } else {
                     TokenType::IllegalMsg("Formato de numero invalido".to_owned())
                 }
             }
             NumericType::Binary => {
-                if let Ok(int) = i64::from_str_radix(
+                if let Some(int) = Numeric::parse_int(
                     self.input[start..end]
                         .iter()
                         .collect::<String>()
                         .trim_start_matches("0b"),
                     2,
                 ) {
-                    TokenType::Numeric(Numeric::Int(int))
+                    TokenType::Numeric(int)
                 } else {
                     TokenType::Numeric(Numeric::Int(1))
                 }
@@ -245,13 +256,7 @@ impl Lexer {
             end += 1;
         }
 
-        let str_size: usize = self.input[start..end]
-            .iter()
-            .fold(0, |c1, c2| c1 + char::len_utf8(*c2));
-
-        let ident = &self.input[start..start + str_size]
-            .iter()
-            .collect::<String>();
+        let ident = &self.input[start..end].iter().collect::<String>();
         if self.identifier_regex.is_match(ident) {
             return Token::new(keywords_to_tokens(ident), self.line, self.col);
         }
@@ -265,26 +270,247 @@ impl Lexer {
         )
     }
 
-    fn read_string(&mut self) -> Token {
-        let start = self.current_pos;
-        let mut end = start + 1;
+    // "cadena", """cadena multilinea""", r"cadena cruda" y r"""cruda multilinea""".
+    // Las cadenas interpoladas f"hola {nombre}" se separan en partes
+    fn read_string(&mut self, raw: bool, interpolated: bool) -> Token {
+        let multiline = self.peek_char() == Some(&'"') && self.peek_nth_char(1) == Some(&'"');
+        if multiline {
+            self.read_char();
+            self.read_char();
+        }
 
-        while self.current_char != '\0' {
+        let mut string = String::new();
+        let mut parts = Vec::new();
+        loop {
             self.read_char();
-            if self.current_char == '"' {
-                return Token::new(
-                    TokenType::String(self.input[start + 1..end].iter().collect::<String>()),
-                    self.line,
-                    self.col,
-                );
+            match self.current_char {
+                '{' if interpolated => {
+                    if !string.is_empty() {
+                        parts.push(StringPart::Literal(std::mem::take(&mut string)));
+                    }
+                    match self.read_interpolation(multiline) {
+                        Ok(part) => parts.push(part),
+                        Err(token) => return token,
+                    }
+                }
+                '}' if interpolated => {
+                    return Token::new(
+                        TokenType::IllegalMsg(
+                            "Usa `\\}` para escribir `}` en una cadena interpolada".to_owned(),
+                        ),
+                        self.line,
+                        self.col,
+                    );
+                }
+                '\0' => {
+                    let delimiter = if multiline { "\"\"\"" } else { "\"" };
+                    return Token::new(
+                        TokenType::IllegalMsg(format!(
+                            "Falta el simbolo `{}` para delimitar la cadena",
+                            delimiter
+                        )),
+                        self.line,
+                        self.col,
+                    );
+                }
+                '"' => {
+                    if !multiline {
+                        break;
+                    }
+                    if self.peek_char() == Some(&'"') && self.peek_nth_char(1) == Some(&'"') {
+                        self.read_char();
+                        self.read_char();
+                        break;
+                    }
+                    string.push('"');
+                }
+                '\n' => {
+                    if !multiline {
+                        return Token::new(
+                            TokenType::IllegalMsg(
+                                "Las cadenas de una linea no pueden tener saltos de linea, usa `\\n` o `\"\"\"`".to_owned(),
+                            ),
+                            self.line,
+                            self.col,
+                        );
+                    }
+                    string.push('\n');
+                    self.line += 1;
+                    self.col = 0;
+                }
+                '\\' if !raw => match self.read_escape() {
+                    Ok(c) => string.push(c),
+                    Err(token) => return token,
+                },
+                c => string.push(c),
             }
-            end += 1;
         }
-        Token::new(
-            TokenType::IllegalMsg("Falta el simbolo `\"` para delimitar la cadena".to_string()),
-            self.line,
-            self.col,
-        )
+        if interpolated {
+            if !string.is_empty() {
+                parts.push(StringPart::Literal(string));
+            }
+            return Token::new(TokenType::InterpolatedString(parts), self.line, self.col);
+        }
+        Token::new(TokenType::String(string), self.line, self.col)
+    }
+
+    // Lee el codigo entre `{` y `}` de una cadena interpolada, el parser
+    // se encarga de convertirlo en una expresion
+    fn read_interpolation(&mut self, multiline: bool) -> Result<StringPart, Token> {
+        let line = self.line;
+        let col = self.col;
+        let mut source = String::new();
+        let mut depth = 0;
+        loop {
+            self.read_char();
+            match self.current_char {
+                '\0' => {
+                    return Err(Token::new(
+                        TokenType::IllegalMsg(
+                            "Falta el simbolo `}` para cerrar la interpolacion".to_owned(),
+                        ),
+                        self.line,
+                        self.col,
+                    ))
+                }
+                '{' => {
+                    depth += 1;
+                    source.push('{');
+                }
+                '}' => {
+                    if depth == 0 {
+                        break;
+                    }
+                    depth -= 1;
+                    source.push('}');
+                }
+                '\n' => {
+                    if !multiline {
+                        return Err(Token::new(
+                            TokenType::IllegalMsg(
+                                "Falta el simbolo `}` para cerrar la interpolacion".to_owned(),
+                            ),
+                            self.line,
+                            self.col,
+                        ));
+                    }
+                    source.push('\n');
+                    self.line += 1;
+                    self.col = 0;
+                }
+                // Las cadenas dentro de la interpolacion pueden tener `{`, `}` y `\"`
+                '"' => {
+                    source.push('"');
+                    loop {
+                        self.read_char();
+                        match self.current_char {
+                            '\0' | '\n' => {
+                                return Err(Token::new(
+                                    TokenType::IllegalMsg(
+                                        "Falta el simbolo `\"` para delimitar la cadena".to_owned(),
+                                    ),
+                                    self.line,
+                                    self.col,
+                                ))
+                            }
+                            '"' => {
+                                source.push('"');
+                                break;
+                            }
+                            '\\' => {
+                                source.push('\\');
+                                self.read_char();
+                                source.push(self.current_char);
+                            }
+                            c => source.push(c),
+                        }
+                    }
+                }
+                c => source.push(c),
+            }
+        }
+        Ok(StringPart::Expr { source, line, col })
+    }
+
+    fn read_escape(&mut self) -> Result<char, Token> {
+        self.read_char();
+        match self.current_char {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' => Ok('\\'),
+            '"' => Ok('"'),
+            '\'' => Ok('\''),
+            '{' => Ok('{'),
+            '}' => Ok('}'),
+            'u' => self.read_unicode_escape(),
+            '\0' => Err(Token::new(
+                TokenType::IllegalMsg("Falta el simbolo `\"` para delimitar la cadena".to_owned()),
+                self.line,
+                self.col,
+            )),
+            c => Err(Token::new(
+                TokenType::IllegalMsg(format!("Secuencia de escape invalida `\\{}`", c)),
+                self.line,
+                self.col,
+            )),
+        }
+    }
+
+    // \u{1F600}
+    fn read_unicode_escape(&mut self) -> Result<char, Token> {
+        if self.peek_char() != Some(&'{') {
+            return Err(Token::new(
+                TokenType::IllegalMsg(
+                    "La secuencia de escape `\\u` debe tener la forma `\\u{XXXX}`".to_owned(),
+                ),
+                self.line,
+                self.col,
+            ));
+        }
+        self.read_char();
+
+        let mut hex = String::new();
+        while let Some(c) = self.peek_char() {
+            if *c == '}' || *c == '"' || *c == '\n' {
+                break;
+            }
+            hex.push(*c);
+            self.read_char();
+        }
+
+        if self.peek_char() != Some(&'}') {
+            return Err(Token::new(
+                TokenType::IllegalMsg("Falta el simbolo `}` en la secuencia `\\u{...}`".to_owned()),
+                self.line,
+                self.col,
+            ));
+        }
+        self.read_char();
+
+        if hex.is_empty() || hex.len() > 6 {
+            return Err(Token::new(
+                TokenType::IllegalMsg(format!(
+                    "La secuencia `\\u{{{}}}` debe tener entre 1 y 6 digitos hexadecimales",
+                    hex
+                )),
+                self.line,
+                self.col,
+            ));
+        }
+
+        match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+            Some(c) => Ok(c),
+            None => Err(Token::new(
+                TokenType::IllegalMsg(format!(
+                    "La secuencia `\\u{{{}}}` no es un caracter unicode valido",
+                    hex
+                )),
+                self.line,
+                self.col,
+            )),
+        }
     }
 
     fn read_2chars_token(
@@ -319,6 +545,10 @@ impl Lexer {
         self.read_char();
         self.skip_whitespace();
         match self.current_char {
+            '=' if self.peek_char() == Some(&'>') => {
+                self.read_char();
+                Token::new(TokenType::Arrow, self.line, self.col)
+            }
             '=' => self.read_2chars_token('=', TokenType::Eq, TokenType::Assign),
             '+' => self.read_2chars_token('=', TokenType::PlusAssing, TokenType::Plus),
             '-' => self.read_2chars_token('=', TokenType::MinusAssing, TokenType::Minus),
@@ -328,6 +558,8 @@ impl Lexer {
             '!' => self.read_2chars_token('=', TokenType::NotEq, TokenType::Bang),
             '<' => self.read_2chars_token('=', TokenType::LtEq, TokenType::Lt),
             '>' => self.read_2chars_token('=', TokenType::GtEq, TokenType::Gt),
+            '&' => self.read_2chars_token('&', TokenType::And, TokenType::Illegal('&')),
+            '|' => self.read_2chars_token('|', TokenType::Or, TokenType::Illegal('|')),
             ',' => Token::new(TokenType::Comma, self.line, self.col),
             '.' => Token::new(TokenType::Dot, self.line, self.col),
             ';' => Token::new(TokenType::SemiColon, self.line, self.col),
@@ -339,13 +571,21 @@ impl Lexer {
             ']' => Token::new(TokenType::RBracket, self.line, self.col),
             '#' => self.read_to_end_line(),
             ':' => Token::new(TokenType::Colon, self.line, self.col),
-            '"' => self.read_string(),
+            '"' => self.read_string(false, false),
             '\n' => {
                 self.col = 0;
                 self.line += 1;
                 Token::new(TokenType::NewLine, self.line, self.col)
             }
             '\0' => Token::new(TokenType::Eof, self.line, self.col),
+            'r' if self.peek_char() == Some(&'"') => {
+                self.read_char();
+                self.read_string(true, false)
+            }
+            'f' if self.peek_char() == Some(&'"') => {
+                self.read_char();
+                self.read_string(false, true)
+            }
             c => {
                 if self.is_identifier_alpha(c) {
                     return self.read_identifier();
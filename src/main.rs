@@ -2,29 +2,126 @@ pub mod buildins;
 mod eval;
 mod lexer;
 mod parser;
+mod repl;
 mod token;
 mod types;
+mod vm;
 
-use std::{fs, path::Path, process::exit};
+use std::{fs, io::Write, path::Path, process::exit, thread};
 
-use clap::{Arg, Command};
+use clap::{Arg, ArgAction, ArgMatches, Command};
 use eval::{
-    evaluator::Evaluator,
+    evaluator::{Evaluator, MAX_DEPTH},
     objects::{Object, ResultObj},
 };
 use lexer::Lexer;
 use parser::Parser;
+use vm::machine::Vm;
 
 pub const PANA_MIGUEL_ASCII: &str = include_str!("../assets/pana_miguel.txt");
 
+// El evaluador usa la pila nativa en cada llamada de Pana, la del hilo
+// principal no alcanza para la profundidad maxima por defecto. La maquina
+// virtual guarda las llamadas en su propia pila y le basta con esta
+const STACK_SIZE: usize = 1024 * 1024 * 1024;
+// Pila que usa cada llamada anidada del evaluador en el peor caso, con
+// margen. Sin optimizaciones es mucho mayor
+const STACK_PER_CALL: usize = if cfg!(debug_assertions) {
+    64 * 1024
+} else {
+    16 * 1024
+};
+
 fn main() {
-    let mut cmd = Command::new("pana.exe")
+    let matches = command().get_matches();
+    let requested_depth = matches
+        .get_one::<usize>("profundidad")
+        .copied()
+        .unwrap_or(MAX_DEPTH);
+    let uses_vm = matches.get_flag("vm") && matches.contains_id("archivo .pana");
+    // Con el evaluador la pila crece con `--profundidad`, asi el limite salta
+    // antes que un desbordamiento de la pila nativa
+    let mut stack_size = if uses_vm {
+        STACK_SIZE
+    } else {
+        STACK_SIZE.max(requested_depth.saturating_mul(STACK_PER_CALL))
+    };
+    loop {
+        // Si la pila no se puede reservar se reduce, y con ella el limite
+        let max_depth = if uses_vm {
+            requested_depth
+        } else {
+            requested_depth.min(stack_size / STACK_PER_CALL)
+        };
+        let run_matches = matches.clone();
+        let spawned = thread::Builder::new()
+            .stack_size(stack_size)
+            .spawn(move || {
+                if max_depth < requested_depth {
+                    eprintln!(
+                        "Aviso: la pila del evaluador solo alcanza para {} llamadas anidadas, se usa ese limite en lugar de {}. La maquina virtual (--vm) no tiene esta restriccion",
+                        max_depth, requested_depth
+                    );
+                }
+                run(run_matches, max_depth)
+            });
+        match spawned {
+            Ok(child) => {
+                if child.join().is_err() {
+                    exit(1);
+                }
+                return;
+            }
+            Err(_) if stack_size > STACK_SIZE => stack_size = (stack_size / 2).max(STACK_SIZE),
+            Err(_) => {
+                eprintln!("No se pudo reservar la pila del interprete");
+                exit(1);
+            }
+        }
+    }
+}
+
+fn command() -> Command {
+    Command::new("pana.exe")
         .version(env!("CARGO_PKG_VERSION"))
         .author("Sebastian Gonzalez. <devsebasgr@gmail.com>")
         .about("Lenguaje de programacion en espanol!")
-        .arg(Arg::new("archivo .pana").help("Direccion relativa del archivo .pana a ejecutar"));
+        .arg(
+            Arg::new("archivo .pana").help(
+                "Direccion relativa del archivo .pana a ejecutar, sin archivo se inicia el REPL",
+            ),
+        )
+        .arg(
+            Arg::new("vm")
+                .long("vm")
+                .help("Ejecuta el programa con la maquina virtual de bytecode (experimental)")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("profundidad")
+                .long("profundidad")
+                .value_name("LLAMADAS")
+                .help(format!(
+                    "Maximo de llamadas anidadas antes de un error de recursion [por defecto: {}]",
+                    MAX_DEPTH
+                ))
+                .value_parser(clap::value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("argumentos")
+                .help("Argumentos para el programa, disponibles en `sis.argumentos`")
+                .num_args(0..)
+                .trailing_var_arg(true)
+                .allow_hyphen_values(true),
+        )
+}
 
-    if let Some(file_path) = cmd.clone().get_matches().get_one::<String>("archivo .pana") {
+fn run(matches: ArgMatches, max_depth: usize) {
+    let script_args: Vec<String> = matches
+        .get_many::<String>("argumentos")
+        .map(|args| args.cloned().collect())
+        .unwrap_or_default();
+    if let Some(file_path) = matches.get_one::<String>("archivo .pana") {
         if file_path == "pana" {
             return println!("{}", PANA_MIGUEL_ASCII);
         }
@@ -44,9 +141,13 @@ fn main() {
             }
         }
 
-        let mut evaluator = Evaluator::new();
-        let file_str = fs::read_to_string(file_path)
-            .unwrap_or_else(|_| panic!("No es encotro el archivo {}", file_path.display()));
+        let file_str = match fs::read_to_string(file_path) {
+            Ok(file_str) => file_str,
+            Err(_) => {
+                eprintln!("No se encontro el archivo {}", file_path.display());
+                exit(1);
+            }
+        };
 
         let lexer = Lexer::new(file_str.chars().collect());
         let mut parser = Parser::new(lexer);
@@ -57,16 +158,38 @@ fn main() {
             eprintln!("{}", err);
             exit(1);
         }
+        for warning in parser.warnings {
+            eprintln!("{}", warning);
+        }
+
+        let res = if matches.get_flag("vm") {
+            let mut vm = Vm::new();
+            vm.set_main_file(file_path);
+            vm.set_script_args(script_args);
+            vm.set_max_depth(max_depth);
+            vm.run_program(program)
+        } else {
+            let mut evaluator = Evaluator::new();
+            evaluator.set_main_file(file_path);
+            evaluator.set_script_args(script_args);
+            evaluator.set_max_depth(max_depth);
+            evaluator.eval_program(program)
+        };
 
         // Imprimir error de runtime
-        if let ResultObj::Copy(Object::Error(msg)) = evaluator.eval_program(program) {
+        if let ResultObj::Copy(Object::Error(msg)) = res {
+            if let Some(code) = msg.salida {
+                let _ = std::io::stdout().flush();
+                exit(code);
+            }
             eprintln!("{}", msg);
             exit(1);
         }
         return;
     }
-    let help = cmd.render_long_help();
-    println!("{help}");
+
+    // Sin archivo se inicia el modo interactivo
+    repl::start(script_args, max_depth);
 }
 
 #[cfg(test)]
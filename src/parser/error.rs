@@ -7,7 +7,6 @@ pub enum ParserError {
     Illegal(Token),
     IllegalMsg(String, usize, usize),
     MissingIn(usize, usize),
-    MissingRange(usize, usize),
     MissingIdentifier(usize, usize),
     MissingAssign(usize, usize),
     MissingColon(usize, usize),
@@ -19,6 +18,7 @@ pub enum ParserError {
     MissingRightParen(usize, usize),
     MissingRightBrace(usize, usize),
     MissingRightBracket(usize, usize),
+    MissingArrow(usize, usize),
 }
 
 pub fn set_parser_err_line_col(err: ParserError, line: usize, col: usize) -> ParserError {
@@ -34,89 +34,72 @@ pub fn set_parser_err_line_col(err: ParserError, line: usize, col: usize) -> Par
         ParserError::MissingRightParen(_, _) => ParserError::MissingRightParen(line, col),
         ParserError::MissingRightBrace(_, _) => ParserError::MissingRightBrace(line, col),
         ParserError::MissingRightBracket(_, _) => ParserError::MissingRightBracket(line, col),
+        ParserError::MissingArrow(_, _) => ParserError::MissingArrow(line, col),
         err => err,
     }
 }
 
-impl Display for ParserError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl ParserError {
+    // Mensaje, linea y columna del error
+    pub fn parts(&self) -> (String, usize, usize) {
         match self {
-            ParserError::Illegal(token) => {
-                write!(
-                    f,
-                    "{}",
-                    create_syntax_err(
-                        &format!("Se encontro un simbolo ilegal `{}`", token.r#type),
-                        &token.line,
-                        &token.col
-                    )
-                )
+            ParserError::Illegal(token) => (
+                format!("Se encontro un simbolo ilegal `{}`", token.r#type),
+                token.line,
+                token.col,
+            ),
+            ParserError::IllegalMsg(msg, line, col) => (msg.clone(), *line, *col),
+            ParserError::MissingIn(line, col) => {
+                ("Falta la palabra clave `en`".to_owned(), *line, *col)
             }
             ParserError::MissingIdentifier(line, col) => {
-                write!(
-                    f,
-                    "{}",
-                    create_syntax_err("Falta el nombre de la variable", line, col)
-                )
+                ("Falta el nombre de la variable".to_owned(), *line, *col)
             }
             ParserError::MissingAssign(line, col) => {
-                write!(
-                    f,
-                    "{}",
-                    create_syntax_err("Falta el simbolo `=` de asignacion", line, col)
-                )
+                ("Falta el simbolo `=` de asignacion".to_owned(), *line, *col)
             }
+            ParserError::MissingColon(line, col) => ("Falta el `:`".to_owned(), *line, *col),
+            ParserError::MissingComma(line, col) => ("Falta el `'`".to_owned(), *line, *col),
             ParserError::MissingExpression(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta la expresion", line, col))
-            }
-            ParserError::MissingSemiColon(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `;`", line, col))
-            }
-            ParserError::MissingLeftBrace(line, col) => {
-                write!(f, "{}", create_syntax_err("Fata el `{`", line, col))
-            }
-            ParserError::MissingRightBrace(line, col) => {
-                write!(f, "{}", create_syntax_err("Fata el `}`", line, col))
-            }
-            ParserError::MissingLeftParen(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `(`", line, col))
-            }
-            ParserError::MissingRightParen(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `)`", line, col))
-            }
-            ParserError::MissingRightBracket(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `]`", line, col))
-            }
-            ParserError::MissingColon(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `:`", line, col))
-            }
-            ParserError::MissingComma(line, col) => {
-                write!(f, "{}", create_syntax_err("Falta el `'`", line, col))
-            }
-            ParserError::IllegalMsg(msg, line, col) => {
-                write!(f, "{}", create_syntax_err(msg, line, col))
-            }
-            ParserError::MissingIn(line, col) => {
-                write!(
-                    f,
-                    "{}",
-                    create_syntax_err("Falta la palabra clave `en`", line, col)
-                )
-            }
-            ParserError::MissingRange(line, col) => {
-                write!(
-                    f,
-                    "{}",
-                    create_syntax_err("Falta la palabra clave `rango`", line, col)
-                )
+                ("Falta la expresion".to_owned(), *line, *col)
             }
+            ParserError::MissingSemiColon(line, col) => ("Falta el `;`".to_owned(), *line, *col),
+            ParserError::MissingLeftBrace(line, col) => ("Fata el `{`".to_owned(), *line, *col),
+            ParserError::MissingLeftParen(line, col) => ("Falta el `(`".to_owned(), *line, *col),
+            ParserError::MissingRightParen(line, col) => ("Falta el `)`".to_owned(), *line, *col),
+            ParserError::MissingRightBrace(line, col) => ("Fata el `}`".to_owned(), *line, *col),
+            ParserError::MissingRightBracket(line, col) => ("Falta el `]`".to_owned(), *line, *col),
+            ParserError::MissingArrow(line, col) => ("Falta el `=>`".to_owned(), *line, *col),
         }
     }
 }
 
-fn create_syntax_err(msg: &str, line: &usize, col: &usize) -> String {
-    format!(
-        "Error de sintaxis: {}. Linea {}, columna {}.",
-        msg, line, col
-    )
+impl Display for ParserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (msg, line, col) = self.parts();
+        write!(
+            f,
+            "Error de sintaxis: {}. Linea {}, columna {}.",
+            msg, line, col
+        )
+    }
+}
+
+// Problemas que no impiden ejecutar el programa, ej. casos de `segun` que
+// nunca coinciden
+#[derive(Debug, PartialEq)]
+pub struct ParserWarning {
+    pub msg: String,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Display for ParserWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Advertencia: {}. Linea {}, columna {}.",
+            self.msg, self.line, self.col
+        )
+    }
 }
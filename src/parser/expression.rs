@@ -1,5 +1,6 @@
+use std::fmt::Display;
 use std::hash::Hash;
-use std::{collections::HashMap, fmt::Display};
+use std::rc::Rc;
 
 use crate::{token::TokenType, types::Numeric};
 
@@ -42,22 +43,69 @@ pub fn format_arguments(exprs: &[Expression]) -> String {
 
 pub type FnParams = Vec<Expression>;
 
+// Parametro de una funcion: `a`, `a = 1` o `...resto`
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: String,
+    // Se evalua al llamar la funcion si no se paso el argumento
+    pub default: Option<Expression>,
+    // Recibe en una lista los argumentos sin nombre que sobran
+    pub rest: bool,
+    pub line: usize,
+    pub col: usize,
+}
+
+impl Display for Param {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.default, self.rest) {
+            (_, true) => write!(f, "...{}", self.name),
+            (Some(default), _) => write!(f, "{} = {}", self.name, default.r#type),
+            (None, _) => write!(f, "{}", self.name),
+        }
+    }
+}
+
+pub fn format_params(params: &[Param]) -> String {
+    params
+        .iter()
+        .map(|param| param.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
 #[derive(Debug, Clone)]
 pub enum ExprType {
     Identifier(String),
+    // Variable local resuelta antes de evaluar: `depth` es cuantos environments
+    // hay que subir y `slot` su posicion en ese environment
+    Local {
+        name: Rc<str>,
+        depth: usize,
+        slot: usize,
+    },
+    // Variable global resuelta antes de evaluar: `slot` es su posicion en el
+    // environment raiz del programa o modulo
+    Global {
+        name: Rc<str>,
+        slot: usize,
+    },
     NumericLiteral(Numeric),
     BooleanLiteral(bool),
     StringLiteral(String),
+    InterpolatedString {
+        parts: Vec<Expression>,
+    },
     NullLiteral,
     FnLiteral {
-        params: FnParams,
+        params: Vec<Param>,
         body: BlockStatement,
     },
     ListLiteral {
         elements: Vec<Expression>,
     },
     DictionaryLiteral {
-        pairs: HashMap<Expression, Expression>,
+        // En el orden en que se escribieron
+        pairs: Vec<(Expression, Expression)>,
     },
     Index {
         left: Box<Expression>,
@@ -85,6 +133,11 @@ pub enum ExprType {
         function: Box<Expression>, // fn literal o identifier
         arguments: FnParams,
     },
+    // `nombre: valor`, solo dentro de los argumentos de una llamada
+    NamedArgument {
+        name: String,
+        value: Box<Expression>,
+    },
     Assignment {
         left: Box<Expression>,
         right: Box<Expression>,
@@ -94,6 +147,68 @@ pub enum ExprType {
         arguments: FnParams,
         body: BlockStatement,
     },
+    ForIn {
+        idents: Vec<String>,
+        iterable: Box<Expression>,
+        body: BlockStatement,
+    },
+    For {
+        ident: String,
+        init: Box<Expression>,
+        condition: Box<Expression>,
+        update: Box<Expression>,
+        body: BlockStatement,
+    },
+    // `super.metodo`, busca el metodo en la clase padre de la que lo declara.
+    // `instance` y `parent` leen las variables `este` y `super` del metodo
+    Super {
+        method: String,
+        instance: Box<Expression>,
+        parent: Box<Expression>,
+    },
+    // segun valor { caso 1, 2 => ..., caso [a, b] si a > b => ... }
+    Match {
+        value: Box<Expression>,
+        cases: Vec<MatchCase>,
+    },
+}
+
+// Un `caso` de `segun`, coincide si alguno de sus patrones coincide y su
+// condicion `si` es verdadera. Las variables de los patrones y del cuerpo
+// viven en el mismo environment
+#[derive(Debug, Clone)]
+pub struct MatchCase {
+    pub patterns: Vec<Pattern>,
+    pub guard: Option<Expression>,
+    pub body: BlockStatement,
+    pub line: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    // `_`, coincide con cualquier valor
+    Wildcard,
+    // Coincide con cualquier valor y lo guarda en la variable
+    Binding(String),
+    // Numero, cadena, valor logico o nulo
+    Literal(Expression),
+    // `[a, b]`, solo coincide con listas del mismo largo
+    List(Vec<Pattern>),
+    // `{"llave": a}`, el diccionario puede tener mas llaves
+    Dictionary(Vec<(Expression, Pattern)>),
+}
+
+impl Pattern {
+    // Variables que declara el patron, en el orden en que se guardan
+    pub fn bindings(&self, names: &mut Vec<String>) {
+        match self {
+            Pattern::Binding(name) => names.push(name.clone()),
+            Pattern::List(patterns) => patterns.iter().for_each(|p| p.bindings(names)),
+            Pattern::Dictionary(pairs) => pairs.iter().for_each(|(_, p)| p.bindings(names)),
+            Pattern::Wildcard | Pattern::Literal(_) => {}
+        }
+    }
 }
 
 impl Eq for ExprType {}
@@ -108,9 +223,19 @@ impl PartialEq for ExprType {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Identifier(l0), Self::Identifier(r0)) => l0 == r0,
+            (Self::Local { name: l_name, .. }, Self::Local { name: r_name, .. }) => {
+                l_name == r_name
+            }
+            (Self::Global { name: l_name, .. }, Self::Global { name: r_name, .. }) => {
+                l_name == r_name
+            }
             (Self::NumericLiteral(l0), Self::NumericLiteral(r0)) => l0 == r0,
             (Self::BooleanLiteral(l0), Self::BooleanLiteral(r0)) => l0 == r0,
             (Self::StringLiteral(l0), Self::StringLiteral(r0)) => l0 == r0,
+            (
+                Self::InterpolatedString { parts: l_parts },
+                Self::InterpolatedString { parts: r_parts },
+            ) => l_parts == r_parts,
             (Self::FnLiteral { .. }, Self::FnLiteral { .. }) => {
                 panic!("No se puede comparar funciones anonimas")
             }
@@ -171,6 +296,7 @@ impl Display for ExprType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ExprType::Identifier(ident) => write!(f, "{}", ident),
+            ExprType::Local { name, .. } | ExprType::Global { name, .. } => write!(f, "{}", name),
             ExprType::NumericLiteral(int) => write!(f, "{}", int),
             ExprType::Prefix { operator, right } => write!(f, "{}{}", operator, right.r#type),
             ExprType::Infix {
@@ -181,16 +307,27 @@ impl Display for ExprType {
             ExprType::BooleanLiteral(boolean) => write!(f, "{}", boolean),
             ExprType::If { condition, .. } => write!(f, "si {} {{...}}", condition.r#type),
             ExprType::FnLiteral { params, .. } => {
-                write!(f, "fn({}) {{...}}", format_arguments(params))
+                write!(f, "fn({}) {{...}}", format_params(params))
             }
             ExprType::Call {
                 function,
                 arguments,
             } => write!(f, "{}({})", function.r#type, format_arguments(arguments)),
+            ExprType::NamedArgument { name, value } => write!(f, "{}: {}", name, value.r#type),
             ExprType::Assignment { left, right } => {
                 write!(f, "{} = {};", left.r#type, right.r#type)
             }
             ExprType::StringLiteral(string) => write!(f, "\"{}\"", string),
+            ExprType::InterpolatedString { parts } => {
+                write!(f, "f\"")?;
+                for part in parts {
+                    match &part.r#type {
+                        ExprType::StringLiteral(string) => write!(f, "{}", string)?,
+                        expr => write!(f, "{{{}}}", expr)?,
+                    }
+                }
+                write!(f, "\"")
+            }
             ExprType::ListLiteral { elements } => write!(f, "[{}]", format_arguments(elements)),
             ExprType::Index { left, index } => write!(f, "{}[{}]", left.r#type, index.r#type),
             ExprType::NullLiteral => write!(f, "nulo"),
@@ -212,6 +349,27 @@ impl Display for ExprType {
                 ident,
                 format_arguments(arguments)
             ),
+            ExprType::ForIn {
+                idents, iterable, ..
+            } => write!(
+                f,
+                "para {} en {} {{...}}",
+                idents.join(", "),
+                iterable.r#type
+            ),
+            ExprType::For {
+                ident,
+                init,
+                condition,
+                update,
+                ..
+            } => write!(
+                f,
+                "para {} = {}; {}; {} {{...}}",
+                ident, init.r#type, condition.r#type, update.r#type
+            ),
+            ExprType::Super { method, .. } => write!(f, "super.{}", method),
+            ExprType::Match { value, .. } => write!(f, "segun {} {{...}}", value.r#type),
         }
     }
 }
@@ -219,10 +377,13 @@ impl Display for ExprType {
 impl ExprType {
     pub fn get_type(&self) -> &str {
         match self {
-            ExprType::Identifier(_) => "identificador",
+            ExprType::Identifier(_) | ExprType::Local { .. } | ExprType::Global { .. } => {
+                "identificador"
+            }
             ExprType::NumericLiteral(_) => "numerico",
             ExprType::BooleanLiteral(_) => "logico",
             ExprType::StringLiteral(_) => "cadena",
+            ExprType::InterpolatedString { .. } => "cadena",
             ExprType::NullLiteral => "nulo",
             ExprType::FnLiteral { .. } => "funcion",
             ExprType::ListLiteral { .. } => "funcion",
@@ -233,8 +394,13 @@ impl ExprType {
             ExprType::If { .. } => "condicion",
             ExprType::While { .. } => "bucle",
             ExprType::Call { .. } => "llamada",
+            ExprType::NamedArgument { .. } => "argumento",
             ExprType::Assignment { .. } => "asignacion",
             ExprType::ForRange { .. } => "bucle",
+            ExprType::ForIn { .. } => "bucle",
+            ExprType::For { .. } => "bucle",
+            ExprType::Super { .. } => "super",
+            ExprType::Match { .. } => "segun",
         }
     }
 }
@@ -1,31 +1,32 @@
-mod error;
+pub mod error;
 pub mod expression;
 pub mod statement;
-use std::collections::HashMap;
 
 use crate::{
     lexer::Lexer,
-    token::{Token, TokenType},
+    token::{StringPart, Token, TokenType},
 };
 
 use self::{
-    error::{set_parser_err_line_col, ParserError},
-    expression::{ExprType, Expression, FnParams},
-    statement::{BlockStatement, Statement},
+    error::{set_parser_err_line_col, ParserError, ParserWarning},
+    expression::{ExprType, Expression, FnParams, MatchCase, Param, Pattern},
+    statement::{BlockStatement, Method, Statement},
 };
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug)]
 enum Precedence {
     Lowest = 0,
-    Equals = 1,      // ==
-    LessGreater = 2, // < >
-    SumSub = 3,      // + y -
-    ProductDiv = 4,  // * y /
-    Prefix = 5,      //-1
-    Member = 6,      // foo()
-    Call = 7,        // foo()
-    Index = 8,       // foo()
+    Or = 1,          // o ||
+    And = 2,         // y &&
+    Equals = 3,      // ==
+    LessGreater = 4, // < >
+    SumSub = 5,      // + y -
+    ProductDiv = 6,  // * y /
+    Prefix = 7,      //-1
+    Member = 8,      // foo()
+    Call = 9,        // foo()
+    Index = 10,      // foo()
 }
 
 fn to_tokens_precedence(token: &TokenType) -> Precedence {
@@ -34,12 +35,15 @@ fn to_tokens_precedence(token: &TokenType) -> Precedence {
         TokenType::Minus => Precedence::SumSub,
         TokenType::Slash => Precedence::ProductDiv,
         TokenType::Asterisk => Precedence::ProductDiv,
+        TokenType::Percent => Precedence::ProductDiv,
         TokenType::Eq => Precedence::Equals,
         TokenType::NotEq => Precedence::Equals,
         TokenType::Lt => Precedence::LessGreater,
         TokenType::Gt => Precedence::LessGreater,
         TokenType::LtEq => Precedence::LessGreater,
         TokenType::GtEq => Precedence::LessGreater,
+        TokenType::And => Precedence::And,
+        TokenType::Or => Precedence::Or,
         TokenType::Dot => Precedence::Member,
         TokenType::LParen => Precedence::Call,
         TokenType::LBracket => Precedence::Index,
@@ -47,6 +51,48 @@ fn to_tokens_precedence(token: &TokenType) -> Precedence {
     }
 }
 
+fn is_literal(value: &ExprType) -> bool {
+    matches!(
+        value,
+        ExprType::NumericLiteral(_)
+            | ExprType::StringLiteral(_)
+            | ExprType::BooleanLiteral(_)
+            | ExprType::NullLiteral
+            | ExprType::ListLiteral { .. }
+            | ExprType::DictionaryLiteral { .. }
+    )
+}
+
+// Si el patron puede coincidir con el valor. Solo se sabe cuando el valor
+// es un literal, cualquier otra expresion puede tener cualquier valor
+fn pattern_can_match(pattern: &Pattern, value: &ExprType) -> bool {
+    if !is_literal(value) {
+        return true;
+    }
+    match (pattern, value) {
+        (Pattern::Wildcard | Pattern::Binding(_), _) => true,
+        (Pattern::Literal(literal), value) => literal.r#type == *value,
+        (Pattern::List(patterns), ExprType::ListLiteral { elements }) => {
+            patterns.len() == elements.len()
+                && patterns
+                    .iter()
+                    .zip(elements)
+                    .all(|(pattern, element)| pattern_can_match(pattern, &element.r#type))
+        }
+        (Pattern::Dictionary(patterns), ExprType::DictionaryLiteral { pairs }) => {
+            patterns.iter().all(|(key, pattern)| {
+                let value = pairs.iter().find(|(k, _)| k.r#type == key.r#type);
+                match value {
+                    Some((_, value)) => pattern_can_match(pattern, &value.r#type),
+                    // La llave puede ser el valor de una variable
+                    None => pairs.iter().any(|(k, _)| !is_literal(&k.r#type)),
+                }
+            })
+        }
+        _ => false,
+    }
+}
+
 fn create_funny_assigment(
     ident: String,
     op: TokenType,
@@ -87,6 +133,7 @@ pub struct Parser {
     current_token: Token,
     peek_token: Token,
     pub error: Option<ParserError>,
+    pub warnings: Vec<ParserWarning>,
 }
 
 impl Parser {
@@ -104,6 +151,7 @@ impl Parser {
                 col: 0,
             },
             error: None,
+            warnings: Vec::new(),
         }
     }
 
@@ -129,7 +177,16 @@ impl Parser {
         }
     }
 
+    // `y` y `o` solo son operadores despues de un operando, en cualquier otro
+    // lugar son identificadores comunes
     fn peek_precedence(&mut self) -> Precedence {
+        if let TokenType::Ident(ident) = &self.peek_token.r#type {
+            match ident.as_str() {
+                "y" => self.peek_token.r#type = TokenType::And,
+                "o" => self.peek_token.r#type = TokenType::Or,
+                _ => {}
+            }
+        }
         to_tokens_precedence(&self.peek_token.r#type)
     }
 
@@ -196,6 +253,11 @@ impl Parser {
             TokenType::Var => self.parse_var_statement(),
             TokenType::Return => self.parse_return_statement(),
             TokenType::Func => self.parse_fn_statement(),
+            TokenType::Import => self.parse_import_statement(),
+            TokenType::Try => self.parse_try_statement(),
+            TokenType::Throw => self.parse_throw_statement(),
+            TokenType::From => self.parse_from_import_statement(),
+            TokenType::Class => self.parse_class_statement(),
             TokenType::Break => {
                 if !self.expected_peek(TokenType::SemiColon) {
                     return Err(ParserError::MissingSemiColon(
@@ -352,38 +414,296 @@ impl Parser {
         })
     }
 
-    fn parse_fn_params(&mut self) -> Result<FnParams, ParserError> {
-        let mut params = FnParams::default();
-
-        if self.peek_token_is(TokenType::RParen) {
-            self.next_token();
-            return Ok(params);
-        }
-
+    fn parse_class_statement(&mut self) -> Result<Statement, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let TokenType::Ident(name) = self.peek_token.r#type.clone() else {
+            return Err(ParserError::MissingIdentifier(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        };
         self.next_token();
 
-        if let TokenType::Ident(ident) = self.current_token.clone().r#type {
-            params.push(Expression::new(
-                ExprType::Identifier(ident),
-                self.current_token.line,
-                self.current_token.col,
-            ));
-            while self.peek_token_is(TokenType::Comma) {
+        let parent = match self.peek_token.r#type {
+            TokenType::Inherits => {
                 self.next_token();
                 self.next_token();
+                Some(self.parse_expression(Precedence::Lowest)?)
+            }
+            _ => None,
+        };
 
-                if let TokenType::Ident(ident) = self.current_token.clone().r#type {
-                    params.push(Expression::new(
-                        ExprType::Identifier(ident),
+        if !self.expected_peek(TokenType::LBrace) {
+            return Err(ParserError::MissingLeftBrace(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+        self.next_token();
+
+        let mut methods: Vec<Method> = Vec::new();
+        while !self.current_token_is(TokenType::RBrace) {
+            match self.current_token.r#type {
+                TokenType::Eof => {
+                    return Err(ParserError::MissingRightBrace(
+                        self.current_token.line,
+                        self.current_token.col,
+                    ));
+                }
+                TokenType::NewLine | TokenType::CommentLine => {}
+                TokenType::Func => {
+                    let Statement::Fn {
+                        name,
+                        params,
+                        body,
+                        line,
+                        col,
+                    } = self.parse_fn_statement()?
+                    else {
+                        unreachable!("`parse_fn_statement` siempre retorna una funcion");
+                    };
+                    if methods.iter().any(|method| method.name == name) {
+                        return Err(ParserError::IllegalMsg(
+                            format!("El metodo `{}` esta repetido", name),
+                            line,
+                            col,
+                        ));
+                    }
+                    methods.push(Method {
+                        name,
+                        params,
+                        body,
+                        line,
+                        col,
+                    });
+                }
+                _ => {
+                    return Err(ParserError::IllegalMsg(
+                        "Una clase solo puede declarar metodos con `fn`".to_owned(),
                         self.current_token.line,
                         self.current_token.col,
                     ));
-                } else {
-                    return Err(ParserError::Illegal(self.current_token.clone()));
                 }
             }
+            self.next_token();
+        }
+
+        Ok(Statement::Class {
+            name,
+            parent,
+            methods,
+            line,
+            col,
+        })
+    }
+
+    fn parse_try_statement(&mut self) -> Result<Statement, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+
+        if !self.expected_peek(TokenType::LBrace) {
+            return Err(ParserError::MissingLeftBrace(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+        let body = self.parse_block_statement()?;
+
+        let mut catch_ident = None;
+        let mut catch_body = None;
+        if self.expected_peek(TokenType::Catch) {
+            if let TokenType::Ident(ident) = self.peek_token.r#type.clone() {
+                catch_ident = Some(ident);
+                self.next_token();
+            }
+            if !self.expected_peek(TokenType::LBrace) {
+                return Err(ParserError::MissingLeftBrace(
+                    self.peek_token.line,
+                    self.peek_token.col,
+                ));
+            }
+            catch_body = Some(self.parse_block_statement()?);
+        }
+
+        let mut finally_body = None;
+        if self.expected_peek(TokenType::Finally) {
+            if !self.expected_peek(TokenType::LBrace) {
+                return Err(ParserError::MissingLeftBrace(
+                    self.peek_token.line,
+                    self.peek_token.col,
+                ));
+            }
+            finally_body = Some(self.parse_block_statement()?);
+        }
+
+        if catch_body.is_none() && finally_body.is_none() {
+            return Err(ParserError::IllegalMsg(
+                "El bloque `intentar` necesita un `capturar` o un `finalmente`".to_owned(),
+                line,
+                col,
+            ));
+        }
+
+        Ok(Statement::Try {
+            body,
+            catch_ident,
+            catch_body,
+            finally_body,
+            line,
+            col,
+        })
+    }
+
+    fn parse_throw_statement(&mut self) -> Result<Statement, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        self.next_token();
+
+        let expr = self.parse_expression(Precedence::Lowest)?;
+
+        match self.check_semicolon(true) {
+            Some(err) => return Err(err),
+            None => self.next_token(),
+        }
+
+        Ok(Statement::Throw(expr, line, col))
+    }
+
+    fn read_module_path(&mut self) -> Result<String, ParserError> {
+        if let TokenType::String(path) = self.peek_token.r#type.clone() {
+            self.next_token();
+            Ok(path)
         } else {
-            return Err(ParserError::Illegal(self.current_token.clone()));
+            Err(ParserError::IllegalMsg(
+                "Se espera la ruta del modulo entre comillas".to_owned(),
+                self.peek_token.line,
+                self.peek_token.col,
+            ))
+        }
+    }
+
+    fn parse_import_statement(&mut self) -> Result<Statement, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let path = self.read_module_path()?;
+
+        let mut alias = None;
+        if self.expected_peek(TokenType::As) {
+            alias = Some(self.read_identifier()?);
+            self.next_token();
+        }
+
+        match self.check_semicolon(true) {
+            Some(err) => return Err(err),
+            None => self.next_token(),
+        }
+
+        Ok(Statement::Import {
+            path,
+            alias,
+            line,
+            col,
+        })
+    }
+
+    fn parse_from_import_statement(&mut self) -> Result<Statement, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let path = self.read_module_path()?;
+
+        if !self.expected_peek(TokenType::Import) {
+            return Err(ParserError::IllegalMsg(
+                "Falta la palabra clave `importar`".to_owned(),
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+
+        let mut names = vec![self.read_identifier()?];
+        self.next_token();
+        while self.expected_peek(TokenType::Comma) {
+            names.push(self.read_identifier()?);
+            self.next_token();
+        }
+
+        match self.check_semicolon(true) {
+            Some(err) => return Err(err),
+            None => self.next_token(),
+        }
+
+        Ok(Statement::FromImport {
+            path,
+            names,
+            line,
+            col,
+        })
+    }
+
+    // a, b = 1, ...resto
+    fn parse_fn_params(&mut self) -> Result<Vec<Param>, ParserError> {
+        let mut params: Vec<Param> = Vec::new();
+
+        if self.peek_token_is(TokenType::RParen) {
+            self.next_token();
+            return Ok(params);
+        }
+
+        loop {
+            self.next_token();
+            let rest = self.current_token_is(TokenType::Dot);
+            if rest {
+                for _ in 0..2 {
+                    if !self.expected_peek(TokenType::Dot) {
+                        return Err(ParserError::Illegal(self.peek_token.clone()));
+                    }
+                }
+                self.next_token();
+            }
+            let TokenType::Ident(name) = self.current_token.r#type.clone() else {
+                return Err(ParserError::Illegal(self.current_token.clone()));
+            };
+            let line = self.current_token.line;
+            let col = self.current_token.col;
+
+            let default = match !rest && self.expected_peek(TokenType::Assign) {
+                true => {
+                    self.next_token();
+                    Some(self.parse_expression(Precedence::Lowest)?)
+                }
+                false => None,
+            };
+
+            if let Some(last) = params.last() {
+                if last.rest {
+                    return Err(ParserError::IllegalMsg(
+                        format!("El parametro `...{}` debe ser el ultimo", last.name),
+                        last.line,
+                        last.col,
+                    ));
+                }
+                if last.default.is_some() && default.is_none() && !rest {
+                    return Err(ParserError::IllegalMsg(
+                        format!(
+                            "El parametro `{}` necesita un valor por defecto, va despues de `{}`",
+                            name, last.name
+                        ),
+                        line,
+                        col,
+                    ));
+                }
+            }
+            params.push(Param {
+                name,
+                default,
+                rest,
+                line,
+                col,
+            });
+
+            if !self.expected_peek(TokenType::Comma) {
+                break;
+            }
         }
 
         if !self.expected_peek(TokenType::RParen) {
@@ -432,14 +752,25 @@ impl Parser {
                     self.current_token.line,
                     self.current_token.col,
                 )),
+                TokenType::InterpolatedString(parts) => {
+                    self.parse_interpolated_string(parts.clone())
+                }
                 TokenType::Null => Ok(Expression::new(
                     ExprType::NullLiteral,
                     self.current_token.line,
                     self.current_token.col,
                 )),
+                // `este` se guarda como una variable mas de los metodos
+                TokenType::This => Ok(Expression::new(
+                    ExprType::Identifier("este".to_owned()),
+                    self.current_token.line,
+                    self.current_token.col,
+                )),
+                TokenType::Super => self.parse_super_expression(),
 
                 // Prefix
                 TokenType::Bang => self.parse_prefix_expression(),
+                TokenType::Not => self.parse_prefix_expression(),
                 TokenType::Plus => self.parse_prefix_expression(),
                 TokenType::Minus => self.parse_prefix_expression(),
                 TokenType::LParen => self.parse_grouped_expression(),
@@ -447,6 +778,7 @@ impl Parser {
                 TokenType::LBrace => self.parse_dictionary_literal(),
 
                 TokenType::If => self.parse_if_expression(),
+                TokenType::Match => self.parse_match_expression(),
                 TokenType::While => self.parse_while_loop(),
                 TokenType::For => self.parse_range_loop(),
                 TokenType::Func => self.parse_fn_literal(),
@@ -469,59 +801,67 @@ impl Parser {
                 match self.peek_token.r#type {
                     TokenType::Plus => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Minus => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Asterisk => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Slash => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Percent => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Eq => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::NotEq => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Lt => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Gt => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::LtEq => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::GtEq => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_infix_expression(left_expr?);
+                    }
+                    TokenType::And => {
+                        self.next_token();
+                        left_expr = self.parse_infix_expression(left_expr?);
+                    }
+                    TokenType::Or => {
+                        self.next_token();
+                        left_expr = self.parse_infix_expression(left_expr?);
                     }
                     TokenType::Dot => {
                         self.next_token();
-                        left_expr = self.parse_infix_expression(left_expr.unwrap());
+                        left_expr = self.parse_member_expression(left_expr?);
                     }
                     TokenType::LParen => {
                         self.next_token();
-                        left_expr = self.parse_call_expression(left_expr.unwrap());
+                        left_expr = self.parse_call_expression(left_expr?);
                     }
                     TokenType::LBracket => {
                         self.next_token();
-                        left_expr = self.parse_index_expression(left_expr.unwrap());
+                        left_expr = self.parse_index_expression(left_expr?);
                     }
                     _ => {
                         return Err(ParserError::Illegal(self.peek_token.clone()));
@@ -536,7 +876,6 @@ impl Parser {
     }
 
     fn parse_identifier(&mut self, ident: String) -> Result<Expression, ParserError> {
-        let peek_token;
         if !matches!(
             self.peek_token.r#type,
             TokenType::Assign
@@ -552,7 +891,7 @@ impl Parser {
                 self.current_token.col,
             ));
         }
-        peek_token = self.peek_token.r#type.clone();
+        let peek_token = self.peek_token.r#type.clone();
 
         self.next_token();
         self.next_token();
@@ -633,6 +972,62 @@ impl Parser {
         }
     }
 
+    fn parse_interpolated_string(
+        &mut self,
+        parts: Vec<StringPart>,
+    ) -> Result<Expression, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let mut exprs = Vec::new();
+        for part in parts {
+            match part {
+                StringPart::Literal(string) => {
+                    exprs.push(Expression::new(ExprType::StringLiteral(string), line, col))
+                }
+                StringPart::Expr { source, line, col } => {
+                    let lexer = Lexer::new_at(source.chars().collect(), line, col);
+                    let mut parser = Parser::new(lexer);
+                    exprs.push(parser.parse_embedded_expression(line, col)?);
+                }
+            }
+        }
+        Ok(Expression::new(
+            ExprType::InterpolatedString { parts: exprs },
+            line,
+            col,
+        ))
+    }
+
+    // Parsea el codigo de una interpolacion, debe ser una sola expresion
+    fn parse_embedded_expression(
+        &mut self,
+        line: usize,
+        col: usize,
+    ) -> Result<Expression, ParserError> {
+        self.next_token();
+        self.next_token();
+        while self.current_token_is(TokenType::NewLine) {
+            self.next_token();
+        }
+
+        if self.current_token_is(TokenType::Eof) {
+            return Err(ParserError::IllegalMsg(
+                "La interpolacion de la cadena esta vacia".to_owned(),
+                line,
+                col,
+            ));
+        }
+
+        let expr = self.parse_expression(Precedence::Lowest)?;
+        while self.peek_token_is(TokenType::NewLine) {
+            self.next_token();
+        }
+        if !self.peek_token_is(TokenType::Eof) {
+            return Err(ParserError::Illegal(self.peek_token.clone()));
+        }
+        Ok(expr)
+    }
+
     fn parse_prefix_expression(&mut self) -> Result<Expression, ParserError> {
         let op_token = self.current_token.clone();
         self.next_token();
@@ -671,6 +1066,118 @@ impl Parser {
         ))
     }
 
+    fn parse_super_expression(&mut self) -> Result<Expression, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        if !self.expected_peek(TokenType::Dot) {
+            return Err(ParserError::IllegalMsg(
+                "Se esperaba `.` despues de `super`".to_owned(),
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+        let TokenType::Ident(method) = self.peek_token.r#type.clone() else {
+            return Err(ParserError::MissingIdentifier(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        };
+        self.next_token();
+
+        let variable = |name: &str| {
+            Box::new(Expression::new(
+                ExprType::Identifier(name.to_owned()),
+                line,
+                col,
+            ))
+        };
+        Ok(Expression::new(
+            ExprType::Super {
+                method,
+                instance: variable("este"),
+                parent: variable("super"),
+            },
+            self.current_token.line,
+            self.current_token.col,
+        ))
+    }
+
+    // `a.b`, `a.b(...)` o la asignacion de un miembro: `a.b = valor;`
+    fn parse_member_expression(&mut self, left: Expression) -> Result<Expression, ParserError> {
+        let precedence = self.current_precedence();
+        self.next_token();
+
+        // Some si es una asignacion, con el operador de `+=`, `-=`, etc.
+        let operator = match self.peek_token.r#type {
+            TokenType::Assign => Some(None),
+            TokenType::PlusAssing => Some(Some(TokenType::Plus)),
+            TokenType::MinusAssing => Some(Some(TokenType::Minus)),
+            TokenType::AsteriskAssing => Some(Some(TokenType::Asterisk)),
+            TokenType::SlashAssing => Some(Some(TokenType::Slash)),
+            TokenType::PercentAssing => Some(Some(TokenType::Percent)),
+            _ => None,
+        };
+        let (TokenType::Ident(name), Some(operator)) =
+            (self.current_token.r#type.clone(), operator)
+        else {
+            let right = self.parse_expression(precedence)?;
+            return Ok(Expression::new(
+                ExprType::Infix {
+                    left: Box::new(left),
+                    operator: TokenType::Dot,
+                    right: Box::new(right),
+                },
+                self.current_token.line,
+                self.current_token.col,
+            ));
+        };
+
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let member = Expression::new(
+            ExprType::Infix {
+                left: Box::new(left),
+                operator: TokenType::Dot,
+                right: Box::new(Expression::new(ExprType::Identifier(name), line, col)),
+            },
+            line,
+            col,
+        );
+        self.next_token();
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+        if let Some(err) = self.check_semicolon(false) {
+            return Err(err);
+        }
+        self.next_token();
+
+        // `a.b += 1` es `a.b = a.b + 1`
+        let value = match operator {
+            Some(operator) => {
+                let (value_line, value_col) = (value.line, value.col);
+                Expression::new(
+                    ExprType::Infix {
+                        left: Box::new(member.clone()),
+                        operator,
+                        right: Box::new(value),
+                    },
+                    value_line,
+                    value_col,
+                )
+            }
+            None => value,
+        };
+        Ok(Expression::new(
+            ExprType::Assignment {
+                left: Box::new(member),
+                right: Box::new(value),
+            },
+            line,
+            col,
+        ))
+    }
+
     fn parse_if_expression(&mut self) -> Result<Expression, ParserError> {
         self.next_token();
 
@@ -716,6 +1223,229 @@ impl Parser {
         ))
     }
 
+    fn parse_match_expression(&mut self) -> Result<Expression, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        self.next_token();
+
+        let value = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expected_peek(TokenType::LBrace) {
+            return Err(ParserError::MissingLeftBrace(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+        self.next_token();
+
+        let mut cases = Vec::new();
+        while !self.current_token_is(TokenType::RBrace) {
+            match self.current_token.r#type {
+                TokenType::Eof => {
+                    return Err(ParserError::MissingRightBrace(
+                        self.current_token.line,
+                        self.current_token.col,
+                    ));
+                }
+                TokenType::NewLine | TokenType::CommentLine => {}
+                TokenType::Case => cases.push(self.parse_match_case()?),
+                _ => {
+                    return Err(ParserError::IllegalMsg(
+                        "Dentro de `segun` solo se puede declarar casos con `caso`".to_owned(),
+                        self.current_token.line,
+                        self.current_token.col,
+                    ));
+                }
+            }
+            self.next_token();
+        }
+
+        self.check_unreachable_cases(&value, &cases);
+        Ok(Expression::new(
+            ExprType::Match {
+                value: Box::new(value),
+                cases,
+            },
+            line,
+            col,
+        ))
+    }
+
+    // caso patron, patron si condicion => expresion o bloque
+    fn parse_match_case(&mut self) -> Result<MatchCase, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+
+        let mut patterns = Vec::new();
+        loop {
+            self.next_token();
+            patterns.push(self.parse_pattern()?);
+            if !self.expected_peek(TokenType::Comma) {
+                break;
+            }
+        }
+        if patterns.len() > 1 {
+            let mut names = Vec::new();
+            patterns
+                .iter()
+                .for_each(|pattern| pattern.bindings(&mut names));
+            if !names.is_empty() {
+                return Err(ParserError::IllegalMsg(
+                    "Un caso con varios patrones no puede declarar variables".to_owned(),
+                    line,
+                    col,
+                ));
+            }
+        }
+
+        let guard = match self.expected_peek(TokenType::If) {
+            true => {
+                self.next_token();
+                Some(self.parse_expression(Precedence::Lowest)?)
+            }
+            false => None,
+        };
+
+        if !self.expected_peek(TokenType::Arrow) {
+            return Err(ParserError::MissingArrow(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+
+        // Un `{` despues de `=>` siempre es un bloque, no un diccionario
+        let body = if self.expected_peek(TokenType::LBrace) {
+            self.parse_block_statement()?
+        } else {
+            self.next_token();
+            let expr = self.parse_expression(Precedence::Lowest)?;
+            if self.peek_token_is(TokenType::Comma) || self.peek_token_is(TokenType::SemiColon) {
+                self.next_token();
+            }
+            vec![Statement::Expression(expr)]
+        };
+
+        Ok(MatchCase {
+            patterns,
+            guard,
+            body,
+            line,
+            col,
+        })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern, ParserError> {
+        let line = self.current_token.line;
+        let col = self.current_token.col;
+        let literal = |r#type| Ok(Pattern::Literal(Expression::new(r#type, line, col)));
+        match self.current_token.r#type.clone() {
+            TokenType::Ident(ident) if ident == "_" => Ok(Pattern::Wildcard),
+            TokenType::Ident(ident) => Ok(Pattern::Binding(ident)),
+            TokenType::Numeric(numeric) => literal(ExprType::NumericLiteral(numeric)),
+            TokenType::Minus => match self.peek_token.r#type.clone() {
+                TokenType::Numeric(numeric) => {
+                    self.next_token();
+                    literal(ExprType::NumericLiteral(-numeric))
+                }
+                _ => Err(ParserError::Illegal(self.current_token.clone())),
+            },
+            TokenType::String(string) => literal(ExprType::StringLiteral(string)),
+            TokenType::True => literal(ExprType::BooleanLiteral(true)),
+            TokenType::False => literal(ExprType::BooleanLiteral(false)),
+            TokenType::Null => literal(ExprType::NullLiteral),
+            TokenType::LBracket => {
+                let mut patterns = Vec::new();
+                while !self.peek_token_is(TokenType::RBracket) {
+                    self.next_token();
+                    patterns.push(self.parse_pattern()?);
+                    if !self.peek_token_is(TokenType::RBracket)
+                        && !self.expected_peek(TokenType::Comma)
+                    {
+                        return Err(ParserError::MissingRightBracket(
+                            self.peek_token.line,
+                            self.peek_token.col,
+                        ));
+                    }
+                }
+                self.next_token();
+                Ok(Pattern::List(patterns))
+            }
+            TokenType::LBrace => {
+                let mut pairs: Vec<(Expression, Pattern)> = Vec::new();
+                while !self.peek_token_is(TokenType::RBrace) {
+                    self.next_token();
+                    let Pattern::Literal(key) = self.parse_pattern()? else {
+                        return Err(ParserError::IllegalMsg(
+                            "Las llaves de un patron de diccionario deben ser literales".to_owned(),
+                            self.current_token.line,
+                            self.current_token.col,
+                        ));
+                    };
+                    if pairs.iter().any(|(k, _)| k.r#type == key.r#type) {
+                        return Err(ParserError::IllegalMsg(
+                            format!("La llave {} esta repetida en el patron", key.r#type),
+                            key.line,
+                            key.col,
+                        ));
+                    }
+                    if !self.expected_peek(TokenType::Colon) {
+                        return Err(ParserError::MissingColon(
+                            self.peek_token.line,
+                            self.peek_token.col,
+                        ));
+                    }
+                    self.next_token();
+                    pairs.push((key, self.parse_pattern()?));
+                    if !self.peek_token_is(TokenType::RBrace)
+                        && !self.expected_peek(TokenType::Comma)
+                    {
+                        return Err(ParserError::MissingRightBrace(
+                            self.peek_token.line,
+                            self.peek_token.col,
+                        ));
+                    }
+                }
+                self.next_token();
+                Ok(Pattern::Dictionary(pairs))
+            }
+            TokenType::IllegalMsg(msg) => Err(ParserError::IllegalMsg(msg, line, col)),
+            _ => Err(ParserError::IllegalMsg(
+                format!("`{}` no es un patron valido", self.current_token.r#type),
+                line,
+                col,
+            )),
+        }
+    }
+
+    // Advierte de los casos que nunca se ejecutan: los que no coinciden con
+    // un valor literal y los que siguen a un caso que coincide con todo
+    fn check_unreachable_cases(&mut self, value: &Expression, cases: &[MatchCase]) {
+        let mut catch_all = false;
+        for case in cases {
+            let msg = if catch_all {
+                "El caso nunca se ejecuta, uno anterior coincide con cualquier valor"
+            } else if !case
+                .patterns
+                .iter()
+                .any(|pattern| pattern_can_match(pattern, &value.r#type))
+            {
+                "El caso nunca coincide con el valor de `segun`"
+            } else {
+                catch_all = case.guard.is_none()
+                    && case
+                        .patterns
+                        .iter()
+                        .any(|pattern| matches!(pattern, Pattern::Wildcard | Pattern::Binding(_)));
+                continue;
+            };
+            self.warnings.push(ParserWarning {
+                msg: msg.to_owned(),
+                line: case.line,
+                col: case.col,
+            });
+        }
+    }
+
     fn parse_fn_literal(&mut self) -> Result<Expression, ParserError> {
         if !self.expected_peek(TokenType::LParen) {
             return Err(ParserError::MissingLeftParen(
@@ -795,9 +1525,75 @@ impl Parser {
         Ok(args)
     }
 
+    // Los argumentos con nombre van despues de los demas: f(1, c: 3, b: 2)
+    fn parse_call_arguments(&mut self) -> Result<FnParams, ParserError> {
+        let mut args = FnParams::default();
+
+        if self.peek_token_is(TokenType::RParen) {
+            self.next_token();
+            return Ok(args);
+        }
+
+        loop {
+            self.next_token();
+            let arg = match self.current_token.r#type.clone() {
+                TokenType::Ident(name) if self.peek_token_is(TokenType::Colon) => {
+                    let line = self.current_token.line;
+                    let col = self.current_token.col;
+                    let repeated = args.iter().any(|arg| {
+                        matches!(&arg.r#type, ExprType::NamedArgument { name: other, .. } if *other == name)
+                    });
+                    if repeated {
+                        return Err(ParserError::IllegalMsg(
+                            format!("El argumento `{}` esta repetido", name),
+                            line,
+                            col,
+                        ));
+                    }
+                    self.next_token();
+                    self.next_token();
+                    let value = self.parse_expression(Precedence::Lowest)?;
+                    Expression::new(
+                        ExprType::NamedArgument {
+                            name,
+                            value: Box::new(value),
+                        },
+                        line,
+                        col,
+                    )
+                }
+                _ => {
+                    let arg = self.parse_expression(Precedence::Lowest)?;
+                    if let Some(ExprType::NamedArgument { .. }) = args.last().map(|a| &a.r#type) {
+                        return Err(ParserError::IllegalMsg(
+                            "Los argumentos sin nombre van antes de los argumentos con nombre"
+                                .to_owned(),
+                            arg.line,
+                            arg.col,
+                        ));
+                    }
+                    arg
+                }
+            };
+            args.push(arg);
+
+            if !self.expected_peek(TokenType::Comma) {
+                break;
+            }
+        }
+
+        if !self.expected_peek(TokenType::RParen) {
+            return Err(ParserError::MissingRightParen(
+                self.peek_token.line,
+                self.peek_token.col,
+            ));
+        }
+
+        Ok(args)
+    }
+
     fn parse_call_expression(&mut self, function: Expression) -> Result<Expression, ParserError> {
-        let arguments =
-            self.parse_expression_list(TokenType::RParen, ParserError::MissingRightParen(0, 0));
+        let arguments = self.parse_call_arguments();
 
         match arguments {
             Ok(arguments) => {
@@ -832,7 +1628,7 @@ impl Parser {
     }
 
     fn parse_dictionary_literal(&mut self) -> Result<Expression, ParserError> {
-        let mut dictionary = HashMap::new();
+        let mut dictionary = Vec::new();
         while !self.peek_token_is(TokenType::RBrace) {
             self.next_token();
             let key = self.parse_expression(Precedence::Lowest)?;
@@ -852,7 +1648,7 @@ impl Parser {
             self.next_token();
             let value = self.parse_expression(Precedence::Lowest)?;
 
-            dictionary.insert(key, value);
+            dictionary.push((key, value));
 
             if !self.peek_token_is(TokenType::RBrace) && !self.expected_peek(TokenType::Comma) {
                 return Err(ParserError::MissingComma(
@@ -949,11 +1745,27 @@ impl Parser {
     }
 
     fn parse_range_loop(&mut self) -> Result<Expression, ParserError> {
+        // para var i = 0; ...
+        if self.peek_token_is(TokenType::Var) {
+            self.next_token();
+        }
         let line = self.peek_token.line;
         let col = self.peek_token.col;
         let identifier = self.read_identifier()?;
         self.next_token();
 
+        if self.peek_token_is(TokenType::Assign) {
+            return self.parse_for_loop(identifier, line, col);
+        }
+
+        // para llave, valor en ...
+        let mut idents = vec![identifier];
+        if self.peek_token_is(TokenType::Comma) {
+            self.next_token();
+            idents.push(self.read_identifier()?);
+            self.next_token();
+        }
+
         if !self.expected_peek(TokenType::In) {
             // TODO!! Cambiar a error adecuado
             return Err(ParserError::MissingIn(
@@ -962,13 +1774,18 @@ impl Parser {
             ));
         }
 
-        if !self.expected_peek(TokenType::Range) {
-            // TODO!! Cambiar a error adecuado
-            return Err(ParserError::MissingRange(
-                self.current_token.line,
-                self.current_token.col,
+        if !self.peek_token_is(TokenType::Range) {
+            return self.parse_for_in_loop(idents, line, col);
+        }
+        if idents.len() != 1 {
+            return Err(ParserError::IllegalMsg(
+                "El bucle `para ... en rango` solo acepta una variable".to_owned(),
+                line,
+                col,
             ));
         }
+        self.next_token();
+
         if !self.expected_peek(TokenType::LParen) {
             // TODO!! Cambiar a error adecuado
             return Err(ParserError::MissingLeftParen(
@@ -990,7 +1807,7 @@ impl Parser {
 
         Ok(Expression::new(
             ExprType::ForRange {
-                ident: identifier,
+                ident: idents.remove(0),
                 arguments,
                 body: consequence_stmts,
             },
@@ -998,4 +1815,84 @@ impl Parser {
             col,
         ))
     }
+
+    // para x en lista {...}
+    fn parse_for_in_loop(
+        &mut self,
+        idents: Vec<String>,
+        line: usize,
+        col: usize,
+    ) -> Result<Expression, ParserError> {
+        self.next_token();
+        let iterable = self.parse_expression(Precedence::Lowest)?;
+
+        if !self.expected_peek(TokenType::LBrace) {
+            return Err(ParserError::MissingLeftBrace(
+                self.current_token.line,
+                self.current_token.col,
+            ));
+        }
+        let body = self.parse_block_statement()?;
+
+        Ok(Expression::new(
+            ExprType::ForIn {
+                idents,
+                iterable: Box::new(iterable),
+                body,
+            },
+            line,
+            col,
+        ))
+    }
+
+    // para i = 0; i < 10; i += 1 {...}
+    fn parse_for_loop(
+        &mut self,
+        ident: String,
+        line: usize,
+        col: usize,
+    ) -> Result<Expression, ParserError> {
+        self.next_token();
+        self.next_token();
+        let init = self.parse_expression(Precedence::Lowest)?;
+        if !self.expected_peek(TokenType::SemiColon) {
+            return Err(ParserError::MissingSemiColon(
+                self.current_token.line,
+                self.current_token.col,
+            ));
+        }
+
+        self.next_token();
+        let condition = self.parse_expression(Precedence::Lowest)?;
+        if !self.expected_peek(TokenType::SemiColon) {
+            return Err(ParserError::MissingSemiColon(
+                self.current_token.line,
+                self.current_token.col,
+            ));
+        }
+
+        self.next_token();
+        let update = self.parse_expression(Precedence::Lowest)?;
+
+        // Las asignaciones ya consumen el token que les sigue
+        if !self.current_token_is(TokenType::LBrace) && !self.expected_peek(TokenType::LBrace) {
+            return Err(ParserError::MissingLeftBrace(
+                self.current_token.line,
+                self.current_token.col,
+            ));
+        }
+        let body = self.parse_block_statement()?;
+
+        Ok(Expression::new(
+            ExprType::For {
+                ident,
+                init: Box::new(init),
+                condition: Box::new(condition),
+                update: Box::new(update),
+                body,
+            },
+            line,
+            col,
+        ))
+    }
 }
@@ -1,4 +1,4 @@
-use super::expression::{Expression, FnParams};
+use super::expression::{Expression, Param};
 
 pub type BlockStatement = Vec<Statement>;
 
@@ -14,9 +14,52 @@ pub enum Statement {
     Expression(Expression),
     Fn {
         name: String,
-        params: FnParams,
+        params: Vec<Param>,
         body: BlockStatement,
         line: usize,
         col: usize,
     },
+    // intentar { } capturar e { } finalmente { }
+    Try {
+        body: BlockStatement,
+        catch_ident: Option<String>,
+        catch_body: Option<BlockStatement>,
+        finally_body: Option<BlockStatement>,
+        line: usize,
+        col: usize,
+    },
+    Throw(Expression, usize, usize),
+    // importar "archivo.pana" como nombre;
+    Import {
+        path: String,
+        alias: Option<String>,
+        line: usize,
+        col: usize,
+    },
+    // desde "archivo.pana" importar a, b;
+    FromImport {
+        path: String,
+        names: Vec<String>,
+        line: usize,
+        col: usize,
+    },
+    // clase Punto { fn iniciar(x, y) { ... } }
+    Class {
+        name: String,
+        // La expresion despues de `hereda`
+        parent: Option<Expression>,
+        methods: Vec<Method>,
+        line: usize,
+        col: usize,
+    },
+}
+
+// Metodo de una clase, dentro de su cuerpo `este` es la instancia
+#[derive(Debug, Clone)]
+pub struct Method {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: BlockStatement,
+    pub line: usize,
+    pub col: usize,
 }
@@ -0,0 +1,215 @@
+use std::{io::Write, path::PathBuf};
+
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use crate::{
+    eval::{
+        evaluator::Evaluator,
+        objects::{Object, ResultObj},
+    },
+    lexer::Lexer,
+    parser::{
+        error::ParserError,
+        statement::{BlockStatement, Statement},
+        Parser,
+    },
+    token::TokenType,
+};
+
+const PROMPT: &str = "pana> ";
+const PROMPT_CONTINUE: &str = "...   ";
+const HISTORY_FILE: &str = ".pana_historial";
+
+const HELP: &str = "Comandos:
+  :salir       Termina la sesion
+  :vars        Muestra las variables globales
+  :reiniciar   Borra todas las variables y modulos importados
+  :ayuda       Muestra este mensaje";
+
+// Sesion del REPL, mantiene el mismo evaluador entre entradas
+pub struct Session {
+    evaluator: Evaluator,
+    script_args: Vec<String>,
+    max_depth: usize,
+}
+
+impl Session {
+    pub fn new(script_args: Vec<String>) -> Self {
+        let mut evaluator = Evaluator::new();
+        evaluator.set_script_args(script_args.clone());
+        let max_depth = evaluator.max_depth();
+        Self {
+            evaluator,
+            script_args,
+            max_depth,
+        }
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        self.evaluator.set_max_depth(max_depth);
+    }
+
+    pub fn reset(&mut self) {
+        let max_depth = self.max_depth;
+        *self = Self::new(std::mem::take(&mut self.script_args));
+        self.set_max_depth(max_depth);
+    }
+
+    // Evalua una entrada completa. Retorna el valor a mostrar si la entrada
+    // termina en una expresion, los errores no modifican la sesion
+    pub fn eval(&mut self, input: &str) -> Result<Option<String>, String> {
+        let program = match parse(input) {
+            // Las llamadas y asignaciones necesitan `;`, en el REPL es opcional
+            // al final de la entrada
+            Err(ParserError::MissingSemiColon(..)) => parse(&format!("{};", input)),
+            res => res,
+        }
+        .map_err(|err| err.to_string())?;
+
+        let echo = matches!(program.last(), Some(Statement::Expression(_)));
+        match self.evaluator.eval_program(program) {
+            ResultObj::Copy(Object::Error(err)) => match err.salida {
+                // `sis.salir` tambien termina el REPL
+                Some(code) => {
+                    let _ = std::io::stdout().flush();
+                    std::process::exit(code);
+                }
+                None => Err(err.to_string()),
+            },
+            ResultObj::Copy(Object::Void) => Ok(None),
+            res if echo => Ok(Some(res.to_string())),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn vars(&self) -> Vec<String> {
+        self.evaluator
+            .global_vars()
+            .into_iter()
+            .map(|(name, value)| format!("{}: {} = {}", name, value.get_type(), value))
+            .collect()
+    }
+}
+
+fn parse(input: &str) -> Result<BlockStatement, ParserError> {
+    let mut parser = Parser::new(Lexer::new(input.chars().collect()));
+    let program = parser.parse();
+    match parser.error {
+        Some(err) => Err(err),
+        None => {
+            for warning in parser.warnings {
+                eprintln!("{}", warning);
+            }
+            Ok(program)
+        }
+    }
+}
+
+// La entrada esta incompleta si tiene `{`, `[` o `(` sin cerrar o una cadena
+// sin terminar, en ese caso el REPL pide mas lineas
+pub fn is_incomplete(input: &str) -> bool {
+    let mut lexer = Lexer::new(input.chars().collect());
+    let mut depth = 0isize;
+    loop {
+        match lexer.next_token().r#type {
+            TokenType::LBrace | TokenType::LBracket | TokenType::LParen => depth += 1,
+            TokenType::RBrace | TokenType::RBracket | TokenType::RParen => depth -= 1,
+            TokenType::IllegalMsg(msg) => return msg.starts_with("Falta el simbolo"),
+            TokenType::Eof => return depth > 0,
+            _ => {}
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+pub fn start(script_args: Vec<String>, max_depth: usize) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("No se pudo iniciar el REPL, {}", err);
+            std::process::exit(1);
+        }
+    };
+    let history = history_path();
+    if let Some(ref path) = history {
+        let _ = editor.load_history(path);
+    }
+
+    println!(
+        "Pana {}. Escribe `:ayuda` para ver los comandos.",
+        env!("CARGO_PKG_VERSION")
+    );
+    let mut session = Session::new(script_args);
+    session.set_max_depth(max_depth);
+    let mut input = String::new();
+    loop {
+        let prompt = if input.is_empty() {
+            PROMPT
+        } else {
+            PROMPT_CONTINUE
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C descarta la entrada actual
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                eprintln!("{}", err);
+                break;
+            }
+        };
+
+        if input.is_empty() {
+            match line.trim() {
+                "" => continue,
+                ":salir" => break,
+                ":vars" => {
+                    session.vars().iter().for_each(|var| println!("{}", var));
+                    let _ = editor.add_history_entry(line.as_str());
+                    continue;
+                }
+                ":reiniciar" => {
+                    session.reset();
+                    let _ = editor.add_history_entry(line.as_str());
+                    continue;
+                }
+                ":ayuda" => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                cmd if cmd.starts_with(':') => {
+                    eprintln!("Comando desconocido `{}`, usa `:ayuda`", cmd);
+                    continue;
+                }
+                _ => {}
+            }
+        } else {
+            input.push('\n');
+        }
+        input.push_str(&line);
+
+        if is_incomplete(&input) {
+            continue;
+        }
+        let _ = editor.add_history_entry(input.as_str());
+        match session.eval(&input) {
+            Ok(Some(value)) => println!("{}", value),
+            Ok(None) => {}
+            Err(err) => eprintln!("{}", err),
+        }
+        input.clear();
+    }
+
+    if let Some(ref path) = history {
+        let _ = editor.save_history(path);
+    }
+}
@@ -1,2 +1,1743 @@
+use crate::{
+    eval::{
+        evaluator::Evaluator,
+        objects::{Object, ResultObj},
+    },
+    lexer::Lexer,
+    parser::{statement::BlockStatement, Parser},
+    vm::machine::Vm,
+};
+
+fn parse_input(input: &str) -> BlockStatement {
+    let lexer = Lexer::new(input.chars().collect());
+    let mut parser = Parser::new(lexer);
+    let program = parser.parse();
+    if let Some(err) = parser.error {
+        panic!("Error del parser: {}", err);
+    }
+    program
+}
+
+// Evalua con el evaluador y con la maquina virtual, ambos deben dar lo mismo
+fn eval_input(input: &str) -> ResultObj {
+    let mut evaluator = Evaluator::new();
+    let obj = evaluator.eval_program(parse_input(input));
+    let vm_obj = Vm::new().run_program(parse_input(input));
+    assert_eq!(
+        vm_obj.to_string(),
+        obj.to_string(),
+        "Maquina virtual, entrada: {}",
+        input
+    );
+    obj
+}
+
+fn assert_eval(input: &str, expected: &str) {
+    let obj = eval_input(input);
+    assert_eq!(obj.to_string(), expected, "Entrada: {}", input);
+}
+
 #[test]
-fn eval() {}
+fn logical_operators() {
+    assert_eval("verdad y falso;", "falso");
+    assert_eval("verdad y verdad;", "verdad");
+    assert_eval("falso o verdad;", "verdad");
+    assert_eval("falso || falso;", "falso");
+    assert_eval("1 < 2 && 2 < 3;", "verdad");
+    assert_eval("no verdad;", "falso");
+    assert_eval("no falso y verdad;", "verdad");
+    assert_eval("verdad o falso y falso;", "verdad");
+    assert_eval("1 == 2 o 3 == 3;", "verdad");
+
+    // `y` y `o` solo son operadores despues de un operando, tambien se pueden
+    // usar como nombres
+    assert_eval("var y = verdad; var o = falso; y y o o y;", "verdad");
+    assert_eval(
+        "fn f(x, y) { retornar x y y; } [f(verdad, falso), f(verdad, verdad)];",
+        "[falso, verdad]",
+    );
+    assert_eval("var o = 2;\nvar y = o * 3;\ny;", "6");
+    assert_eval(
+        "clase P { fn iniciar(y) { este.y = y; } }\nP(falso).y o P(verdad).y;",
+        "verdad",
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    let input = "
+        var a = 0;
+        fn marcar() {
+            a = 1;
+            verdad;
+        }
+        falso y marcar();
+        verdad o marcar();
+        a;
+    ";
+    assert_eval(input, "0");
+
+    let input = "
+        var a = 0;
+        fn marcar() {
+            a = 1;
+            verdad;
+        }
+        verdad y marcar();
+        a;
+    ";
+    assert_eval(input, "1");
+}
+
+#[test]
+fn logical_operators_errors() {
+    let obj = eval_input("\"hola\" y verdad;");
+    assert!(obj.to_string().contains("operador logico `y`"));
+}
+
+#[test]
+fn interpolated_strings() {
+    let input = r#"
+        var nombre = "Ana";
+        var edad = 20;
+        f"hola {nombre}, tienes {edad + 1} años";
+    "#;
+    assert_eval(input, "hola Ana, tienes 21 años");
+    assert_eval(
+        r#"f"{[1, 2]} {{"a": nulo}["a"]} {verdad}";"#,
+        "[1, 2] nulo verdad",
+    );
+    assert_eval(r#"f"\{literal\} {f"{1 + 1}"}";"#, "{literal} 2");
+    assert_eval("f\"\"\"uno {1}\ndos {2}\"\"\";", "uno 1\ndos 2");
+}
+
+#[test]
+fn interpolated_strings_errors() {
+    let obj = eval_input("var a = 1;\nf\"valor: {a + b}\";");
+    assert_eq!(
+        obj.to_string(),
+        "Error de ejecución. El identicador `b` no existe. Linea 2, columna 15."
+    );
+
+    let lexer = Lexer::new("var a = 1;\nf\"valor: {a +}\";".chars().collect());
+    let mut parser = Parser::new(lexer);
+    parser.parse();
+    assert_eq!(
+        parser.error.unwrap().to_string(),
+        "Error de sintaxis: Se encontro un simbolo ilegal `EOF`. Linea 2, columna 14."
+    );
+
+    let lexer = Lexer::new("f\"valor: {}\";".chars().collect());
+    let mut parser = Parser::new(lexer);
+    parser.parse();
+    assert_eq!(
+        parser.error.unwrap().to_string(),
+        "Error de sintaxis: La interpolacion de la cadena esta vacia. Linea 1, columna 10."
+    );
+}
+
+#[test]
+fn for_loop() {
+    let input = "
+        var total = 0;
+        para i = 0; i < 10; i += 1 {
+            si i == 2 {
+                continuar;
+            }
+            si i == 6 {
+                romper;
+            }
+            total += i;
+        }
+        total;
+    ";
+    assert_eval(input, "13");
+
+    let input = "
+        var pasos = [];
+        para var i = 10; i > 0; i = i - 4 {
+            pasos.agregar(i);
+        }
+        pasos;
+    ";
+    assert_eval(input, "[10, 6, 2]");
+
+    let input = "
+        fn buscar() {
+            para i = 0; verdad; i += 1 {
+                si i == 4 {
+                    retornar i;
+                }
+            }
+        }
+        buscar();
+    ";
+    assert_eval(input, "4");
+
+    assert_eval(
+        "para i = 0; 5; i += 1 {}",
+        "Error de ejecución. La condicion de `para` debe ser un valor logico, no numerico entero. Linea 1, columna 13.",
+    );
+    assert_eval(
+        "para i = 0; nulo; i += 1 {}",
+        "Error de ejecución. La condicion de `para` debe ser un valor logico, no nulo. Linea 1, columna 16.",
+    );
+}
+
+#[test]
+fn for_loop_scope() {
+    let obj = eval_input("para i = 0; i < 2; i += 1 {}\ni;");
+    assert!(obj.to_string().contains("El identicador `i` no existe"));
+
+    let obj = eval_input("var i = 0;\npara i = 0; i < 2; i += 1 {}");
+    assert!(obj
+        .to_string()
+        .contains("Ya existe referencias hacia la variable `i`"));
+}
+
+#[test]
+fn nested_loops_break() {
+    let input = "
+        var vueltas = 0;
+        para i en rango(3) {
+            para j = 0; j < 3; j += 1 {
+                romper;
+            }
+            vueltas += 1;
+            si i == 1 {
+                romper;
+            }
+        }
+        vueltas;
+    ";
+    assert_eval(input, "2");
+}
+
+#[test]
+fn for_in_loop() {
+    let input = "
+        var total = 0;
+        para x en [1, 2, 3, 4] {
+            si x == 3 {
+                continuar;
+            }
+            total += x;
+        }
+        total;
+    ";
+    assert_eval(input, "7");
+
+    let input = "
+        var partes = [];
+        para i, x en [\"a\", \"b\"] {
+            partes.agregar(f\"{i}={x}\");
+        }
+        partes;
+    ";
+    assert_eval(input, "[0=a, 1=b]");
+
+    let input = "
+        var letras = [];
+        para c en \"pana\" {
+            si c == \"n\" {
+                romper;
+            }
+            letras.agregar(c);
+        }
+        letras;
+    ";
+    assert_eval(input, "[p, a]");
+
+    let input = "
+        var d = {\"uno\": 1};
+        var res = [];
+        para k en d {
+            res.agregar(k);
+        }
+        para k, v en d {
+            res.agregar(v);
+        }
+        res;
+    ";
+    assert_eval(input, "[uno, 1]");
+}
+
+#[test]
+fn for_in_loop_errors() {
+    let obj = eval_input("para x en 10 {}");
+    assert_eq!(
+        obj.to_string(),
+        "Error de ejecución. No se puede iterar el tipo de dato numerico entero. Linea 1, columna 12."
+    );
+
+    let obj = eval_input("var l = [1, 2];\npara x en l {\n    l.agregar(x);\n}");
+    assert_eq!(
+        obj.to_string(),
+        "Error de ejecución. No se puede modificar el tipo de dato lista mientras se recorre con un bucle `para`. Linea 3, columna 5."
+    );
+
+    let obj = eval_input("var l = [1, 2];\npara x en l {\n    l[0] = 3;\n}");
+    assert!(obj
+        .to_string()
+        .contains("No se puede modificar el tipo de dato lista mientras se recorre"));
+
+    // Despues del bucle se puede modificar
+    assert_eval("var l = [1];\npara x en l {}\nl.agregar(2);\nl;", "[1, 2]");
+}
+
+// Crea un directorio temporal con los archivos dados y evalua `principal.pana`
+fn eval_files(dir_name: &str, files: &[(&str, &str)]) -> ResultObj {
+    let dir = std::env::temp_dir().join(format!("pana_test_{}", dir_name));
+    let _ = std::fs::remove_dir_all(&dir);
+    for (name, source) in files {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, source).unwrap();
+    }
+
+    let main_path = dir.join("principal.pana");
+    let source = std::fs::read_to_string(&main_path).unwrap();
+    let mut parser = Parser::new(Lexer::new(source.chars().collect()));
+    let program = parser.parse();
+    if let Some(err) = parser.error {
+        panic!("Error del parser: {}", err);
+    }
+    let mut evaluator = Evaluator::new();
+    evaluator.set_main_file(&main_path);
+    let obj = evaluator.eval_program(program.clone());
+    let mut vm = Vm::new();
+    vm.set_main_file(&main_path);
+    let vm_obj = vm.run_program(program);
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(vm_obj.to_string(), obj.to_string(), "Maquina virtual");
+    obj
+}
+
+#[test]
+fn modules() {
+    let util = "
+        var contador = 0;
+        var PI = 3.14;
+        fn sumar(a, b) { retornar a + b; }
+        fn incrementar() { contador += 1; retornar contador; }
+    ";
+
+    let obj = eval_files(
+        "modulos_importar",
+        &[
+            ("util.pana", util),
+            (
+                "principal.pana",
+                r#"
+                    importar "util.pana" como u;
+                    importar "util.pana";
+                    u.incrementar();
+                    util.incrementar();
+                    [u.sumar(1, 2), u.PI, u.contador, util.contador];
+                "#,
+            ),
+        ],
+    );
+    assert_eq!(obj.to_string(), "[3, 3.14, 2, 2]");
+
+    // Las rutas son relativas al archivo que importa
+    let obj = eval_files(
+        "modulos_desde",
+        &[
+            ("lib/util.pana", util),
+            (
+                "lib/mate.pana",
+                r#"
+                    desde "util.pana" importar sumar;
+                    var cuatro = sumar(2, 2);
+                    fn doble(x) { retornar x * 2; }
+                "#,
+            ),
+            (
+                "principal.pana",
+                r#"
+                    desde "lib/mate.pana" importar doble;
+                    desde "lib/util.pana" importar sumar, PI;
+                    importar "lib/mate.pana" como m;
+                    [doble(4), sumar(PI, 1), m.cuatro];
+                "#,
+            ),
+        ],
+    );
+    assert_eq!(obj.to_string(), "[8, 4.140000000000001, 4]");
+}
+
+#[test]
+fn modules_errors() {
+    let obj = eval_files(
+        "modulos_no_existe",
+        &[("principal.pana", "importar \"nada.pana\" como n;")],
+    );
+    assert!(obj
+        .to_string()
+        .starts_with("Error de ejecución. No se encontro el modulo \"nada.pana\" en `"));
+
+    let obj = eval_files(
+        "modulos_circular",
+        &[
+            ("a.pana", "importar \"b.pana\" como b;"),
+            ("b.pana", "importar \"principal.pana\" como p;"),
+            ("principal.pana", "importar \"a.pana\" como a;"),
+        ],
+    );
+    assert_eq!(
+        obj.to_string(),
+        "Error de ejecución. Importacion circular entre modulos: principal.pana -> a.pana -> b.pana -> principal.pana. Linea 1, columna 8. Archivo \"b.pana\"."
+    );
+
+    let obj = eval_files(
+        "modulos_miembro",
+        &[
+            ("m.pana", "var a = 1;"),
+            ("principal.pana", "importar \"m.pana\" como m;\nm.b;"),
+        ],
+    );
+    // Los errores del programa principal indican su archivo
+    assert!(obj.to_string().starts_with(
+        "Error de ejecución. El modulo \"m.pana\" no tiene el miembro `b`. Linea 2, columna 3. Archivo \""
+    ));
+    assert!(obj.to_string().ends_with("principal.pana\"."));
+
+    let obj = eval_files(
+        "modulos_error_principal",
+        &[(
+            "principal.pana",
+            "var archivo = nulo;\nfn falla() { retornar 1 / 0; }\nintentar { falla(); } capturar e { archivo = e.archivo; }\narchivo;",
+        )],
+    );
+    assert!(obj.to_string().ends_with("principal.pana"));
+
+    let obj = eval_files(
+        "modulos_error_funcion",
+        &[
+            ("m.pana", "fn falla() {\n  retornar nada;\n}"),
+            (
+                "principal.pana",
+                "desde \"m.pana\" importar falla;\nfalla();",
+            ),
+        ],
+    );
+    assert_eq!(
+        obj.to_string(),
+        "Error de ejecución. El identicador `nada` no existe. Linea 2, columna 15. Archivo \"m.pana\"."
+    );
+
+    let obj = eval_files(
+        "modulos_error_sintaxis",
+        &[
+            ("m.pana", "var a = ;"),
+            ("principal.pana", "importar \"m.pana\" como m;"),
+        ],
+    );
+    assert!(obj.to_string().starts_with("Error de sintaxis"));
+    assert!(obj.to_string().ends_with("Archivo \"m.pana\"."));
+}
+
+#[test]
+fn math_module() {
+    assert_eval("importar \"mate\"; mate.raiz(16);", "4");
+    assert_eval(
+        "importar \"mate\" como m; tipo(m.raiz(4));",
+        "numerico flotante",
+    );
+    assert_eval("desde \"mate\" importar potencia; potencia(2, 10);", "1024");
+    assert_eval("desde \"mate\" importar potencia; potencia(2, -1);", "0.5");
+    assert_eval("desde \"mate\" importar potencia; potencia(4, 0.5);", "2");
+    assert_eval(
+        "desde \"mate\" importar abs; [abs(-3), abs(-2.5)];",
+        "[3, 2.5]",
+    );
+    assert_eval(
+        "desde \"mate\" importar piso, techo, redondear; [piso(2.7), techo(2.1), redondear(2.5), piso(3)];",
+        "[2, 3, 3, 3]",
+    );
+    assert_eval(
+        "importar \"mate\"; tipo(mate.piso(2.7));",
+        "numerico entero",
+    );
+    assert_eval("importar \"mate\"; mate.sen(0) + mate.cos(0);", "1");
+    assert_eval("importar \"mate\"; mate.redondear(mate.PI * 100);", "314");
+    assert_eval("importar \"mate\"; mate.ln(mate.E);", "1");
+    assert_eval(
+        "importar \"mate\"; [mate.log10(1000), mate.log2(8), mate.log(81, 3)];",
+        "[3, 3, 4]",
+    );
+    assert_eval(
+        "importar \"mate\"; [mate.min([3, 1.5, 2]), mate.max([3, 1.5, 7])];",
+        "[1.5, 7]",
+    );
+}
+
+#[test]
+fn big_integers() {
+    // Los enteros que no caben en 64 bits pasan a precision arbitraria
+    let input = "
+        fn fibonacci(n) {
+            var a = 0;
+            var b = 1;
+            para i en rango(n) {
+                var c = a + b;
+                a = b;
+                b = c;
+            }
+            retornar a;
+        }
+        fibonacci(100);
+    ";
+    assert_eval(input, "354224848179261915075");
+    assert_eval("9223372036854775807 + 1;", "9223372036854775808");
+    assert_eval("-9223372036854775807 - 2;", "-9223372036854775809");
+    assert_eval("4294967296 * 4294967296;", "18446744073709551616");
+    assert_eval(
+        "123456789012345678901234567890 * 10;",
+        "1234567890123456789012345678900",
+    );
+    assert_eval("0xffffffffffffffffff;", "4722366482869645213695");
+    // Vuelven a ser enteros de 64 bits cuando el resultado cabe
+    assert_eval(
+        "var a = 9223372036854775807 + 1; [a - 1, tipo(a), a % 1000, a / 2];",
+        "[9223372036854775807, numerico entero, 808, 4611686018427387904]",
+    );
+    assert_eval(
+        "var a = 100000000000000000000; [a > 9223372036854775807, a < 1.5, -a < 0, a == 100000000000000000000];",
+        "[verdad, falso, verdad, verdad]",
+    );
+    assert_eval("100000000000000000000 * 1.5;", "150000000000000000000");
+    assert_eval(
+        "var d = {100000000000000000000: \"grande\"}; d[10000000000 * 10000000000];",
+        "grande",
+    );
+    assert_eval(
+        "importar \"mate\"; [mate.potencia(10, 30), mate.abs(-100000000000000000000), mate.piso(100000000000000000000.5)];",
+        "[1000000000000000000000000000000, 100000000000000000000, 100000000000000000000]",
+    );
+    assert_eval("1 < 0.5;", "falso");
+    assert_eval(
+        "importar \"mate\"; var l = [9007199254740993, 9007199254740992]; [mate.max(l), mate.min(l)];",
+        "[9007199254740993, 9007199254740992]",
+    );
+    // El comparador de `ordenar` puede retornar enteros de cualquier tamaño
+    assert_eval(
+        "var l = [9223372036854775807, -9223372036854775807, 0]; l.ordenar(fn(a, b) { retornar a - b; });",
+        "[-9223372036854775807, 0, 9223372036854775807]",
+    );
+}
+
+#[test]
+fn mixed_numeric_comparisons() {
+    // Enteros y flotantes se comparan por su valor, `==` concuerda con `<=`
+    assert_eval(
+        "[1 == 1.0, 1 != 1.0, 1 <= 1.0 y 1 >= 1.0, 1 < 1.5, 2 > 1.5, 0.0 == -0.0];",
+        "[verdad, falso, verdad, verdad, verdad, verdad]",
+    );
+    // Sin perder precision con enteros que no caben en un flotante
+    assert_eval(
+        "[9007199254740993 == 9007199254740992.0, 9007199254740993 > 9007199254740992.0, 9223372036854775808 == 9223372036854775808.0];",
+        "[falso, verdad, verdad]",
+    );
+    assert_eval(
+        "var n = 0.0 / 0.0; [n == n, n < 1, 1 > n];",
+        "[falso, falso, falso]",
+    );
+    // `1` y `1.0` son la misma llave de un diccionario
+    assert_eval("var d = {2: \"a\"}; d[2.0] = \"b\"; d;", "{2: b}");
+    assert_eval(
+        "{1: \"a\", 1.0: \"b\"};",
+        "Error de ejecución. La llave `1` esta repetida en el diccionario. Linea 1, columna 12.",
+    );
+    assert_eval(
+        "[[1, 2] == [1.0, 2.0], {1: 2} == {1.0: 2.0}];",
+        "[verdad, verdad]",
+    );
+}
+
+#[test]
+fn division_by_zero() {
+    assert_eval(
+        "5 / 0;",
+        "Error de ejecución. No se puede dividir entre cero. Linea 1, columna 1.",
+    );
+    assert_eval(
+        "5 % 0;",
+        "Error de ejecución. No se puede dividir entre cero. Linea 1, columna 1.",
+    );
+    assert_eval(
+        "100000000000000000000 / 0;",
+        "Error de ejecución. No se puede dividir entre cero. Linea 1, columna 21.",
+    );
+    assert_eval(
+        "var e = \"\"; intentar { 5 % 0; } capturar err { e = err.mensaje; } e;",
+        "No se puede dividir entre cero",
+    );
+    assert_eval("[5 / 0.0, 5 / 2, -7 % 2];", "[inf, 2, -1]");
+}
+
+#[test]
+fn math_module_errors() {
+    assert_eval(
+        "importar \"mate\";\nmate.raiz(-1);",
+        "Error de ejecución. Dominio invalido para `raiz`: se espera un numero mayor o igual a 0, se encontro -1. Linea 2, columna 12.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.ln(0);",
+        "Error de ejecución. Dominio invalido para `ln`: se espera un numero mayor a 0, se encontro 0. Linea 2, columna 9.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.acos(2);",
+        "Error de ejecución. Dominio invalido para `acos`: se espera un numero entre -1 y 1, se encontro 2. Linea 2, columna 11.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.potencia(0, -1);",
+        "Error de ejecución. Dominio invalido para `potencia`: se espera un exponente positivo cuando la base es 0, se encontro -1. Linea 2, columna 19.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.potencia(2, 5000000000);",
+        "Error de ejecución. El resultado de `potencia` excede el rango de los enteros: 2 elevado a 5000000000. Linea 2, columna 27.",
+    );
+    // El resultado entero tiene un limite de tamaño, mas alla es un error de dominio
+    assert_eval(
+        "importar \"mate\";\nmate.potencia(2, 3000000000);",
+        "Error de ejecución. El resultado de `potencia` excede el rango de los enteros: 2 elevado a 3000000000. Linea 2, columna 27.",
+    );
+    assert_eval(
+        "importar \"mate\"; var t = \"\"; intentar { mate.potencia(10, 400000); } capturar e { t = e.tipo; } t;",
+        "dominio",
+    );
+    assert_eval(
+        "importar \"mate\"; [mate.potencia(2, 1000000) > 0, mate.potencia(-1, 3000000001)];",
+        "[verdad, -1]",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.raiz(\"a\");",
+        "Error de ejecución. `raiz` espera un tipo de dato numerico, no cadena. Linea 2, columna 13.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.raiz(1, 2);",
+        "Error de ejecución. Se encontro 2 argumentos, se espera raiz(x). Linea 2, columna 9.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.max([]);",
+        "Error de ejecución. `max` no puede operar con una lista vacia. Linea 2, columna 11.",
+    );
+    assert_eval(
+        "importar \"mate\";\nmate.sqrt(4);",
+        "Error de ejecución. El modulo \"mate\" no tiene el miembro `sqrt`. Linea 2, columna 9.",
+    );
+}
+
+#[test]
+fn file_module() {
+    let dir = std::env::temp_dir().join("pana_test_archivo");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let dir_str = dir.display().to_string();
+
+    let input = format!(
+        r#"
+        importar "archivo";
+        var dir = r"{dir_str}";
+        var ruta = dir + "/datos.txt";
+        var antes = archivo.existe(ruta);
+        archivo.escribir(ruta, "uno\ndos\n");
+        archivo.agregar(ruta, "tres");
+        archivo.escribir(dir + "/otro.txt", "");
+        var contenido = archivo.leer(ruta);
+        var lineas = archivo.lineas(ruta);
+        var nombres = archivo.listar(dir);
+        archivo.eliminar(ruta);
+        [antes, contenido.separar("\n"), lineas, nombres, archivo.existe(ruta)];
+    "#
+    );
+    assert_eval(
+        &input,
+        "[falso, [uno, dos, tres], [uno, dos, tres], [datos.txt, otro.txt], falso]",
+    );
+
+    let input = format!(
+        "desde \"archivo\" importar leer;\nleer(r\"{}/nada.txt\");",
+        dir_str
+    );
+    assert_eval(
+        &input,
+        &format!(
+            "Error de ejecución. No se pudo leer el archivo `{}/nada.txt`, no existe. Linea 2, columna {}.",
+            dir_str,
+            dir_str.len() + 17
+        ),
+    );
+    assert_eval(
+        "importar \"archivo\";\narchivo.escribir(\"a.txt\", 1);",
+        "Error de ejecución. `escribir` espera un tipo de dato cadena, no numerico entero. Linea 2, columna 27.",
+    );
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn system_module() {
+    let input = "importar \"sis\"; [sis.argumentos, longitud(sis.argumentos)];";
+    let mut parser = Parser::new(Lexer::new(input.chars().collect()));
+    let program = parser.parse();
+    let mut evaluator = Evaluator::new();
+    evaluator.set_script_args(vec!["datos.txt".to_owned(), "-v".to_owned()]);
+    assert_eq!(
+        evaluator.eval_program(program).to_string(),
+        "[[datos.txt, -v], 2]"
+    );
+
+    std::env::set_var("PANA_TEST_ENTORNO", "valor");
+    assert_eval(
+        "importar \"sis\"; [sis.entorno(\"PANA_TEST_ENTORNO\"), sis.entorno(\"PANA_TEST_NO_EXISTE\")];",
+        "[valor, nulo]",
+    );
+    assert_eval(
+        "importar \"sis\"; var t = sis.reloj(); sis.dormir(5); [sis.reloj() - t >= 5, sis.tiempo() > 0];",
+        "[verdad, verdad]",
+    );
+    assert_eval(
+        "importar \"sis\";\nsis.dormir(-1);",
+        "Error de ejecución. `dormir` espera un entero positivo de milisegundos, se encontro -1. Linea 2, columna 13.",
+    );
+    assert_eval(
+        "importar \"sis\";\nsis.salir(1.5);",
+        "Error de ejecución. `salir` espera un codigo entero de 32 bits, se encontro 1.5. Linea 2, columna 13.",
+    );
+    // `salir` no se captura, pero corren los bloques `finalmente`
+    let obj = eval_input(
+        "importar \"sis\";\nvar l = [];\nintentar { sis.salir(3); } capturar e { l.agregar(e); }",
+    );
+    match obj {
+        ResultObj::Copy(Object::Error(err)) => assert_eq!(err.salida, Some(3)),
+        obj => panic!("Se esperaba la salida del programa, se encontro {}", obj),
+    }
+    assert_eval(
+        "importar \"sis\";\nintentar { sis.salir(); } capturar e { 1; } finalmente { lanzar \"limpieza\"; }",
+        "Error de ejecución. limpieza. Linea 2, columna 63.",
+    );
+    // Los parametros opcionales muestran su valor por defecto
+    assert_eval(
+        "importar \"sis\"; [cadena(sis.salir), cadena(sis.dormir), cadena(leer)];",
+        "[fn salir(codigo = 0) {...}, fn dormir(milisegundos) {...}, fn leer(mensaje = nulo) {...}]",
+    );
+    assert_eval(
+        "importar \"sis\";\nsis.salir(1, 2);",
+        "Error de ejecución. Se encontro 2 argumentos, se espera salir(codigo = 0). Linea 2, columna 9.",
+    );
+}
+
+#[test]
+fn buildin_errors_propagate() {
+    assert_eval(
+        "imprimir(1, nada);\n2;",
+        "Error de ejecución. El identicador `nada` no existe. Linea 1, columna 16.",
+    );
+    assert_eval(
+        "tipo(nada);",
+        "Error de ejecución. El identicador `nada` no existe. Linea 1, columna 9.",
+    );
+    assert_eval(
+        "cadena(nada);",
+        "Error de ejecución. El identicador `nada` no existe. Linea 1, columna 11.",
+    );
+}
+
+#[test]
+fn try_catch() {
+    let input = "
+        var r = [];
+        intentar {
+            r.agregar(\"antes\");
+            sin_definir;
+            r.agregar(\"nunca\");
+        } capturar e {
+            r.agregar(e.tipo);
+            r.agregar(e.linea);
+            r.agregar(tipo(e));
+        } finalmente {
+            r.agregar(\"fin\");
+        }
+        r;
+    ";
+    assert_eval(input, "[antes, ejecucion, 5, error, fin]");
+
+    let input = "
+        fn dividir(a, b) {
+            si b == 0 {
+                lanzar \"division por cero\";
+            }
+            retornar a / b;
+        }
+        var mensaje = \"\";
+        intentar {
+            dividir(1, 0);
+        } capturar e {
+            mensaje = e.mensaje + \" \" + e.tipo + \" \" + cadena(e.linea);
+        }
+        [dividir(6, 3), mensaje];
+    ";
+    assert_eval(input, "[2, division por cero usuario 4]");
+
+    // Errores de modulos nativos con su tipo
+    assert_eval(
+        "importar \"mate\"; var t = \"\"; intentar { mate.raiz(-1); } capturar e { t = e.tipo; } t;",
+        "dominio",
+    );
+    assert_eval(
+        "importar \"archivo\"; var t = \"\"; intentar { archivo.leer(\"/no/existe.txt\"); } capturar e { t = e.tipo; } t;",
+        "archivo",
+    );
+
+    // Relanzar conserva el error original y `finalmente` siempre se ejecuta
+    let input = "
+        var pasos = [];
+        intentar {
+            intentar {
+                lanzar \"interno\";
+            } capturar e {
+                pasos.agregar(\"capturado\");
+                lanzar e;
+            } finalmente {
+                pasos.agregar(\"finalmente\");
+            }
+        } capturar e {
+            pasos.agregar(e.mensaje);
+            pasos.agregar(e.linea);
+        }
+        pasos;
+    ";
+    assert_eval(input, "[capturado, finalmente, interno, 5]");
+
+    // `finalmente` tambien corre al retornar y romper
+    let input = "
+        var pasos = [];
+        fn f() {
+            intentar {
+                retornar 1;
+            } finalmente {
+                pasos.agregar(\"f\");
+            }
+            retornar 2;
+        }
+        para i = 0; i < 3; i += 1 {
+            intentar {
+                si i == 1 { romper; }
+            } finalmente {
+                pasos.agregar(i);
+            }
+        }
+        [f(), pasos];
+    ";
+    assert_eval(input, "[1, [0, 1, f]]");
+}
+
+#[test]
+fn try_catch_errors() {
+    assert_eval(
+        "lanzar 1;",
+        "Error de ejecución. Solo se puede lanzar cadenas o errores, no el tipo de dato numerico entero. Linea 1, columna 6.",
+    );
+    assert_eval(
+        "var m = nulo;\nintentar { lanzar \"x\"; } capturar e { m = e.nombre; }\nm;",
+        "Error de ejecución. El tipo de dato error no posee el miembro `nombre`. Linea 2, columna 50.",
+    );
+    // Sin `capturar` el error sigue subiendo despues de `finalmente`
+    assert_eval(
+        "intentar { lanzar \"x\"; } finalmente { 1; }",
+        "Error de ejecución. x. Linea 1, columna 17.",
+    );
+
+    let mut parser = Parser::new(Lexer::new("intentar { 1; }".chars().collect()));
+    parser.parse();
+    assert_eq!(
+        parser.error.unwrap().to_string(),
+        "Error de sintaxis: El bloque `intentar` necesita un `capturar` o un `finalmente`. Linea 1, columna 8."
+    );
+}
+
+#[test]
+fn nested_return_and_continue() {
+    let input = "
+        fn f() {
+            si verdad {
+                retornar 1;
+                imprimir(\"no\");
+            }
+            retornar 2;
+        }
+        fn g() {
+            mientras verdad {
+                si verdad {
+                    retornar 3;
+                }
+            }
+            retornar 4;
+        }
+        var impares = [];
+        para i = 0; i < 6; i += 1 {
+            si i % 2 == 0 {
+                continuar;
+                impares.agregar(\"no\");
+            }
+            impares.agregar(i);
+        }
+        [f(), g(), impares];
+    ";
+    assert_eval(input, "[1, 3, [1, 3, 5]]");
+}
+
+#[test]
+fn local_variables() {
+    // Los argumentos se evaluan donde se llama a la funcion
+    let input = "
+        fn f(a, b) { retornar [a, b]; }
+        fn g() {
+            var b = 5;
+            retornar f(b, 1);
+        }
+        g();
+    ";
+    assert_eval(input, "[5, 1]");
+
+    let input = "
+        fn fib(n) {
+            si n < 2 { retornar n; }
+            retornar fib(n - 1) + fib(n - 2);
+        }
+        fib(15);
+    ";
+    assert_eval(input, "610");
+
+    // Funciones locales que se llaman entre si
+    let input = "
+        fn g() {
+            fn par(n) { si n == 0 { retornar verdad; } retornar impar(n - 1); }
+            fn impar(n) { si n == 0 { retornar falso; } retornar par(n - 1); }
+            retornar [par(10), impar(7)];
+        }
+        g();
+    ";
+    assert_eval(input, "[verdad, verdad]");
+
+    let input = "
+        fn g() {
+            var total = 0;
+            para i en rango(4) { total += i; }
+            para i, x en [10, 20] { total = total + i * x; }
+            para i = 0; i < 3; i += 1 { total += 1; }
+            intentar { lanzar \"uy\"; } capturar e { total += longitud(e.mensaje); }
+            retornar total;
+        }
+        g();
+    ";
+    assert_eval(input, "31");
+
+    assert_eval(
+        "fn g() {\n    fn f() { retornar w; }\n    var r = f();\n    var w = 2;\n}\ng();",
+        "Error de ejecución. El identicador `w` no existe. Linea 2, columna 23.",
+    );
+    assert_eval(
+        "var x = 1;\nfn g() {\n    var x = 2;\n}\ng();",
+        "Error de ejecución. El identificador `x` ya habia sido declarado. Linea 3, columna 13.",
+    );
+}
+
+#[test]
+fn dictionary_keys() {
+    let input = "
+        var d = {1: \"a\", \"b\": 2, verdad: 3, nulo: 4, 1.5: 5};
+        d[\"c\"] = 6;
+        [d[1], d[\"b\"], d[verdad], d[nulo], d[1.5], d[\"c\"], longitud(d)];
+    ";
+    assert_eval(input, "[a, 2, 3, 4, 5, 6, 6]");
+
+    // La llave se compara por su contenido, no por su tipo
+    let input = "
+        var d = {\"a\": 1};
+        d[\"b\"] = 2;
+        d[\"a\"] = 3;
+        [d[\"a\"], d[\"b\"], d.eliminar(\"b\"), longitud(d)];
+    ";
+    assert_eval(input, "[3, 2, 2, 1]");
+
+    let input = "
+        var igual = {\"a\": 1, \"b\": [1, 2]} == {\"b\": [1, 2], \"a\": 1};
+        var distinto = {\"a\": 1} == {\"a\": 2};
+        [igual, distinto, {\"a\": 1} != {\"b\": 1}];
+    ";
+    assert_eval(input, "[verdad, falso, verdad]");
+
+    let input = "
+        var f = fn() { retornar 1; };
+        var g = f;
+        [f == g, f == fn() { retornar 1; }, imprimir != imprimir];
+    ";
+    assert_eval(input, "[verdad, falso, falso]");
+
+    let input = "
+        var l = [1];
+        l.agregar(l);
+        l == l;
+    ";
+    assert_eval(input, "verdad");
+
+    let errors = [
+        (
+            "var d = {[1]: 2};",
+            "Error de ejecución. No se puede usar el tipo de dato `lista` como llave de un diccionario. Linea 1, columna 12.",
+        ),
+        (
+            "var d = {};\nd[d] = 1;",
+            "Error de ejecución. No se puede usar el tipo de dato `diccionario` como llave de un diccionario. Linea 2, columna 3.",
+        ),
+        (
+            "var d = {};\nd[[1]];",
+            "Error de ejecución. No se puede usar el tipo de dato `lista` como llave de un diccionario. Linea 2, columna 5.",
+        ),
+        (
+            "var d = {};\nd.eliminar([]);",
+            "Error de ejecución. No se puede usar el tipo de dato `lista` como llave de un diccionario. Linea 2, columna 13.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn ordered_dictionaries() {
+    let input = "
+        var d = {\"z\": 1, \"a\": 2, \"m\": 3, 10: 4, verdad: 5};
+        d[\"b\"] = 6;
+        d[\"z\"] = 7;
+        d.eliminar(\"a\");
+        [d, d.llaves(), d.valores()];
+    ";
+    assert_eval(
+        input,
+        "[{z: 7, m: 3, 10: 4, verdad: 5, b: 6}, [z, m, 10, verdad, b], [7, 3, 4, 5, 6]]",
+    );
+
+    let input = "
+        var d = {\"uno\": 1, \"dos\": 2, \"tres\": 3};
+        var orden = \"\";
+        para k, v en d { orden = orden + k + \" \"; }
+        orden;
+    ";
+    assert_eval(input, "uno dos tres ");
+
+    let errors = [
+        (
+            "var d = {1: 1, 2: 2, 1: 3};",
+            "Error de ejecución. La llave `1` esta repetida en el diccionario. Linea 1, columna 22.",
+        ),
+        (
+            "var x = \"a\";\nvar d = {\"a\": 1, x: 2};",
+            "Error de ejecución. La llave `a` esta repetida en el diccionario. Linea 2, columna 18.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn higher_order_members() {
+    let input = "
+        fn par(x) { retornar x % 2 == 0; }
+        var l = [3, 1, 2, 4];
+        var suma = fn(a, b) { retornar a + b; };
+        [l.mapear(fn(x) { retornar x * 2; }), l.filtrar(par), l.reducir(suma), l.reducir(suma, 10)];
+    ";
+    assert_eval(input, "[[6, 2, 4, 8], [2, 4], 10, 20]");
+
+    let input = "
+        var visto = [];
+        [1, 2, 3].cada(fn(x) { visto.agregar(x * x); });
+        [visto, [1, 3].alguno(fn(x) { retornar x > 2; }), [1, 3].todos(fn(x) { retornar x > 2; })];
+    ";
+    assert_eval(input, "[[1, 4, 9], verdad, falso]");
+
+    // Las funciones internas tambien se pueden pasar
+    assert_eval("[\"ccc\", \"a\", \"bb\"].mapear(longitud);", "[3, 1, 2]");
+
+    // `ordenar` es estable, con una llave o con un comparador
+    let input = "
+        var p = [[\"b\", 2], [\"a\", 2], [\"c\", 1]];
+        p.ordenar(fn(x) { retornar x[1]; });
+    ";
+    assert_eval(input, "[[c, 1], [b, 2], [a, 2]]");
+    let input = "
+        var l = [3, 1, 2];
+        l.ordenar(fn(a, b) { retornar b - a; });
+        l;
+    ";
+    assert_eval(input, "[3, 2, 1]");
+    assert_eval(
+        "[\"ccc\", \"a\", \"bb\"].ordenar(longitud);",
+        "[a, bb, ccc]",
+    );
+    assert_eval("[3, 1, 2].ordenar();", "[1, 2, 3]");
+
+    let errors = [
+        (
+            "[1, 2].mapear(fn(x) {\n    lanzar \"uy\";\n});",
+            "Error de ejecución. uy. Linea 2, columna 10.",
+        ),
+        (
+            "[1, \"a\"].ordenar();",
+            "Error de ejecución. No se puede comparar el tipo de dato numerico entero con cadena. Linea 1, columna 8.",
+        ),
+        (
+            "var l = [];\nl.reducir(fn(a, b) { retornar a; });",
+            "Error de ejecución. No se puede reducir una lista vacia sin un valor inicial. Linea 2, columna 34.",
+        ),
+        (
+            "var f = fn(x) { retornar \"a\"; };\n[1].filtrar(f);",
+            "Error de ejecución. La funcion de `filtrar` debe retornar un valor logico, no cadena. Linea 2, columna 13.",
+        ),
+        (
+            "[1].filtrar(fn(x) { retornar [x]; });",
+            "Error de ejecución. La funcion de `filtrar` debe retornar un valor logico, no lista. Linea 1, columna 35.",
+        ),
+        (
+            "[1].mapear(1);",
+            "Error de ejecución. La operacion de llamada solo puede ser aplicada a objetos que sean funciones. Linea 1, columna 12.",
+        ),
+        (
+            "var l = [2, 1];\npara x en l { l.ordenar(fn(a) { retornar a; }); }",
+            "Error de ejecución. No se puede modificar el tipo de dato lista mientras se recorre con un bucle `para`. Linea 2, columna 15.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn sort_inconsistent_comparator() {
+    // Un comparador que no es un orden total no debe detener el interprete
+    let input = "
+        var l = [];
+        para i = 0; i < 200; i += 1 { l.agregar((i * 7) % 200); }
+        var c = 0;
+        l.ordenar(fn(a, b) { c += 1; retornar (a * 31 + b * 17 + c) % 3 - 1; });
+        longitud(l);
+    ";
+    assert_eval(input, "200");
+    let input = "
+        clase A {
+            fn iniciar(x) { este.x = x; }
+            fn menor(otro) { retornar (este.x + otro.x) % 2 == 0; }
+        }
+        var l = [];
+        para i = 0; i < 200; i += 1 { l.agregar(A(i)); }
+        longitud(l.ordenar());
+    ";
+    assert_eval(input, "200");
+
+    // Se detiene en el primer error del comparador
+    let input = "
+        var l = [];
+        para i = 0; i < 200; i += 1 { l.agregar(i % 13); }
+        var c = 0;
+        l.ordenar(fn(a, b) {
+            c += 1;
+            si c == 50 { lanzar \"uy\"; }
+            retornar (a + b + c) % 3 - 1;
+        });
+    ";
+    assert_eval(input, "Error de ejecución. uy. Linea 7, columna 31.");
+}
+
+#[test]
+fn classes() {
+    let input = "
+        clase Punto {
+            fn iniciar(x, y) {
+                este.x = x;
+                este.y = y;
+            }
+
+            fn distancia(otro) {
+                var dx = este.x - otro.x;
+                var dy = este.y - otro.y;
+                retornar dx * dx + dy * dy;
+            }
+
+            fn mover(dx) {
+                este.x += dx;
+                retornar este;
+            }
+        }
+        var p = Punto(1, 2);
+        var q = Punto(4, 6);
+        [p.distancia(q), p.mover(2).x, p.y, tipo(p), tipo(Punto)];
+    ";
+    assert_eval(input, "[25, 3, 2, Punto, clase]");
+
+    // Los campos se muestran en el orden en que se asignaron
+    assert_eval(
+        "clase A {}\nvar a = A();\na.b = 1;\na.c = \"dos\";\na;",
+        "A {b: 1, c: dos}",
+    );
+    // Los metodos recuerdan a su instancia
+    assert_eval(
+        "clase A { fn yo() { retornar este; } }\nvar a = A();\nvar f = a.yo;\n[f() == a, A() == a];",
+        "[verdad, falso]",
+    );
+    // Los campos ocultan a los metodos con el mismo nombre
+    assert_eval(
+        "clase A { fn f() { retornar 1; } }\nvar a = A();\na.f = 2;\na.f;",
+        "2",
+    );
+
+    let errors = [
+        (
+            "clase A {}\nA().b;",
+            "Error de ejecución. No se encontro el miembro `b` en la clase `A`. Linea 2, columna 5.",
+        ),
+        (
+            "clase A {}\nA(1);",
+            "Error de ejecución. Se encontro 1 argumentos, se espera A(). Linea 2, columna 1.",
+        ),
+        (
+            "clase A { fn iniciar() { lanzar \"uy\"; } }\nA();",
+            "Error de ejecución. uy. Linea 1, columna 31.",
+        ),
+        (
+            "var l = [1];\nl.x = 2;",
+            "Error de ejecución. No se puede asignar el miembro `x` al tipo de dato lista. Linea 2, columna 1.",
+        ),
+        (
+            "imprimir(este);",
+            "Error de sintaxis: `este` solo se puede usar dentro de los metodos de una clase. Linea 1, columna 13.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn inheritance() {
+    let input = "
+        clase Animal {
+            fn iniciar(nombre) {
+                este.nombre = nombre;
+            }
+            fn sonido() { retornar \"...\"; }
+            fn hablar() { retornar este.nombre + \" dice \" + este.sonido(); }
+        }
+        clase Perro hereda Animal {
+            fn iniciar(nombre, raza) {
+                super.iniciar(nombre);
+                este.raza = raza;
+            }
+            fn sonido() { retornar \"guau\"; }
+        }
+        clase Cachorro hereda Perro {
+            fn sonido() { retornar \"yip \" + super.sonido(); }
+        }
+        var p = Perro(\"Rex\", \"pastor\");
+        var c = Cachorro(\"Bo\", \"mestizo\");
+        [p.hablar(), c.hablar(), c.raza, tipo(c)];
+    ";
+    assert_eval(
+        input,
+        "[Rex dice guau, Bo dice yip guau, mestizo, Cachorro]",
+    );
+
+    let input = "
+        clase A {}
+        clase B hereda A {}
+        var b = B();
+        [es_instancia(b, A), es_instancia(b, B), es_instancia(A(), B), es_instancia(1, A)];
+    ";
+    assert_eval(input, "[verdad, verdad, falso, falso]");
+
+    let errors = [
+        (
+            "clase A {}\nclase B hereda A {}\nB().c;",
+            "Error de ejecución. No se encontro el miembro `c` en la clase `B` ni en `A`. Linea 3, columna 5.",
+        ),
+        (
+            "clase A {}\nclase B hereda A { fn f() { retornar super.f(); } }\nB().f();",
+            "Error de ejecución. No se encontro el miembro `f` en la clase `A`. Linea 2, columna 44.",
+        ),
+        (
+            "var x = 1;\nclase A hereda x {}",
+            "Error de ejecución. Una clase solo puede heredar de otra clase, no de numerico entero. Linea 2, columna 16.",
+        ),
+        (
+            "clase A {}\nes_instancia(A(), 1);",
+            "Error de ejecución. El segundo argumento de `es_instancia` debe ser una clase, no numerico entero. Linea 2, columna 12.",
+        ),
+        (
+            "clase A { fn f() { retornar super.f(); } }",
+            "Error de sintaxis: `super` solo se puede usar dentro de los metodos de una clase que hereda. Linea 1, columna 33.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn operator_overloading() {
+    let input = "
+        clase Vec {
+            fn iniciar(x, y) {
+                este.x = x;
+                este.y = y;
+            }
+            fn sumar(otro) { retornar Vec(este.x + otro.x, este.y + otro.y); }
+            fn igual(otro) { retornar este.x == otro.x y este.y == otro.y; }
+            fn menor(otro) { retornar este.x < otro.x; }
+            fn texto() { retornar f\"({este.x}, {este.y})\"; }
+        }
+        var a = Vec(1, 2);
+        var b = Vec(3, 4);
+        [cadena(a + b), a == Vec(1, 2), a != b, a < b, f\"{a}\"];
+    ";
+    assert_eval(input, "[(4, 6), verdad, verdad, verdad, (1, 2)]");
+
+    let input = "
+        clase P {
+            fn iniciar(x) { este.x = x; }
+            fn menor(otro) { retornar este.x < otro.x; }
+            fn texto() { retornar f\"P{este.x}\"; }
+        }
+        var l = [P(3), P(1), P(2)];
+        [cadena(l.ordenar()), cadena({\"a\": [P(5)]}), f\"{l}\"];
+    ";
+    assert_eval(input, "[[P1, P2, P3], {a: [P5]}, [P1, P2, P3]]");
+    assert_eval(
+        "clase A { fn menor(b) { retornar 1; } }\n[A(), A()].ordenar();",
+        "Error de ejecución. El metodo `menor` debe retornar un valor logico, no numerico entero. Linea 2, columna 10.",
+    );
+
+    let errors = [
+        (
+            "clase A {}\nA() - A();",
+            "Error de ejecución. No se soporta operaciones A - A. Linea 2, columna 3.",
+        ),
+        (
+            "1 + [1];",
+            "Error de ejecución. No se soporta operaciones numerico entero + lista. Linea 1, columna 1.",
+        ),
+        (
+            "\"a\" - \"b\";",
+            "Error de ejecución. No se soporta operaciones cadena - cadena. Linea 1, columna 3.",
+        ),
+        (
+            "{\"a\": 1} < {\"a\": 1};",
+            "Error de ejecución. No se soporta operaciones diccionario < diccionario. Linea 1, columna 8.",
+        ),
+        (
+            "clase A { fn igual(b) { retornar 1; } }\nA() != A();",
+            "Error de ejecución. El metodo `igual` debe retornar un valor logico, no numerico entero. Linea 2, columna 3.",
+        ),
+        (
+            "clase A { fn texto() { retornar 1; } }\ncadena(A());",
+            "Error de ejecución. El metodo `texto` debe retornar una cadena, no numerico entero. Linea 2, columna 10.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+}
+
+#[test]
+fn pattern_matching() {
+    let input = "
+        fn describir(valor) {
+            retornar segun valor {
+                caso 1, 2 => \"uno o dos\",
+                caso -1 => \"menos uno\",
+                caso [a, [b, _]] => f\"lista {a} {b}\",
+                caso {\"nombre\": n, \"edad\": e} si e >= 18 => f\"adulto {n}\",
+                caso {\"nombre\": n} => f\"hola {n}\",
+                caso n si tipo(n) == \"numerico entero\" y n > 3 => {
+                    var doble = n * 2;
+                    f\"grande {doble}\"
+                }
+                caso _ => \"otro\"
+            };
+        }
+        var ana = {\"nombre\": \"ana\", \"edad\": 20};
+        var luis = {\"nombre\": \"luis\", \"edad\": 9};
+        [2, -1, [1, [2, 3]], [1, 2], ana, luis, 5, verdad, \"1\"].mapear(describir);
+    ";
+    assert_eval(
+        input,
+        "[uno o dos, menos uno, lista 1 2, otro, adulto ana, hola luis, grande 10, otro, otro]",
+    );
+
+    // Sin caso que coincida no hay valor
+    // Sin un caso que coincida el resultado es `nulo`, en ambos motores
+    assert_eval("segun 1 { caso 2 => 3 }", "nulo");
+    assert_eval(
+        "var x = segun 1 { caso 2 => 3 }; [x, f\"{segun [1, 2] { caso [a] => a }}\"];",
+        "[nulo, nulo]",
+    );
+    assert_eval("segun nulo { caso nulo => 1 }", "1");
+    // Las variables del caso no existen fuera de el
+    assert_eval("var x = segun [1] { caso [a] => a }; x;", "1");
+    assert_eval(
+        "segun [1] { caso [a] => a }; a;",
+        "Error de ejecución. El identicador `a` no existe. Linea 1, columna 30.",
+    );
+    assert_eval(
+        "var s = 0; para i en rango(5) { segun i { caso 1 => { continuar; } caso 3 => { romper; } caso n => { s += n; } } } s;",
+        "2",
+    );
+    assert_eval(
+        "var a = 1; fn f() { var b = 2; retornar segun b { caso a => a }; } f();",
+        "2",
+    );
+
+    let errors = [
+        (
+            "fn f(a) { retornar segun 1 { caso a => a }; }",
+            "Error de sintaxis: Ya existe referencias hacia la variable `a`. Linea 1, columna 33.",
+        ),
+        (
+            "segun 1 { caso 1 => 1 / \"a\" }",
+            "Error de ejecución. No se soporta operaciones numerico entero / cadena. Linea 1, columna 21.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+
+    let parser_errors = [
+        (
+            "segun 1 { caso 1 2 }",
+            "Error de sintaxis: Falta el `=>`. Linea 1, columna 18.",
+        ),
+        (
+            "segun 1 { caso [a], 2 => 1 }",
+            "Error de sintaxis: Un caso con varios patrones no puede declarar variables. Linea 1, columna 14.",
+        ),
+        (
+            "segun 1 { caso {a: 1} => 1 }",
+            "Error de sintaxis: Las llaves de un patron de diccionario deben ser literales. Linea 1, columna 17.",
+        ),
+        (
+            "segun 1 { 1 => 1 }",
+            "Error de sintaxis: Dentro de `segun` solo se puede declarar casos con `caso`. Linea 1, columna 11.",
+        ),
+    ];
+    for (input, expected) in parser_errors {
+        let mut parser = Parser::new(Lexer::new(input.chars().collect()));
+        parser.parse();
+        assert_eq!(
+            parser.error.unwrap().to_string(),
+            expected,
+            "Entrada: {}",
+            input
+        );
+    }
+
+    // Con un valor literal se avisa de los casos que nunca se ejecutan
+    let mut parser = Parser::new(Lexer::new(
+        "segun [1, 2] { caso [a] => 1, caso \"x\" => 2, caso [1, z] => z, caso _ => 3, caso 4 => 4 }"
+            .chars()
+            .collect(),
+    ));
+    parser.parse();
+    let warnings: Vec<String> = parser.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(
+        warnings,
+        [
+            "Advertencia: El caso nunca coincide con el valor de `segun`. Linea 1, columna 19.",
+            "Advertencia: El caso nunca coincide con el valor de `segun`. Linea 1, columna 34.",
+            "Advertencia: El caso nunca se ejecuta, uno anterior coincide con cualquier valor. Linea 1, columna 80.",
+        ]
+    );
+}
+
+#[test]
+fn fn_params() {
+    let input = "
+        fn saludo(nombre, saludo = \"hola\", signo = saludo + \"!\") {
+            retornar f\"{saludo} {nombre} {signo}\";
+        }
+        [saludo(\"ana\"), saludo(\"luis\", \"chao\"), saludo(signo: \"?\", nombre: \"eva\")];
+    ";
+    assert_eval(input, "[hola ana hola!, chao luis chao!, hola eva ?]");
+
+    let input = "
+        fn sumar(base, ...resto) {
+            var total = base;
+            para n en resto {
+                total += n;
+            }
+            retornar total;
+        }
+        [sumar(1), sumar(1, 2, 3), sumar(base: 10)];
+    ";
+    assert_eval(input, "[1, 6, 10]");
+
+    // El valor por defecto se evalua en cada llamada
+    assert_eval(
+        "fn f(l = []) { l.agregar(1); retornar l; } f(); f();",
+        "[1]",
+    );
+    assert_eval(
+        "clase Caja { fn iniciar(ancho = 0, alto = 0) { este.ancho = ancho; este.alto = alto; } } var c = Caja(alto: 2); [c.ancho, c.alto];",
+        "[0, 2]",
+    );
+    assert_eval(
+        "var f = fn(a, b = 1) { a - b }; [f(b: 2, a: 5), f(3)];",
+        "[3, 2]",
+    );
+    assert_eval(
+        "importar \"mate\"; mate.potencia(exponente: 3, base: 2);",
+        "8",
+    );
+    assert_eval("tipo(valor: 1);", "numerico entero");
+    assert_eval("imprimir; fn f(...a) {} f;", "fn f(...a) {...}");
+
+    let errors = [
+        (
+            "fn f(a, b = 2) {} f();",
+            "Error de ejecución. Falta el argumento `a`, se espera f(a, b = 2). Linea 1, columna 19.",
+        ),
+        (
+            "fn f(a) {} f(1, 2);",
+            "Error de ejecución. Se encontro 2 argumentos, se espera f(a). Linea 1, columna 12.",
+        ),
+        (
+            "var f = fn(a) {}; f(b: 1);",
+            "Error de ejecución. No existe el parametro `b`, se espera fn(a). Linea 1, columna 19.",
+        ),
+        (
+            "fn f(a, b) {} f(1, a: 2);",
+            "Error de ejecución. El argumento `a` se paso mas de una vez, se espera f(a, b). Linea 1, columna 15.",
+        ),
+        (
+            "fn f(...a) {} f(a: 1);",
+            "Error de ejecución. El parametro `...a` no puede recibir argumentos con nombre, se espera f(...a). Linea 1, columna 15.",
+        ),
+        (
+            "longitud(lista: [1]);",
+            "Error de ejecución. No existe el parametro `lista`, se espera longitud(valor). Linea 1, columna 8.",
+        ),
+        (
+            "[1].agregar(valor: 2);",
+            "Error de ejecución. Los argumentos con nombre solo se pueden usar al llamar funciones. Linea 1, columna 17.",
+        ),
+    ];
+    for (input, expected) in errors {
+        assert_eval(input, expected);
+    }
+
+    let parser_errors = [
+        (
+            "fn f(...a, b) {}",
+            "Error de sintaxis: El parametro `...a` debe ser el ultimo. Linea 1, columna 9.",
+        ),
+        (
+            "fn f(a = 1, b) {}",
+            "Error de sintaxis: El parametro `b` necesita un valor por defecto, va despues de `a`. Linea 1, columna 13.",
+        ),
+        (
+            "f(a: 1, a: 2);",
+            "Error de sintaxis: El argumento `a` esta repetido. Linea 1, columna 9.",
+        ),
+        (
+            "f(a: 1, 2);",
+            "Error de sintaxis: Los argumentos sin nombre van antes de los argumentos con nombre. Linea 1, columna 9.",
+        ),
+    ];
+    for (input, expected) in parser_errors {
+        let mut parser = Parser::new(Lexer::new(input.chars().collect()));
+        parser.parse();
+        assert_eq!(
+            parser.error.unwrap().to_string(),
+            expected,
+            "Entrada: {}",
+            input
+        );
+    }
+}
+
+#[test]
+fn call_arguments() {
+    // Los argumentos se evaluan en el environment de quien llama
+    assert_eval("var b = 10; fn f(a, b) { a + b } f(b, 1);", "11");
+    assert_eval(
+        "fn g(a) { fn h(b, a) { retornar b - a; } retornar h(a, 1); } g(5);",
+        "4",
+    );
+    assert_eval(
+        "var x = 1; var f = fn(x, y = x) { [x, y] }; f(x + 1);",
+        "[2, 2]",
+    );
+    assert_eval(
+        "clase A { fn iniciar(v) { este.v = v; } } var v = 3; A(v * 2).v;",
+        "6",
+    );
+
+    // Una sola vez y de izquierda a derecha, tambien con nombre
+    let input = "
+        var orden = [];
+        fn marcar(x) {
+            orden.agregar(x);
+            retornar x;
+        }
+        fn f(a, b) { [a, b] }
+        [f(b: marcar(1), a: marcar(2)), orden];
+    ";
+    assert_eval(input, "[[2, 1], [1, 2]]");
+    let input = "
+        var n = 0;
+        fn contar() {
+            n += 1;
+            retornar n;
+        }
+        var l = [];
+        l.agregar(contar());
+        [cadena(contar()), tipo(contar()), n, l];
+    ";
+    assert_eval(input, "[2, numerico entero, 3, [1]]");
+    assert_eval("var l = [1]; l.agregar(longitud(l)); l;", "[1, 1]");
+
+    // El error de un argumento detiene la llamada antes de revisar los demas
+    assert_eval(
+        "fn f(a) {} f(1, x);",
+        "Error de ejecución. El identicador `x` no existe. Linea 1, columna 17.",
+    );
+    assert_eval(
+        "[1].agregar(1, x);",
+        "Error de ejecución. El identicador `x` no existe. Linea 1, columna 16.",
+    );
+}
+
+// Evalua con un maximo de llamadas anidadas, igual en ambos
+fn assert_eval_depth(input: &str, max_depth: usize, expected: &str) {
+    let mut evaluator = Evaluator::new();
+    evaluator.set_max_depth(max_depth);
+    let obj = evaluator.eval_program(parse_input(input));
+    let mut vm = Vm::new();
+    vm.set_max_depth(max_depth);
+    let vm_obj = vm.run_program(parse_input(input));
+    assert_eq!(
+        vm_obj.to_string(),
+        obj.to_string(),
+        "Maquina virtual, entrada: {}",
+        input
+    );
+    assert_eq!(obj.to_string(), expected, "Entrada: {}", input);
+}
+
+#[test]
+fn max_depth() {
+    let input = "fn f(n) { si n == 0 { retornar 0; } retornar 1 + f(n - 1); } f(9);";
+    assert_eval_depth(input, 10, "9");
+    assert_eval_depth(
+        "fn f(n) { si n == 0 { retornar 0; } retornar 1 + f(n - 1); } f(10);",
+        10,
+        "Error de ejecución. profundidad máxima de recursión excedida, se permiten 10 llamadas anidadas. Linea 1, columna 50.",
+    );
+    // El error se puede capturar y las llamadas siguientes empiezan de cero
+    let input = "
+        fn infinita() { retornar 1 + infinita(); }
+        var mensaje = \"\";
+        intentar { infinita(); } capturar e { mensaje = e.mensaje; }
+        fn f(n) { si n == 0 { retornar 0; } retornar 1 + f(n - 1); }
+        [mensaje, f(15)];
+    ";
+    assert_eval_depth(
+        input,
+        20,
+        "[profundidad máxima de recursión excedida, se permiten 20 llamadas anidadas, 15]",
+    );
+    // Tambien cuenta las funciones que reciben las funciones miembro
+    assert_eval_depth(
+        "fn f(l) { retornar l.mapear(f); } f([[[[1]]]]);",
+        3,
+        "Error de ejecución. profundidad máxima de recursión excedida, se permiten 3 llamadas anidadas. Linea 1, columna 29.",
+    );
+}
+
+#[test]
+fn vm_large_max_depth() {
+    // La maquina virtual guarda las llamadas en su propia pila, no necesita
+    // una pila nativa grande. Las pruebas corren en un hilo de pocos MB
+    let input = "fn f(n) { si n == 0 { retornar 0; } retornar 1 + f(n - 1); } f(150000);";
+    let mut vm = Vm::new();
+    vm.set_max_depth(200000);
+    assert_eq!(vm.run_program(parse_input(input)).to_string(), "150000");
+    let mut vm = Vm::new();
+    vm.set_max_depth(100000);
+    assert_eq!(
+        vm.run_program(parse_input(input)).to_string(),
+        "Error de ejecución. profundidad máxima de recursión excedida, se permiten 100000 llamadas anidadas. Linea 1, columna 50.",
+    );
+}
+
+#[test]
+fn tail_calls() {
+    // Las llamadas en posicion de cola no aumentan la profundidad
+    let input = "
+        fn contar(n, total) {
+            si n == 0 { retornar total; }
+            retornar contar(n - 1, total + 1);
+        }
+        contar(1000000, 0);
+    ";
+    assert_eval_depth(input, 10, "1000000");
+    let input = "
+        var sumar = fn(n, total) {
+            si n == 0 { retornar total; }
+            retornar sumar(n - 1, total + n);
+        };
+        sumar(100000, 0);
+    ";
+    assert_eval_depth(input, 10, "5000050000");
+    let input = "
+        fn es_par(n) { si n == 0 { retornar verdad; } retornar es_impar(n - 1); }
+        fn es_impar(n) { si n == 0 { retornar falso; } retornar es_par(n - 1); }
+        es_par(10001);
+    ";
+    assert_eval_depth(input, 10, "falso");
+    // Salir del bucle libera la lista antes de la siguiente llamada
+    let input = "
+        fn recorrer(l, n) {
+            para x en l {
+                si n == 0 { retornar x; }
+                retornar recorrer(l, n - 1);
+            }
+        }
+        var l = [1, 2];
+        recorrer(l, 100);
+        l.agregar(3);
+        l;
+    ";
+    assert_eval_depth(input, 10, "[1, 2, 3]");
+    // Dentro de `intentar` la llamada si aumenta la profundidad
+    let input = "
+        fn f(n) {
+            intentar {
+                si n == 0 { retornar 0; }
+                retornar f(n - 1);
+            } capturar e {
+                retornar -1;
+            }
+        }
+        f(20);
+    ";
+    assert_eval_depth(input, 10, "-1");
+    assert_eval_depth(
+        "fn f(a) { retornar f(1, 2); } f(1);",
+        10,
+        "Error de ejecución. Se encontro 2 argumentos, se espera f(a). Linea 1, columna 20.",
+    );
+}
@@ -1,6 +1,6 @@
 use crate::{
     lexer::Lexer,
-    token::{Token, TokenType},
+    token::{StringPart, Token, TokenType},
     types::Numeric,
 };
 
@@ -13,6 +13,10 @@ fn gen_tokens(input: &str) -> Vec<Token> {
             tokens.push(token);
             return tokens;
         }
+        // Los saltos de linea solo delimitan, el parser los ignora
+        if token.r#type.eq(&TokenType::NewLine) {
+            continue;
+        }
         tokens.push(token);
     }
 }
@@ -65,7 +69,7 @@ fn one_char_tokens() {
 
 #[test]
 fn two_char_token() {
-    let input = "== != <= >=";
+    let input = "== != <= >= => = >";
     let tokens = gen_tokens(input);
 
     let test_tokens = vec![
@@ -73,6 +77,9 @@ fn two_char_token() {
         TokenType::NotEq,
         TokenType::LtEq,
         TokenType::GtEq,
+        TokenType::Arrow,
+        TokenType::Assign,
+        TokenType::Gt,
         TokenType::Eof,
     ];
 
@@ -156,6 +163,8 @@ fn numerics() {
         0b1111
         0xFf
         0o10
+        123456789012345678901234567890
+        0xffffffffffffffffff
     ";
     let tokens = gen_tokens(input);
 
@@ -167,6 +176,10 @@ fn numerics() {
         TokenType::Numeric(Numeric::Int(15)),
         TokenType::Numeric(Numeric::Int(255)),
         TokenType::Numeric(Numeric::Int(8)),
+        TokenType::Numeric(Numeric::BigInt(
+            "123456789012345678901234567890".parse().unwrap(),
+        )),
+        TokenType::Numeric(Numeric::BigInt("4722366482869645213695".parse().unwrap())),
         TokenType::Eof,
     ];
 
@@ -277,3 +290,158 @@ fn code() {
 
     cmp_tokens_types(test_tokens, tokens)
 }
+
+#[test]
+fn logical_operators() {
+    let input = "y o no && || & |";
+    let tokens = gen_tokens(input);
+
+    // `y` y `o` son identificadores, el parser los trata como operadores
+    // despues de un operando
+    let test_tokens = vec![
+        TokenType::Ident("y".to_owned()),
+        TokenType::Ident("o".to_owned()),
+        TokenType::Not,
+        TokenType::And,
+        TokenType::Or,
+        TokenType::Illegal('&'),
+        TokenType::Illegal('|'),
+        TokenType::Eof,
+    ];
+
+    cmp_tokens_types(test_tokens, tokens)
+}
+
+#[test]
+fn string_escapes() {
+    let input = r#""a\nb" "\t\"\\\'" "\u{48}\u{f1}\u{1F600}" "año""#;
+    let tokens = gen_tokens(input);
+
+    let test_tokens = vec![
+        TokenType::String("a\nb".to_owned()),
+        TokenType::String("\t\"\\'".to_owned()),
+        TokenType::String("Hñ😀".to_owned()),
+        TokenType::String("año".to_owned()),
+        TokenType::Eof,
+    ];
+
+    cmp_tokens_types(test_tokens, tokens)
+}
+
+#[test]
+fn string_bad_escapes() {
+    let cases = [
+        (r#""\q""#, "Secuencia de escape invalida `\\q`"),
+        (
+            r#""\u48""#,
+            "La secuencia de escape `\\u` debe tener la forma `\\u{XXXX}`",
+        ),
+        (
+            r#""\u{48""#,
+            "Falta el simbolo `}` en la secuencia `\\u{...}`",
+        ),
+        (
+            r#""\u{}""#,
+            "La secuencia `\\u{}` debe tener entre 1 y 6 digitos hexadecimales",
+        ),
+        (
+            r#""\u{D800}""#,
+            "La secuencia `\\u{D800}` no es un caracter unicode valido",
+        ),
+        (
+            "\"hola\nmundo\"",
+            "Las cadenas de una linea no pueden tener saltos de linea, usa `\\n` o `\"\"\"`",
+        ),
+        ("\"hola", "Falta el simbolo `\"` para delimitar la cadena"),
+    ];
+
+    for (input, msg) in cases {
+        let mut lexer = Lexer::new(input.chars().collect());
+        assert_eq!(
+            lexer.next_token().r#type,
+            TokenType::IllegalMsg(msg.to_owned()),
+            "Entrada: {}",
+            input
+        );
+    }
+}
+
+#[test]
+fn raw_and_multiline_strings() {
+    let input = "r\"C:\\nuevo\\{x}\" \"\"\"linea 1\n\"linea\" 2\"\"\" r\"\"\"a\\n\nb\"\"\"";
+    let tokens = gen_tokens(input);
+
+    let test_tokens = vec![
+        TokenType::String("C:\\nuevo\\{x}".to_owned()),
+        TokenType::String("linea 1\n\"linea\" 2".to_owned()),
+        TokenType::String("a\\n\nb".to_owned()),
+        TokenType::Eof,
+    ];
+
+    cmp_tokens_types(test_tokens, tokens)
+}
+
+#[test]
+fn multiline_string_positions() {
+    let input = "\"\"\"uno\ndos\ntres\"\"\" var";
+    let tokens = gen_tokens(input);
+
+    assert_eq!(
+        tokens[0].r#type,
+        TokenType::String("uno\ndos\ntres".to_owned())
+    );
+    assert_eq!((tokens[0].line, tokens[0].col), (3, 7));
+    assert_eq!(tokens[1].r#type, TokenType::Var);
+    assert_eq!((tokens[1].line, tokens[1].col), (3, 11));
+
+    let mut lexer = Lexer::new("\"\"\"uno\n\\q\"\"\"".chars().collect());
+    let token = lexer.next_token();
+    assert_eq!(
+        token.r#type,
+        TokenType::IllegalMsg("Secuencia de escape invalida `\\q`".to_owned())
+    );
+    assert_eq!((token.line, token.col), (2, 2));
+}
+
+#[test]
+fn interpolated_strings() {
+    let input = r#"f"hola {nombre}, {d["x}"] + 1} \{\}" f"{a}""#;
+    let tokens = gen_tokens(input);
+
+    let test_tokens = vec![
+        TokenType::InterpolatedString(vec![
+            StringPart::Literal("hola ".to_owned()),
+            StringPart::Expr {
+                source: "nombre".to_owned(),
+                line: 1,
+                col: 8,
+            },
+            StringPart::Literal(", ".to_owned()),
+            StringPart::Expr {
+                source: "d[\"x}\"] + 1".to_owned(),
+                line: 1,
+                col: 18,
+            },
+            StringPart::Literal(" {}".to_owned()),
+        ]),
+        TokenType::InterpolatedString(vec![StringPart::Expr {
+            source: "a".to_owned(),
+            line: 1,
+            col: 40,
+        }]),
+        TokenType::Eof,
+    ];
+
+    cmp_tokens_types(test_tokens, tokens);
+
+    let mut lexer = Lexer::new("f\"hola {nombre".chars().collect());
+    assert_eq!(
+        lexer.next_token().r#type,
+        TokenType::IllegalMsg("Falta el simbolo `}` para cerrar la interpolacion".to_owned())
+    );
+    let mut lexer = Lexer::new("f\"hola }\"".chars().collect());
+    assert_eq!(
+        lexer.next_token().r#type,
+        TokenType::IllegalMsg("Usa `\\}` para escribir `}` en una cadena interpolada".to_owned())
+    );
+}
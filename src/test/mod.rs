@@ -1,3 +1,5 @@
 mod evaluator;
 mod lexer;
 mod parser;
+mod repl;
+mod resolver;
@@ -0,0 +1,52 @@
+use crate::repl::{is_incomplete, Session};
+
+#[test]
+fn incomplete_input() {
+    assert!(is_incomplete("fn f(a) {"));
+    assert!(is_incomplete("fn f(a) {\n  si a {\n"));
+    assert!(is_incomplete("var l = [1, 2"));
+    assert!(is_incomplete("imprimir("));
+    assert!(is_incomplete("var s = \"\"\"hola\nmundo"));
+    assert!(is_incomplete("var s = \"hola"));
+
+    assert!(!is_incomplete("fn f(a) {\n  retornar a;\n}"));
+    assert!(!is_incomplete("var l = [1, 2];"));
+    assert!(!is_incomplete("var s = \"{\";"));
+    assert!(!is_incomplete("# {"));
+    assert!(!is_incomplete("}"));
+    assert!(!is_incomplete("var s = \"linea\nsin cerrar\""));
+}
+
+#[test]
+fn session_keeps_state() {
+    let mut session = Session::new(Vec::new());
+    assert_eq!(session.eval("var x = 5;"), Ok(None));
+    assert_eq!(session.eval("x + 1"), Ok(Some("6".to_owned())));
+    assert_eq!(
+        session.eval("fn doble(a) {\n  retornar a * 2;\n}"),
+        Ok(None)
+    );
+    assert_eq!(session.eval("doble(x)"), Ok(Some("10".to_owned())));
+    assert_eq!(session.eval("imprimir(\"\")"), Ok(None));
+
+    // Los errores no pierden el estado de la sesion
+    assert!(session.eval("var y = ;").is_err());
+    assert!(session.eval("sin_definir").is_err());
+    assert!(session
+        .eval("fn f() { retornar sin_definir; }\nf()")
+        .is_err());
+    assert_eq!(session.eval("x"), Ok(Some("5".to_owned())));
+
+    assert_eq!(
+        session.vars(),
+        vec![
+            "doble: funcion = fn doble(a) {...}".to_owned(),
+            "f: funcion = fn f() {...}".to_owned(),
+            "x: numerico entero = 5".to_owned(),
+        ]
+    );
+
+    session.reset();
+    assert!(session.vars().is_empty());
+    assert!(session.eval("x").is_err());
+}
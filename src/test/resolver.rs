@@ -0,0 +1,150 @@
+use std::{
+    cell::RefCell,
+    hint::black_box,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    eval::{
+        environment::Environment,
+        objects::{Object, ResultObj},
+        resolver::resolve,
+    },
+    lexer::Lexer,
+    parser::{
+        expression::ExprType,
+        statement::{BlockStatement, Statement},
+        Parser,
+    },
+    types::Numeric,
+};
+
+fn resolve_input(input: &str) -> Result<BlockStatement, String> {
+    let mut parser = Parser::new(Lexer::new(input.chars().collect()));
+    let mut program = parser.parse();
+    if let Some(err) = parser.error {
+        panic!("Error del parser: {}", err);
+    }
+    let env = Rc::new(RefCell::new(Environment::new(None)));
+    resolve(&mut program, &env).map_err(|err| err.to_string())?;
+    Ok(program)
+}
+
+#[test]
+fn locals_get_depth_and_slot() {
+    let program =
+        resolve_input("var g = 1;\nfn f(a, b) {\n    si a {\n        b;\n    }\n    g;\n}")
+            .unwrap();
+    let Statement::Fn { body, .. } = &program[1] else {
+        panic!("Se esperaba una funcion");
+    };
+
+    // `b` esta un environment arriba del bloque del `si`, en el slot 1
+    let Statement::Expression(expr) = &body[0] else {
+        panic!("Se esperaba una expresion");
+    };
+    let ExprType::If { consequence, .. } = &expr.r#type else {
+        panic!("Se esperaba un si");
+    };
+    let Statement::Expression(local) = &consequence[0] else {
+        panic!("Se esperaba una expresion");
+    };
+    assert!(matches!(
+        local.r#type,
+        ExprType::Local {
+            depth: 1,
+            slot: 1,
+            ..
+        }
+    ));
+
+    // Las globales toman su posicion en el environment raiz
+    let Statement::Expression(global) = &body[1] else {
+        panic!("Se esperaba una expresion");
+    };
+    assert!(matches!(global.r#type, ExprType::Global { slot: 0, .. }));
+}
+
+#[test]
+fn globals_get_root_slot() {
+    let program =
+        resolve_input("fn f() {\n    g;\n    imprimir;\n}\nvar h = 1;\nvar g = 2;").unwrap();
+    let Statement::Fn { body, .. } = &program[0] else {
+        panic!("Se esperaba una funcion");
+    };
+
+    // `g` se declara despues de la funcion pero ya tiene posicion
+    let Statement::Expression(global) = &body[0] else {
+        panic!("Se esperaba una expresion");
+    };
+    assert!(matches!(global.r#type, ExprType::Global { slot: 2, .. }));
+
+    // Las funciones internas se siguen buscando por nombre
+    let Statement::Expression(buildin) = &body[1] else {
+        panic!("Se esperaba una expresion");
+    };
+    assert!(matches!(buildin.r#type, ExprType::Identifier(_)));
+}
+
+#[test]
+fn resolve_errors() {
+    assert_eq!(
+        resolve_input("fn f() {\n    imprimir(x);\n    var x = 1;\n}").unwrap_err(),
+        "Error de sintaxis: El identificador `x` se usa antes de ser declarado. Linea 2, columna 14."
+    );
+    assert_eq!(
+        resolve_input("fn f() {\n    var x = 1;\n    var x = 2;\n}").unwrap_err(),
+        "Error de sintaxis: El identificador `x` ya habia sido declarado. Linea 3, columna 13."
+    );
+    assert_eq!(
+        resolve_input("var x = 1;\nvar x = 2;").unwrap_err(),
+        "Error de sintaxis: El identificador `x` ya habia sido declarado. Linea 2, columna 9."
+    );
+    assert_eq!(
+        resolve_input("fn f(a, a) {}").unwrap_err(),
+        "Error de sintaxis: El parametro `a` esta repetido. Linea 1, columna 9."
+    );
+
+    // Una funcion puede usar variables declaradas despues de ella
+    assert!(resolve_input("fn f() {\n    fn g() { retornar x; }\n    var x = 1;\n}").is_ok());
+    // En bloques distintos se puede repetir el nombre
+    assert!(resolve_input("si verdad { var x = 1; } sino { var x = 2; }").is_ok());
+}
+
+// Mejor tiempo de varias repeticiones, para que la prueba no dependa de la carga
+fn best_time(f: impl Fn() -> Option<ResultObj>) -> Duration {
+    (0..5)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..100_000 {
+                black_box(f());
+            }
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+#[test]
+fn global_slots_are_faster_than_names() {
+    let root = Rc::new(RefCell::new(Environment::new(None)));
+    for i in 0..64 {
+        root.borrow_mut().set(
+            format!("variable_{}", i),
+            ResultObj::Copy(Object::Numeric(Numeric::Int(i))),
+        );
+    }
+    // Como el cuerpo de un bucle dentro de un `si`
+    let mut env = root.clone();
+    for _ in 0..3 {
+        env = Rc::new(RefCell::new(Environment::new(Some(env))));
+    }
+    let name = "variable_63".to_owned();
+    let slot = root.borrow().global_slot(&name).unwrap();
+
+    let by_name = best_time(|| env.borrow().get(&name));
+    let by_slot = best_time(|| env.borrow().get_global(slot));
+    println!("por nombre: {:?}, por posicion: {:?}", by_name, by_slot);
+    assert!(by_slot < by_name);
+}
@@ -12,6 +12,7 @@ pub enum TokenType {
     Ident(String),
     Numeric(Numeric),
     String(String),
+    InterpolatedString(Vec<StringPart>),
 
     // Operators
     Assign,
@@ -33,6 +34,9 @@ pub enum TokenType {
     LtEq,
     GtEq,
     Dot,
+    And,
+    Or,
+    Arrow,
 
     // Delimiters
     Comma,
@@ -61,6 +65,20 @@ pub enum TokenType {
     Range,
     Break,
     Continue,
+    Not,
+    Import,
+    As,
+    From,
+    Try,
+    Catch,
+    Finally,
+    Throw,
+    Class,
+    This,
+    Inherits,
+    Super,
+    Match,
+    Case,
 }
 
 impl Display for TokenType {
@@ -96,6 +114,16 @@ impl Display for TokenType {
             TokenType::If => write!(f, "si"),
             TokenType::Else => write!(f, "sino"),
             TokenType::String(string) => write!(f, "\"{}\"", string),
+            TokenType::InterpolatedString(parts) => {
+                write!(f, "f\"")?;
+                for part in parts {
+                    match part {
+                        StringPart::Literal(string) => write!(f, "{}", string)?,
+                        StringPart::Expr { source, .. } => write!(f, "{{{}}}", source)?,
+                    }
+                }
+                write!(f, "\"")
+            }
             TokenType::Dot => write!(f, "."),
             TokenType::LBracket => write!(f, "["),
             TokenType::RBracket => write!(f, "]"),
@@ -116,10 +144,39 @@ impl Display for TokenType {
             TokenType::AsteriskAssing => write!(f, "*="),
             TokenType::SlashAssing => write!(f, "/="),
             TokenType::PercentAssing => write!(f, "%="),
+            TokenType::And => write!(f, "y"),
+            TokenType::Or => write!(f, "o"),
+            TokenType::Arrow => write!(f, "=>"),
+            TokenType::Not => write!(f, "no"),
+            TokenType::Import => write!(f, "importar"),
+            TokenType::As => write!(f, "como"),
+            TokenType::From => write!(f, "desde"),
+            TokenType::Try => write!(f, "intentar"),
+            TokenType::Catch => write!(f, "capturar"),
+            TokenType::Finally => write!(f, "finalmente"),
+            TokenType::Throw => write!(f, "lanzar"),
+            TokenType::Class => write!(f, "clase"),
+            TokenType::This => write!(f, "este"),
+            TokenType::Inherits => write!(f, "hereda"),
+            TokenType::Super => write!(f, "super"),
+            TokenType::Match => write!(f, "segun"),
+            TokenType::Case => write!(f, "caso"),
         }
     }
 }
 
+// Partes de una cadena interpolada. Las expresiones guardan su codigo y
+// la posicion del `{` para que los errores apunten al lugar correcto
+#[derive(PartialEq, Clone, Debug)]
+pub enum StringPart {
+    Literal(String),
+    Expr {
+        source: String,
+        line: usize,
+        col: usize,
+    },
+}
+
 pub fn keywords_to_tokens(v: &str) -> TokenType {
     match v {
         "var" => TokenType::Var,
@@ -136,6 +193,20 @@ pub fn keywords_to_tokens(v: &str) -> TokenType {
         "mientras" => TokenType::While,
         "continuar" => TokenType::Continue,
         "romper" => TokenType::Break,
+        "no" => TokenType::Not,
+        "importar" => TokenType::Import,
+        "como" => TokenType::As,
+        "desde" => TokenType::From,
+        "intentar" => TokenType::Try,
+        "capturar" => TokenType::Catch,
+        "finalmente" => TokenType::Finally,
+        "lanzar" => TokenType::Throw,
+        "clase" => TokenType::Class,
+        "este" => TokenType::This,
+        "hereda" => TokenType::Inherits,
+        "super" => TokenType::Super,
+        "segun" => TokenType::Match,
+        "caso" => TokenType::Case,
         _ => TokenType::Ident(v.to_owned()),
     }
 }
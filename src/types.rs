@@ -1,96 +1,213 @@
 use std::{
+    cmp::Ordering,
     fmt::Display,
-    ops::{Add, Div, Mul, Neg, Rem, Sub},
+    hash::{Hash, Hasher},
+    ops::{Add, Mul, Neg, Sub},
 };
 
-#[derive(Clone, Debug, PartialEq, PartialOrd)]
+use num_bigint::BigInt;
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+#[derive(Clone, Debug)]
 pub enum Numeric {
-    Int(i64), // dependiendo del build, tendras: 32 o 64 bits de numero entero
+    Int(i64),
+    // Solo para enteros que no caben en `Int`, las operaciones regresan a
+    // `Int` en cuanto el resultado cabe
+    BigInt(BigInt),
     Float(f64),
 }
 
 impl Numeric {
     pub fn get_type(&self) -> &str {
         match self {
-            Numeric::Int(_) => "entero",
+            Numeric::Int(_) | Numeric::BigInt(_) => "entero",
             Numeric::Float(_) => "flotante",
         }
     }
-}
 
-impl Neg for Numeric {
-    type Output = Self;
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Numeric::Int(int) => *int as f64,
+            // Los enteros fuera del rango de f64 quedan como infinito con su signo
+            Numeric::BigInt(int) => int.to_f64().unwrap_or(if int.is_negative() {
+                f64::NEG_INFINITY
+            } else {
+                f64::INFINITY
+            }),
+            Numeric::Float(float) => *float,
+        }
+    }
 
-    fn neg(self) -> Self::Output {
+    // Lee un entero sin signo en la base dada, de cualquier tamaño
+    pub fn parse_int(digits: &str, radix: u32) -> Option<Numeric> {
+        match i64::from_str_radix(digits, radix) {
+            Ok(int) => Some(Numeric::Int(int)),
+            Err(_) => BigInt::parse_bytes(digits.as_bytes(), radix).map(Numeric::from),
+        }
+    }
+
+    // Parte entera de un flotante, None si es infinito o NaN
+    pub fn from_f64_int(float: f64) -> Option<Numeric> {
+        BigInt::from_f64(float).map(Numeric::from)
+    }
+
+    // Division entera truncada, None si el divisor es el entero 0
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.is_int_zero() {
+            return None;
+        }
+        Some(self.arithmetic(rhs, i64::checked_div, |a, b| a / b, |a, b| a / b))
+    }
+
+    // Residuo con el signo del dividendo, None si el divisor es el entero 0
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        if rhs.is_int_zero() {
+            return None;
+        }
+        Some(self.arithmetic(rhs, i64::checked_rem, |a, b| a % b, |a, b| a % b))
+    }
+
+    // Si es el entero 0, el flotante 0.0 no cuenta
+    pub fn is_int_zero(&self) -> bool {
         match self {
-            Numeric::Int(int) => Numeric::Int(-int),
-            Numeric::Float(float) => Numeric::Float(-float),
+            Numeric::Int(int) => *int == 0,
+            Numeric::BigInt(int) => int.is_zero(),
+            Numeric::Float(_) => false,
         }
     }
-}
 
-impl Add for Numeric {
-    type Output = Self;
+    // Compara un entero con un flotante sin perder precision, None si el
+    // flotante es NaN
+    fn cmp_float(&self, float: f64) -> Option<Ordering> {
+        const EXACT_F64: u64 = 1 << f64::MANTISSA_DIGITS;
+        match self {
+            Numeric::Int(int) if int.unsigned_abs() <= EXACT_F64 => {
+                (*int as f64).partial_cmp(&float)
+            }
+            _ if float.is_nan() => None,
+            _ if float.is_infinite() => Some(if float > 0.0 {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }),
+            int => {
+                // Se compara con la parte entera y, si es igual, con la decimal
+                let trunc = float.trunc();
+                let ord = int.clone().into_bigint().cmp(&BigInt::from_f64(trunc)?);
+                Some(ord.then(0.0.partial_cmp(&(float - trunc))?))
+            }
+        }
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
+    fn into_bigint(self) -> BigInt {
+        match self {
+            Numeric::Int(int) => BigInt::from(int),
+            Numeric::BigInt(int) => int,
+            Numeric::Float(_) => unreachable!("Los flotantes no se convierten a enteros"),
+        }
+    }
+
+    // Opera con i64 y solo si se desborda repite la operacion con BigInt
+    fn arithmetic(
+        self,
+        rhs: Self,
+        int_op: fn(i64, i64) -> Option<i64>,
+        big_op: fn(BigInt, BigInt) -> BigInt,
+        float_op: fn(f64, f64) -> f64,
+    ) -> Self {
         match (self, rhs) {
-            (Numeric::Int(a), Numeric::Int(b)) => Numeric::Int(a + b),
-            (Numeric::Int(a), Numeric::Float(b)) => Numeric::Float(a as f64 + b),
-            (Numeric::Float(a), Numeric::Int(b)) => Numeric::Float(a + b as f64),
-            (Numeric::Float(a), Numeric::Float(b)) => Numeric::Float(a + b),
+            (Numeric::Int(a), Numeric::Int(b)) => match int_op(a, b) {
+                Some(int) => Numeric::Int(int),
+                None => Numeric::from(big_op(BigInt::from(a), BigInt::from(b))),
+            },
+            (a @ Numeric::Float(_), b) | (a, b @ Numeric::Float(_)) => {
+                Numeric::Float(float_op(a.to_f64(), b.to_f64()))
+            }
+            (a, b) => Numeric::from(big_op(a.into_bigint(), b.into_bigint())),
         }
     }
 }
 
-impl Sub for Numeric {
-    type Output = Self;
+impl From<BigInt> for Numeric {
+    fn from(int: BigInt) -> Self {
+        match int.to_i64() {
+            Some(int) => Numeric::Int(int),
+            None => Numeric::BigInt(int),
+        }
+    }
+}
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Numeric::Int(a), Numeric::Int(b)) => Numeric::Int(a - b),
-            (Numeric::Int(a), Numeric::Float(b)) => Numeric::Float(a as f64 - b),
-            (Numeric::Float(a), Numeric::Int(b)) => Numeric::Float(a - b as f64),
-            (Numeric::Float(a), Numeric::Float(b)) => Numeric::Float(a - b),
+// Los numeros se comparan por su valor sin importar su representacion, asi
+// `1 == 1.0` y el orden concuerda con la igualdad
+impl PartialOrd for Numeric {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Numeric::Int(a), Numeric::Int(b)) => Some(a.cmp(b)),
+            (Numeric::Float(a), Numeric::Float(b)) => a.partial_cmp(b),
+            (int, Numeric::Float(float)) => int.cmp_float(*float),
+            (Numeric::Float(float), int) => int.cmp_float(*float).map(Ordering::reverse),
+            (a, b) => Some(a.clone().into_bigint().cmp(&b.clone().into_bigint())),
         }
     }
 }
 
-impl Mul for Numeric {
-    type Output = Self;
+impl PartialEq for Numeric {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Numeric::Int(a), Numeric::Int(b)) => Numeric::Int(a * b),
-            (Numeric::Int(a), Numeric::Float(b)) => Numeric::Float(a as f64 * b),
-            (Numeric::Float(a), Numeric::Int(b)) => Numeric::Float(a * b as f64),
-            (Numeric::Float(a), Numeric::Float(b)) => Numeric::Float(a * b),
+// Los numeros iguales tienen el mismo hash: un flotante sin parte decimal
+// usa el hash del entero que representa
+impl Hash for Numeric {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            Numeric::Int(int) => int.hash(state),
+            Numeric::BigInt(int) => int.hash(state),
+            Numeric::Float(float) => match Numeric::from_f64_int(*float) {
+                Some(int) if float.fract() == 0.0 => int.hash(state),
+                _ => float.to_bits().hash(state),
+            },
         }
     }
 }
 
-impl Div for Numeric {
+impl Neg for Numeric {
     type Output = Self;
 
-    fn div(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Numeric::Int(a), Numeric::Int(b)) => Numeric::Int(a / b),
-            (Numeric::Int(a), Numeric::Float(b)) => Numeric::Float(a as f64 / b),
-            (Numeric::Float(a), Numeric::Int(b)) => Numeric::Float(a / b as f64),
-            (Numeric::Float(a), Numeric::Float(b)) => Numeric::Float(a / b),
+    fn neg(self) -> Self::Output {
+        match self {
+            Numeric::Int(int) => match int.checked_neg() {
+                Some(int) => Numeric::Int(int),
+                None => Numeric::from(-BigInt::from(int)),
+            },
+            Numeric::BigInt(int) => Numeric::from(-int),
+            Numeric::Float(float) => Numeric::Float(-float),
         }
     }
 }
 
-impl Rem for Numeric {
+impl Add for Numeric {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.arithmetic(rhs, i64::checked_add, |a, b| a + b, |a, b| a + b)
+    }
+}
+
+impl Sub for Numeric {
     type Output = Self;
 
-    fn rem(self, rhs: Self) -> Self::Output {
-        match (self, rhs) {
-            (Numeric::Int(a), Numeric::Int(b)) => Numeric::Int(a % b),
-            (Numeric::Int(a), Numeric::Float(b)) => Numeric::Float(a as f64 % b),
-            (Numeric::Float(a), Numeric::Int(b)) => Numeric::Float(a % b as f64),
-            (Numeric::Float(a), Numeric::Float(b)) => Numeric::Float(a % b),
-        }
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.arithmetic(rhs, i64::checked_sub, |a, b| a - b, |a, b| a - b)
+    }
+}
+
+impl Mul for Numeric {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        self.arithmetic(rhs, i64::checked_mul, |a, b| a * b, |a, b| a * b)
     }
 }
 
@@ -98,6 +215,7 @@ impl Display for Numeric {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Numeric::Int(int) => write!(f, "{}", int),
+            Numeric::BigInt(int) => write!(f, "{}", int),
             Numeric::Float(float) => write!(f, "{}", float),
         }
     }
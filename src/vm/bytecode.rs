@@ -0,0 +1,242 @@
+use std::rc::Rc;
+
+use crate::{
+    parser::expression::{Param, Pattern},
+    token::TokenType,
+    types::Numeric,
+};
+
+// Operadores binarios, se traducen al token que espera el evaluador
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    LtEq,
+    GtEq,
+}
+
+impl BinaryOp {
+    pub fn from_token(token: &TokenType) -> Option<Self> {
+        match token {
+            TokenType::Plus => Some(BinaryOp::Add),
+            TokenType::Minus => Some(BinaryOp::Sub),
+            TokenType::Asterisk => Some(BinaryOp::Mul),
+            TokenType::Slash => Some(BinaryOp::Div),
+            TokenType::Percent => Some(BinaryOp::Mod),
+            TokenType::Eq => Some(BinaryOp::Eq),
+            TokenType::NotEq => Some(BinaryOp::NotEq),
+            TokenType::Lt => Some(BinaryOp::Lt),
+            TokenType::Gt => Some(BinaryOp::Gt),
+            TokenType::LtEq => Some(BinaryOp::LtEq),
+            TokenType::GtEq => Some(BinaryOp::GtEq),
+            _ => None,
+        }
+    }
+
+    pub fn token(&self) -> TokenType {
+        match self {
+            BinaryOp::Add => TokenType::Plus,
+            BinaryOp::Sub => TokenType::Minus,
+            BinaryOp::Mul => TokenType::Asterisk,
+            BinaryOp::Div => TokenType::Slash,
+            BinaryOp::Mod => TokenType::Percent,
+            BinaryOp::Eq => TokenType::Eq,
+            BinaryOp::NotEq => TokenType::NotEq,
+            BinaryOp::Lt => TokenType::Lt,
+            BinaryOp::Gt => TokenType::Gt,
+            BinaryOp::LtEq => TokenType::LtEq,
+            BinaryOp::GtEq => TokenType::GtEq,
+        }
+    }
+}
+
+// Donde esta una variable, segun lo que calculo el resolver. Los `name` son
+// para los errores
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Var {
+    // Funciones internas y nombres que el resolver no encontro
+    Name(usize),
+    // A `depth` environments del actual, en la posicion `slot`
+    Local {
+        depth: usize,
+        slot: usize,
+        name: usize,
+    },
+    // Posicion en el environment raiz del programa o modulo
+    Global {
+        slot: usize,
+        name: usize,
+    },
+}
+
+// Instrucciones de la maquina virtual. Los `usize` de nombres, constantes,
+// posiciones y llamadas son indices en las tablas del `Chunk`, y los de
+// saltos son la posicion de la instruccion destino
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpCode {
+    Constant(usize),
+    Null,
+    Void,
+    True,
+    False,
+    Pop,
+    PopN(usize),
+
+    // Variables
+    GetVar(Var),
+    SetVar(Var),
+    // Declaran por nombre en el environment actual. Fuera del environment
+    // raiz la variable toma el siguiente slot, en el mismo orden que el resolver
+    DefineVar(usize),
+    Bind(usize),
+    AssertDeclared(Var),
+    AssertUndeclared(usize),
+    AssertNoLoopVar(usize),
+
+    // Operadores
+    Infix(BinaryOp),
+    Negate,
+    Not,
+    Logical {
+        or: bool,
+        end: usize,
+    },
+    LogicalEnd {
+        or: bool,
+    },
+
+    // Colecciones y cadenas
+    List(usize),
+    Dict(usize),
+    // Indice del par dentro del diccionario, para buscar llaves repetidas
+    CheckKey(usize),
+    ToStr,
+    Concat(usize),
+    GetIndex(usize),
+    SetIndex(usize),
+    GetMember(usize),
+    // El nombre del miembro y la posicion del valor
+    SetMember {
+        name: usize,
+        span: usize,
+    },
+    // Toma la instancia y la clase padre del stack
+    GetSuper(usize),
+
+    // Funciones
+    Closure(usize),
+    // Si la llamada no paso el parametro opcional en `slot`, sigue con su
+    // valor por defecto, si no salta a `skip`
+    DefaultParam {
+        slot: usize,
+        skip: usize,
+    },
+    // Asigna el valor por defecto al parametro en `slot`
+    DefineParam(usize),
+    Call(usize),
+    // Igual que `Call`, pero una funcion compilada reemplaza al frame actual
+    TailCall(usize),
+    CallMember {
+        name: usize,
+        site: usize,
+    },
+    Return,
+    // Toma los metodos del stack, cada uno es un `Closure` con su nombre.
+    // Si hereda, antes de ellos esta la clase padre y `parent` es su posicion
+    Class {
+        name: usize,
+        methods: usize,
+        parent: Option<usize>,
+    },
+
+    // Control de flujo
+    Jump(usize),
+    JumpIfFalse(usize),
+    Truthy(usize),
+    // Error si la condicion de `para` no es un valor logico
+    AssertBool,
+    PushScope,
+    PopScope,
+
+    // `segun`: el valor se queda en el stack mientras se prueban los casos.
+    // Si coincide con los patrones del caso abre un scope con sus variables,
+    // si no salta a `next`
+    Match {
+        pattern: usize,
+        next: usize,
+    },
+    // Quita el valor de `segun` y deja el resultado del caso
+    MatchEnd,
+
+    // Bucles `para`
+    IterInit,
+    RangeInit {
+        argc: usize,
+        span: usize,
+    },
+    IterNext {
+        exit: usize,
+        count: usize,
+    },
+    IterEnd,
+
+    // Errores
+    TryBegin(usize),
+    TryEnd,
+    // Salta si el error capturado es de `sis.salir`, no lo atrapa `capturar`
+    JumpIfExit(usize),
+    Throw,
+    Rethrow,
+    Raise(usize),
+
+    // Modulos
+    LoadModule(usize),
+    ImportMember(usize),
+}
+
+pub enum Constant {
+    Numeric(Numeric),
+    String(String),
+    Fn(Rc<FnProto>),
+}
+
+// Posiciones de una llamada: la funcion, el objeto (en llamadas a miembros)
+// y cada argumento, para reportar errores igual que el evaluador
+#[derive(Clone)]
+pub struct CallSite {
+    pub line: usize,
+    pub col: usize,
+    pub target: (usize, usize),
+    pub args: Vec<(usize, usize)>,
+    // Nombre de cada argumento, si se paso con nombre
+    pub names: Vec<Option<String>>,
+}
+
+#[derive(Default)]
+pub struct Chunk {
+    pub code: Vec<OpCode>,
+    // Linea y columna de cada instruccion
+    pub positions: Vec<(usize, usize)>,
+    pub constants: Vec<Constant>,
+    pub names: Vec<String>,
+    // Posiciones extra de algunas instrucciones, ej. el indice de `l[i]`
+    pub spans: Vec<(usize, usize)>,
+    pub sites: Vec<CallSite>,
+    pub messages: Vec<String>,
+    // Patrones de cada caso de `segun`
+    pub patterns: Vec<Vec<Pattern>>,
+}
+
+// Funcion compilada, se convierte en `Object::CompiledFn` al evaluarse
+pub struct FnProto {
+    pub name: Option<String>,
+    pub params: Vec<Param>,
+    pub chunk: Rc<Chunk>,
+}
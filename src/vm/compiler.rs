@@ -0,0 +1,1004 @@
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    eval::evaluator::{import_name, split_named_arg},
+    eval::objects::{Object, ResultObj},
+    parser::{
+        expression::{ExprType, Expression, FnParams, Param},
+        statement::{BlockStatement, Statement},
+    },
+    token::TokenType,
+};
+
+use super::bytecode::{BinaryOp, CallSite, Chunk, Constant, FnProto, OpCode, Var};
+
+// Bloques abiertos mientras se compila, sirven para saber que hay que
+// deshacer al salir de ellos con `romper`, `continuar` o `retornar`
+enum Block {
+    Scope,
+    Loop {
+        // Valores en el stack al empezar el bucle
+        depth: usize,
+        iter: bool,
+        continue_target: Option<usize>,
+        breaks: Vec<usize>,
+        continues: Vec<usize>,
+    },
+    Try {
+        finally: Option<BlockStatement>,
+    },
+}
+
+struct Compiler {
+    chunk: Chunk,
+    names: HashMap<String, usize>,
+    // Valores que deja en el stack el codigo compilado hasta ahora
+    depth: usize,
+    blocks: Vec<Block>,
+    in_fn: bool,
+}
+
+// Compila un programa, el valor de su ultima sentencia es el resultado
+pub fn compile_program(program: BlockStatement) -> Chunk {
+    let mut compiler = Compiler::new(false);
+    compiler.compile_block(program);
+    compiler.emit(OpCode::Return, 0, 0);
+    compiler.chunk
+}
+
+impl Compiler {
+    fn new(in_fn: bool) -> Self {
+        Self {
+            chunk: Chunk::default(),
+            names: HashMap::new(),
+            depth: 0,
+            blocks: Vec::new(),
+            in_fn,
+        }
+    }
+
+    fn emit(&mut self, op: OpCode, line: usize, col: usize) -> usize {
+        self.depth = (self.depth as isize + self.stack_effect(&op)) as usize;
+        self.chunk.code.push(op);
+        self.chunk.positions.push((line, col));
+        self.chunk.code.len() - 1
+    }
+
+    // Cuantos valores agrega (o quita) cada instruccion al stack
+    fn stack_effect(&self, op: &OpCode) -> isize {
+        match op {
+            OpCode::Constant(_)
+            | OpCode::Null
+            | OpCode::Void
+            | OpCode::True
+            | OpCode::False
+            | OpCode::GetVar(_)
+            | OpCode::Closure(_)
+            | OpCode::LoadModule(_)
+            | OpCode::ImportMember(_)
+            // No retorna, pero el codigo que sigue espera su valor
+            | OpCode::Raise(_) => 1,
+            OpCode::Pop
+            | OpCode::Bind(_)
+            | OpCode::Infix(_)
+            | OpCode::Logical { .. }
+            | OpCode::JumpIfFalse(_)
+            | OpCode::GetIndex(_)
+            | OpCode::IterInit
+            | OpCode::SetMember { .. }
+            | OpCode::GetSuper(_)
+            | OpCode::MatchEnd
+            | OpCode::Rethrow => -1,
+            OpCode::PopN(n) => -(*n as isize),
+            OpCode::SetIndex(_) => -2,
+            OpCode::List(n) | OpCode::Concat(n) => 1 - *n as isize,
+            OpCode::Dict(n) => 1 - 2 * *n as isize,
+            OpCode::Class {
+                methods, parent, ..
+            } => 1 - *methods as isize - parent.is_some() as isize,
+            OpCode::Call(site) | OpCode::TailCall(site) | OpCode::CallMember { site, .. } => {
+                -(self.chunk.sites[*site].args.len() as isize)
+            }
+            OpCode::RangeInit { argc, .. } => -(*argc as isize),
+            OpCode::IterNext { count, .. } => *count as isize,
+            _ => 0,
+        }
+    }
+
+    fn here(&self) -> usize {
+        self.chunk.code.len()
+    }
+
+    // Apunta el salto de la instruccion `at` a la siguiente instruccion
+    fn patch(&mut self, at: usize) {
+        let target = self.here();
+        self.chunk.code[at] = match self.chunk.code[at] {
+            OpCode::Jump(_) => OpCode::Jump(target),
+            OpCode::JumpIfFalse(_) => OpCode::JumpIfFalse(target),
+            OpCode::Truthy(_) => OpCode::Truthy(target),
+            OpCode::JumpIfExit(_) => OpCode::JumpIfExit(target),
+            OpCode::Logical { or, .. } => OpCode::Logical { or, end: target },
+            OpCode::IterNext { count, .. } => OpCode::IterNext {
+                exit: target,
+                count,
+            },
+            OpCode::TryBegin(_) => OpCode::TryBegin(target),
+            OpCode::Match { pattern, .. } => OpCode::Match {
+                pattern,
+                next: target,
+            },
+            OpCode::DefaultParam { slot, .. } => OpCode::DefaultParam { slot, skip: target },
+            op => unreachable!("La instruccion {:?} no es un salto", op),
+        };
+    }
+
+    fn name(&mut self, name: &str) -> usize {
+        if let Some(idx) = self.names.get(name) {
+            return *idx;
+        }
+        self.chunk.names.push(name.to_owned());
+        let idx = self.chunk.names.len() - 1;
+        self.names.insert(name.to_owned(), idx);
+        idx
+    }
+
+    // Variable de una expresion que ya paso por el resolver
+    fn var(&mut self, expr: &ExprType) -> Option<Var> {
+        let var = match expr {
+            ExprType::Identifier(name) => Var::Name(self.name(name)),
+            ExprType::Local { name, depth, slot } => Var::Local {
+                depth: *depth,
+                slot: *slot,
+                name: self.name(name),
+            },
+            ExprType::Global { name, slot } => Var::Global {
+                slot: *slot,
+                name: self.name(name),
+            },
+            _ => return None,
+        };
+        Some(var)
+    }
+
+    fn constant(&mut self, constant: Constant) -> usize {
+        self.chunk.constants.push(constant);
+        self.chunk.constants.len() - 1
+    }
+
+    fn span(&mut self, line: usize, col: usize) -> usize {
+        self.chunk.spans.push((line, col));
+        self.chunk.spans.len() - 1
+    }
+
+    fn raise(&mut self, msg: String, line: usize, col: usize) {
+        self.chunk.messages.push(msg);
+        let idx = self.chunk.messages.len() - 1;
+        self.emit(OpCode::Raise(idx), line, col);
+    }
+
+    // Deja en el stack el valor de la ultima sentencia del bloque
+    fn compile_block(&mut self, block: BlockStatement) {
+        if block.is_empty() {
+            self.emit(OpCode::Void, 0, 0);
+            return;
+        }
+        let last = block.len() - 1;
+        for (i, stmt) in block.into_iter().enumerate() {
+            self.compile_statement(stmt);
+            if i != last {
+                self.emit(OpCode::Pop, 0, 0);
+            }
+        }
+    }
+
+    // Igual que `compile_block` pero con su propio environment
+    fn compile_scoped_block(&mut self, block: BlockStatement) {
+        if block.is_empty() {
+            self.emit(OpCode::Void, 0, 0);
+            return;
+        }
+        self.emit(OpCode::PushScope, 0, 0);
+        self.blocks.push(Block::Scope);
+        self.compile_block(block);
+        self.blocks.pop();
+        self.emit(OpCode::PopScope, 0, 0);
+    }
+
+    fn compile_finally(&mut self, finally: BlockStatement) {
+        self.compile_scoped_block(finally);
+        self.emit(OpCode::Pop, 0, 0);
+    }
+
+    // Sale de los bloques abiertos hasta `until`, sin incluirlo
+    fn exit_blocks(&mut self, until: usize) {
+        let mut i = self.blocks.len();
+        while i > until {
+            i -= 1;
+            match &self.blocks[i] {
+                Block::Scope => {
+                    self.emit(OpCode::PopScope, 0, 0);
+                }
+                Block::Loop { iter, .. } => {
+                    if *iter {
+                        self.emit(OpCode::IterEnd, 0, 0);
+                    }
+                }
+                Block::Try { finally } => {
+                    let finally = finally.clone();
+                    self.emit(OpCode::TryEnd, 0, 0);
+                    // `finalmente` se compila como si estuviera fuera del `intentar`
+                    if let Some(finally) = finally {
+                        let inner = self.blocks.split_off(i);
+                        self.compile_finally(finally);
+                        self.blocks.extend(inner);
+                    }
+                }
+            }
+        }
+    }
+
+    fn compile_statement(&mut self, stmt: Statement) {
+        match stmt {
+            Statement::Var { name, value } => {
+                let (line, col) = (value.line, value.col);
+                let name = self.name(&name);
+                self.emit(OpCode::AssertUndeclared(name), line, col);
+                self.compile_expression(value);
+                self.emit(OpCode::DefineVar(name), line, col);
+            }
+            Statement::Return(expr, line, col) => {
+                if !self.in_fn {
+                    return self.raise(
+                        "Solo se puede retornar dentro de funciones".into(),
+                        line,
+                        col,
+                    );
+                }
+                let in_try = self
+                    .blocks
+                    .iter()
+                    .any(|block| matches!(block, Block::Try { .. }));
+                match expr.r#type {
+                    // Dentro de `intentar` la llamada debe terminar antes de salir del bloque
+                    ExprType::Call {
+                        function,
+                        arguments,
+                    } if !in_try => {
+                        let (fn_line, fn_col) = (function.line, function.col);
+                        self.compile_expression(*function);
+                        let site = self.compile_args(arguments, fn_line, fn_col, (fn_line, fn_col));
+                        self.emit(OpCode::TailCall(site), fn_line, fn_col);
+                    }
+                    _ => self.compile_expression(expr),
+                }
+                self.exit_blocks(0);
+                self.emit(OpCode::Return, line, col);
+                self.depth += 1;
+            }
+            Statement::Break(line, col) => self.compile_loop_jump(true, line, col),
+            Statement::Continue(line, col) => self.compile_loop_jump(false, line, col),
+            Statement::Expression(expr) => self.compile_expression(expr),
+            Statement::Fn {
+                name,
+                params,
+                body,
+                line,
+                col,
+            } => {
+                let name_idx = self.name(&name);
+                self.emit(OpCode::AssertUndeclared(name_idx), line, col);
+                let proto = self.compile_fn(Some(name), &params, body);
+                self.emit(OpCode::Closure(proto), line, col);
+                self.emit(OpCode::DefineVar(name_idx), line, col);
+            }
+            Statement::Try {
+                body,
+                catch_ident,
+                catch_body,
+                finally_body,
+                line,
+                col,
+            } => self.compile_try(body, catch_ident, catch_body, finally_body, line, col),
+            Statement::Throw(expr, line, col) => {
+                self.compile_expression(expr);
+                self.emit(OpCode::Throw, line, col);
+            }
+            Statement::Import {
+                path,
+                alias,
+                line,
+                col,
+            } => {
+                let name = match import_name(&path, alias, line, col) {
+                    Ok(name) => name,
+                    Err(ResultObj::Copy(Object::Error(err))) => {
+                        return self.raise(err.mensaje, line, col)
+                    }
+                    Err(_) => unreachable!(),
+                };
+                let path = self.name(&path);
+                let name = self.name(&name);
+                self.emit(OpCode::LoadModule(path), line, col);
+                self.emit(OpCode::AssertUndeclared(name), line, col);
+                self.emit(OpCode::Bind(name), line, col);
+                self.emit(OpCode::Void, line, col);
+            }
+            Statement::FromImport {
+                path,
+                names,
+                line,
+                col,
+            } => {
+                let path = self.name(&path);
+                self.emit(OpCode::LoadModule(path), line, col);
+                for name in names {
+                    let name = self.name(&name);
+                    self.emit(OpCode::ImportMember(name), line, col);
+                    self.emit(OpCode::AssertUndeclared(name), line, col);
+                    self.emit(OpCode::Bind(name), line, col);
+                }
+                self.emit(OpCode::Pop, line, col);
+                self.emit(OpCode::Void, line, col);
+            }
+            Statement::Class {
+                name,
+                parent,
+                methods,
+                line,
+                col,
+            } => {
+                let name_idx = self.name(&name);
+                self.emit(OpCode::AssertUndeclared(name_idx), line, col);
+                let parent = parent.map(|parent| {
+                    let span = self.span(parent.line, parent.col);
+                    self.compile_expression(parent);
+                    span
+                });
+                let count = methods.len();
+                for method in methods {
+                    let proto = self.compile_fn(Some(method.name), &method.params, method.body);
+                    self.emit(OpCode::Closure(proto), method.line, method.col);
+                }
+                self.emit(
+                    OpCode::Class {
+                        name: name_idx,
+                        methods: count,
+                        parent,
+                    },
+                    line,
+                    col,
+                );
+                self.emit(OpCode::DefineVar(name_idx), line, col);
+            }
+        }
+    }
+
+    fn compile_loop_jump(&mut self, is_break: bool, line: usize, col: usize) {
+        let loop_idx = self
+            .blocks
+            .iter()
+            .rposition(|block| matches!(block, Block::Loop { .. }));
+        let Some(loop_idx) = loop_idx else {
+            let msg = if is_break {
+                "Solo se puede romper condicionales y bucles"
+            } else {
+                "Solo se puede continuar en bucles"
+            };
+            return self.raise(msg.into(), line, col);
+        };
+
+        let depth = self.depth;
+        self.exit_blocks(loop_idx + 1);
+        let Block::Loop {
+            depth: loop_depth, ..
+        } = self.blocks[loop_idx]
+        else {
+            unreachable!()
+        };
+        if self.depth > loop_depth {
+            self.emit(OpCode::PopN(self.depth - loop_depth), line, col);
+        }
+
+        let jump = self.emit(OpCode::Jump(0), line, col);
+        if let Block::Loop {
+            continue_target,
+            breaks,
+            continues,
+            ..
+        } = &mut self.blocks[loop_idx]
+        {
+            match (is_break, *continue_target) {
+                (true, _) => breaks.push(jump),
+                (false, Some(target)) => self.chunk.code[jump] = OpCode::Jump(target),
+                (false, None) => continues.push(jump),
+            }
+        }
+        self.depth = depth + 1;
+    }
+
+    fn compile_try(
+        &mut self,
+        body: BlockStatement,
+        catch_ident: Option<String>,
+        catch_body: Option<BlockStatement>,
+        finally_body: Option<BlockStatement>,
+        line: usize,
+        col: usize,
+    ) {
+        let depth = self.depth;
+        let handler = self.emit(OpCode::TryBegin(0), line, col);
+        self.blocks.push(Block::Try {
+            finally: finally_body.clone(),
+        });
+        self.compile_scoped_block(body);
+        self.blocks.pop();
+        self.emit(OpCode::TryEnd, line, col);
+        if let Some(ref finally) = finally_body {
+            self.compile_finally(finally.clone());
+        }
+        let mut ends = vec![self.emit(OpCode::Jump(0), line, col)];
+
+        // La maquina salta aqui con el error capturado en el stack
+        self.patch(handler);
+        self.depth = depth + 1;
+        match catch_body {
+            Some(catch_body) => {
+                let exit = self.emit(OpCode::JumpIfExit(0), line, col);
+                // Si hay `finalmente`, tambien debe correr cuando falla `capturar`
+                let catch_handler = finally_body
+                    .as_ref()
+                    .map(|_| self.emit(OpCode::TryBegin(0), line, col));
+                if finally_body.is_some() {
+                    self.blocks.push(Block::Try {
+                        finally: finally_body.clone(),
+                    });
+                }
+
+                self.emit(OpCode::PushScope, line, col);
+                self.blocks.push(Block::Scope);
+                match catch_ident {
+                    Some(ident) => {
+                        let ident = self.name(&ident);
+                        self.emit(OpCode::Bind(ident), line, col);
+                    }
+                    None => {
+                        self.emit(OpCode::Pop, line, col);
+                    }
+                }
+                self.compile_block(catch_body);
+                self.blocks.pop();
+                self.emit(OpCode::PopScope, line, col);
+
+                if let (Some(catch_handler), Some(finally)) = (catch_handler, finally_body) {
+                    self.blocks.pop();
+                    self.emit(OpCode::TryEnd, line, col);
+                    self.compile_finally(finally.clone());
+                    ends.push(self.emit(OpCode::Jump(0), line, col));
+
+                    // `sis.salir` tambien corre `finalmente` antes de seguir subiendo
+                    self.patch(catch_handler);
+                    self.patch(exit);
+                    self.depth = depth + 1;
+                    self.compile_finally(finally);
+                    self.emit(OpCode::Rethrow, line, col);
+                } else {
+                    ends.push(self.emit(OpCode::Jump(0), line, col));
+                    self.patch(exit);
+                    self.depth = depth + 1;
+                    self.emit(OpCode::Rethrow, line, col);
+                }
+            }
+            None => {
+                if let Some(finally) = finally_body {
+                    self.compile_finally(finally);
+                }
+                self.emit(OpCode::Rethrow, line, col);
+            }
+        }
+
+        for end in ends {
+            self.patch(end);
+        }
+        self.depth = depth + 1;
+    }
+
+    fn compile_fn(
+        &mut self,
+        name: Option<String>,
+        params: &[Param],
+        body: BlockStatement,
+    ) -> usize {
+        let mut compiler = Compiler::new(true);
+        // Los valores por defecto se evaluan al inicio, en orden. Cada
+        // parametro tiene el slot de su posicion
+        for (slot, param) in params.iter().enumerate() {
+            if let Some(default) = param.default.clone() {
+                let (line, col) = (default.line, default.col);
+                let skip = compiler.emit(OpCode::DefaultParam { slot, skip: 0 }, line, col);
+                compiler.compile_expression(default);
+                compiler.emit(OpCode::DefineParam(slot), line, col);
+                compiler.emit(OpCode::Pop, line, col);
+                compiler.patch(skip);
+            }
+        }
+        compiler.compile_block(body);
+        compiler.emit(OpCode::Return, 0, 0);
+        self.constant(Constant::Fn(Rc::new(FnProto {
+            name,
+            params: params.to_vec(),
+            chunk: Rc::new(compiler.chunk),
+        })))
+    }
+
+    fn compile_expression(&mut self, expr: Expression) {
+        let (line, col) = (expr.line, expr.col);
+        match expr.r#type {
+            ExprType::Identifier(_) | ExprType::Local { .. } | ExprType::Global { .. } => {
+                let var = self
+                    .var(&expr.r#type)
+                    .expect("La expresion es una variable");
+                self.emit(OpCode::GetVar(var), line, col);
+            }
+            ExprType::NumericLiteral(numeric) => {
+                let idx = self.constant(Constant::Numeric(numeric));
+                self.emit(OpCode::Constant(idx), line, col);
+            }
+            ExprType::BooleanLiteral(true) => {
+                self.emit(OpCode::True, line, col);
+            }
+            ExprType::BooleanLiteral(false) => {
+                self.emit(OpCode::False, line, col);
+            }
+            ExprType::NullLiteral => {
+                self.emit(OpCode::Null, line, col);
+            }
+            ExprType::NamedArgument { .. } => {
+                self.raise(
+                    "Los argumentos con nombre solo se pueden usar al llamar funciones".into(),
+                    line,
+                    col,
+                );
+            }
+            ExprType::StringLiteral(string) => {
+                let idx = self.constant(Constant::String(string));
+                self.emit(OpCode::Constant(idx), line, col);
+            }
+            ExprType::InterpolatedString { parts } => {
+                let count = parts.len();
+                for part in parts {
+                    let (line, col) = (part.line, part.col);
+                    self.compile_expression(part);
+                    self.emit(OpCode::ToStr, line, col);
+                }
+                self.emit(OpCode::Concat(count), line, col);
+            }
+            ExprType::FnLiteral { params, body } => {
+                let proto = self.compile_fn(None, &params, body);
+                self.emit(OpCode::Closure(proto), line, col);
+            }
+            ExprType::ListLiteral { elements } => {
+                let count = elements.len();
+                for element in elements {
+                    self.compile_expression(element);
+                }
+                self.emit(OpCode::List(count), line, col);
+            }
+            ExprType::DictionaryLiteral { pairs } => {
+                let count = pairs.len();
+                for (index, (key, value)) in pairs.into_iter().enumerate() {
+                    let (key_line, key_col) = (key.line, key.col);
+                    self.compile_expression(key);
+                    self.emit(OpCode::CheckKey(index), key_line, key_col);
+                    self.compile_expression(value);
+                }
+                self.emit(OpCode::Dict(count), line, col);
+            }
+            ExprType::Index { left, index } => {
+                let (left_line, left_col) = (left.line, left.col);
+                let span = self.span(index.line, index.col);
+                self.compile_expression(*left);
+                self.compile_expression(*index);
+                self.emit(OpCode::GetIndex(span), left_line, left_col);
+            }
+            ExprType::Prefix { operator, right } => {
+                self.compile_expression(*right);
+                match operator {
+                    TokenType::Plus => {}
+                    TokenType::Minus => {
+                        self.emit(OpCode::Negate, line, col);
+                    }
+                    TokenType::Bang | TokenType::Not => {
+                        self.emit(OpCode::Not, line, col);
+                    }
+                    _ => {
+                        self.emit(OpCode::Pop, line, col);
+                        self.emit(OpCode::Null, line, col);
+                    }
+                }
+            }
+            ExprType::Infix {
+                left,
+                right,
+                operator,
+            } => self.compile_infix(*left, *right, operator),
+            ExprType::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                let (cond_line, cond_col) = (condition.line, condition.col);
+                self.compile_expression(*condition);
+                let exit = self.emit(OpCode::Truthy(0), cond_line, cond_col);
+                let alternative_jump = self.emit(OpCode::JumpIfFalse(0), cond_line, cond_col);
+                self.compile_scoped_block(consequence);
+                let end = self.emit(OpCode::Jump(0), line, col);
+                self.patch(alternative_jump);
+                self.depth -= 1;
+                self.compile_scoped_block(alternative);
+                self.patch(end);
+                self.patch(exit);
+            }
+            ExprType::Match { value, cases } => {
+                self.compile_expression(*value);
+                let mut ends = Vec::new();
+                for case in cases {
+                    self.chunk.patterns.push(case.patterns);
+                    let pattern = self.chunk.patterns.len() - 1;
+                    let next = self.emit(OpCode::Match { pattern, next: 0 }, case.line, case.col);
+                    self.blocks.push(Block::Scope);
+                    let guard = case.guard.map(|guard| {
+                        let (guard_line, guard_col) = (guard.line, guard.col);
+                        self.compile_expression(guard);
+                        let exit = self.emit(OpCode::Truthy(0), guard_line, guard_col);
+                        let fail = self.emit(OpCode::JumpIfFalse(0), guard_line, guard_col);
+                        (exit, fail)
+                    });
+                    self.compile_block(case.body);
+                    if let Some((exit, _)) = guard {
+                        self.patch(exit);
+                    }
+                    self.blocks.pop();
+                    self.emit(OpCode::PopScope, line, col);
+                    ends.push(self.emit(OpCode::Jump(0), line, col));
+                    self.depth -= 1;
+                    // La condicion fue falsa, se prueba el siguiente caso
+                    if let Some((_, fail)) = guard {
+                        self.patch(fail);
+                        self.emit(OpCode::PopScope, line, col);
+                    }
+                    self.patch(next);
+                }
+                // Ningun caso coincide
+                self.emit(OpCode::Null, line, col);
+                for end in ends {
+                    self.patch(end);
+                }
+                self.emit(OpCode::MatchEnd, line, col);
+            }
+            ExprType::While { condition, body } => {
+                let depth = self.depth;
+                let start = self.here();
+                let (cond_line, cond_col) = (condition.line, condition.col);
+                self.compile_expression(*condition);
+                let exit = self.emit(OpCode::Truthy(0), cond_line, cond_col);
+                let end = self.emit(OpCode::JumpIfFalse(0), cond_line, cond_col);
+                let (breaks, _) = self.compile_loop_body(body, depth, Some(start));
+                self.emit(OpCode::Jump(start), line, col);
+                self.patch(end);
+                for jump in breaks {
+                    self.patch(jump);
+                }
+                self.emit(OpCode::Void, line, col);
+                self.patch(exit);
+            }
+            ExprType::Call {
+                function,
+                arguments,
+            } => {
+                let (fn_line, fn_col) = (function.line, function.col);
+                self.compile_expression(*function);
+                let site = self.compile_args(arguments, fn_line, fn_col, (fn_line, fn_col));
+                self.emit(OpCode::Call(site), fn_line, fn_col);
+            }
+            ExprType::Assignment { left, right } => match left.r#type {
+                ExprType::Identifier(_) | ExprType::Local { .. } | ExprType::Global { .. } => {
+                    let var = self
+                        .var(&left.r#type)
+                        .expect("La expresion es una variable");
+                    self.emit(OpCode::AssertDeclared(var), left.line, left.col);
+                    self.compile_expression(*right);
+                    self.emit(OpCode::SetVar(var), left.line, left.col);
+                }
+                ExprType::Index {
+                    left: target,
+                    index,
+                } => {
+                    let (target_line, target_col) = (target.line, target.col);
+                    let span = self.span(index.line, index.col);
+                    self.compile_expression(*right);
+                    self.compile_expression(*target);
+                    self.compile_expression(*index);
+                    self.emit(OpCode::SetIndex(span), target_line, target_col);
+                }
+                ExprType::Infix {
+                    left: target,
+                    operator: TokenType::Dot,
+                    right: member,
+                } if matches!(member.r#type, ExprType::Identifier(_)) => {
+                    let ExprType::Identifier(ident) = member.r#type else {
+                        unreachable!();
+                    };
+                    let (target_line, target_col) = (target.line, target.col);
+                    let name = self.name(&ident);
+                    let span = self.span(right.line, right.col);
+                    self.compile_expression(*target);
+                    self.compile_expression(*right);
+                    self.emit(OpCode::SetMember { name, span }, target_line, target_col);
+                }
+                ref expr => self.raise(
+                    format!("No se puede realizar operaciones de asignacion a {}", expr),
+                    left.line,
+                    left.col,
+                ),
+            },
+            ExprType::ForRange {
+                ident,
+                arguments,
+                body,
+            } => {
+                let name = self.name(&ident);
+                self.emit(OpCode::AssertNoLoopVar(name), line, col);
+                if !(1..=3).contains(&arguments.len()) {
+                    return self.raise(
+                        format!("Se encontro {} argumentos de 1..3", arguments.len()),
+                        line,
+                        col,
+                    );
+                }
+                let argc = arguments.len();
+                let span = self.chunk.spans.len();
+                for arg in arguments.iter() {
+                    self.span(arg.line, arg.col);
+                }
+                for arg in arguments {
+                    self.compile_expression(arg);
+                }
+                self.emit(OpCode::RangeInit { argc, span }, line, col);
+                self.compile_iteration(&[ident], body, line, col);
+            }
+            ExprType::ForIn {
+                idents,
+                iterable,
+                body,
+            } => {
+                for ident in idents.iter() {
+                    let name = self.name(ident);
+                    self.emit(OpCode::AssertNoLoopVar(name), line, col);
+                }
+                let (iter_line, iter_col) = (iterable.line, iterable.col);
+                self.compile_expression(*iterable);
+                self.emit(OpCode::IterInit, iter_line, iter_col);
+                self.compile_iteration(&idents, body, line, col);
+            }
+            ExprType::For {
+                ident,
+                init,
+                condition,
+                update,
+                body,
+            } => {
+                let name = self.name(&ident);
+                self.emit(OpCode::AssertNoLoopVar(name), line, col);
+
+                // La variable del bucle solo existe dentro del bucle
+                self.emit(OpCode::PushScope, line, col);
+                self.blocks.push(Block::Scope);
+                let (init_line, init_col) = (init.line, init.col);
+                self.emit(OpCode::AssertUndeclared(name), init_line, init_col);
+                self.compile_expression(*init);
+                self.emit(OpCode::DefineVar(name), init_line, init_col);
+                self.emit(OpCode::Pop, line, col);
+
+                let depth = self.depth;
+                let start = self.here();
+                let (cond_line, cond_col) = (condition.line, condition.col);
+                self.compile_expression(*condition);
+                self.emit(OpCode::AssertBool, cond_line, cond_col);
+                let end = self.emit(OpCode::JumpIfFalse(0), cond_line, cond_col);
+                let (breaks, continues) = self.compile_loop_body(body, depth, None);
+                for jump in continues {
+                    self.patch(jump);
+                }
+                self.compile_expression(*update);
+                self.emit(OpCode::Pop, line, col);
+                self.emit(OpCode::Jump(start), line, col);
+                self.patch(end);
+                for jump in breaks {
+                    self.patch(jump);
+                }
+                self.emit(OpCode::Void, line, col);
+                self.blocks.pop();
+                self.emit(OpCode::PopScope, line, col);
+            }
+            ExprType::Super {
+                method,
+                instance,
+                parent,
+            } => {
+                let name = self.name(&method);
+                self.compile_expression(*instance);
+                self.compile_expression(*parent);
+                self.emit(OpCode::GetSuper(name), line, col);
+            }
+        }
+    }
+
+    // Compila el cuerpo de un bucle, cada vuelta con su propio environment.
+    // Retorna los saltos de `romper` y `continuar` que faltan apuntar
+    fn compile_loop_body(
+        &mut self,
+        body: BlockStatement,
+        depth: usize,
+        continue_target: Option<usize>,
+    ) -> (Vec<usize>, Vec<usize>) {
+        self.blocks.push(Block::Loop {
+            depth,
+            iter: false,
+            continue_target,
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        self.compile_scoped_block(body);
+        self.emit(OpCode::Pop, 0, 0);
+        let Some(Block::Loop {
+            breaks, continues, ..
+        }) = self.blocks.pop()
+        else {
+            unreachable!()
+        };
+        (breaks, continues)
+    }
+
+    // Bucles `para` que toman sus valores de un iterador de la maquina
+    fn compile_iteration(
+        &mut self,
+        idents: &[String],
+        body: BlockStatement,
+        line: usize,
+        col: usize,
+    ) {
+        let depth = self.depth;
+        let start = self.here();
+        let count = if idents.len() == 1 { 1 } else { 2 };
+        let next = self.emit(OpCode::IterNext { exit: 0, count }, line, col);
+
+        self.blocks.push(Block::Loop {
+            depth,
+            iter: true,
+            continue_target: Some(start),
+            breaks: Vec::new(),
+            continues: Vec::new(),
+        });
+        // Las variables del bucle van en el mismo environment que el cuerpo.
+        // `IterNext` deja la primera arriba del stack, asi se declaran en orden
+        self.emit(OpCode::PushScope, line, col);
+        self.blocks.push(Block::Scope);
+        for ident in idents.iter().take(count) {
+            let name = self.name(ident);
+            self.emit(OpCode::Bind(name), line, col);
+        }
+        self.compile_block(body);
+        self.emit(OpCode::Pop, 0, 0);
+        self.blocks.pop();
+        self.emit(OpCode::PopScope, line, col);
+        let Some(Block::Loop { breaks, .. }) = self.blocks.pop() else {
+            unreachable!()
+        };
+        self.emit(OpCode::Jump(start), line, col);
+
+        self.patch(next);
+        for jump in breaks {
+            self.patch(jump);
+        }
+        self.emit(OpCode::IterEnd, line, col);
+        self.emit(OpCode::Void, line, col);
+    }
+
+    fn compile_args(
+        &mut self,
+        arguments: FnParams,
+        line: usize,
+        col: usize,
+        target: (usize, usize),
+    ) -> usize {
+        let args = arguments.iter().map(|arg| (arg.line, arg.col)).collect();
+        let mut names = Vec::with_capacity(arguments.len());
+        for arg in arguments {
+            let (name, value) = split_named_arg(arg);
+            names.push(name);
+            self.compile_expression(value);
+        }
+        self.chunk.sites.push(CallSite {
+            line,
+            col,
+            target,
+            args,
+            names,
+        });
+        self.chunk.sites.len() - 1
+    }
+
+    fn compile_infix(&mut self, left: Expression, right: Expression, operator: TokenType) {
+        let (line, col) = (left.line, left.col);
+        match operator {
+            TokenType::Dot => self.compile_member(left, right),
+            TokenType::And | TokenType::Or => {
+                let or = operator == TokenType::Or;
+                self.compile_expression(left);
+                let logical = self.emit(OpCode::Logical { or, end: 0 }, line, col);
+                let (right_line, right_col) = (right.line, right.col);
+                self.compile_expression(right);
+                self.emit(OpCode::LogicalEnd { or }, right_line, right_col);
+                self.patch(logical);
+            }
+            operator => {
+                self.compile_expression(left);
+                self.compile_expression(right);
+                match BinaryOp::from_token(&operator) {
+                    Some(op) => {
+                        self.emit(OpCode::Infix(op), line, col);
+                    }
+                    None => {
+                        self.emit(OpCode::PopN(2), line, col);
+                        self.emit(OpCode::Null, line, col);
+                    }
+                }
+            }
+        }
+    }
+
+    fn compile_member(&mut self, left: Expression, right: Expression) {
+        let (line, col) = (left.line, left.col);
+        self.compile_expression(left);
+        match right.r#type {
+            ExprType::Identifier(ident) => {
+                let name = self.name(&ident);
+                self.emit(OpCode::GetMember(name), right.line, right.col);
+            }
+            ExprType::Call {
+                function,
+                arguments,
+            } => match function.r#type {
+                ExprType::Identifier(ident) => {
+                    let name = self.name(&ident);
+                    let site =
+                        self.compile_args(arguments, function.line, function.col, (line, col));
+                    self.emit(
+                        OpCode::CallMember { name, site },
+                        function.line,
+                        function.col,
+                    );
+                }
+                _ => {
+                    self.emit(OpCode::Pop, line, col);
+                    self.raise(
+                        "El operador de acceso de miembro espera un identicador o llamada".into(),
+                        line,
+                        col,
+                    );
+                }
+            },
+            expr => {
+                self.emit(OpCode::Pop, line, col);
+                self.raise(
+                    format!(
+                        "El operador de acceso de miembro no puede operar con una expresion {}",
+                        expr.get_type()
+                    ),
+                    right.line,
+                    right.col,
+                );
+            }
+        }
+    }
+}
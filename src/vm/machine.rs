@@ -0,0 +1,1189 @@
+use std::{cell::RefCell, path::Path, rc::Rc};
+
+use indexmap::IndexMap;
+
+use crate::{
+    buildins::{
+        internal::get_buildin,
+        member::{match_member_fn, member_lock_err},
+    },
+    eval::{
+        environment::{Environment, RcEnvironment},
+        evaluator::{
+            bind_args, bind_method, call_buildin, call_operator, check_dict_key, contains_instance,
+            create_msg_err, eval_error_member, eval_index_obj, eval_logical_operand,
+            eval_prefix_obj, fn_param_kinds, fn_signature, for_condition, instance_member,
+            is_instance, match_case, match_infix_ops, max_depth_err, named_arg_err, new_instance,
+            obj_text, parent_class, repeated_key_err, set_member, super_method, throw_obj,
+            with_main_file, BoundArg, Evaluator, ModuleSource, MAX_DEPTH,
+        },
+        iterator::{new_iterator, PanaIterator},
+        objects::{new_rc_object, ClassObj, CompiledFnObj, ErrorObj, Object, RcObject, ResultObj},
+        resolver::resolve,
+    },
+    parser::statement::BlockStatement,
+    token::TokenType,
+    types::Numeric,
+};
+
+use super::{
+    bytecode::{BinaryOp, CallSite, Chunk, Constant, OpCode, Var},
+    compiler::compile_program,
+};
+
+struct Frame {
+    chunk: Rc<Chunk>,
+    ip: usize,
+    // Inicio de los valores de la funcion en el stack
+    base: usize,
+    env: RcEnvironment,
+    // Environment donde se definio la funcion, None en programas y modulos
+    closure_env: Option<RcEnvironment>,
+    iters_len: usize,
+    locks_len: usize,
+}
+
+// Estado guardado por `TryBegin` para volver a `capturar`
+struct Handler {
+    frame: usize,
+    target: usize,
+    stack_len: usize,
+    env: RcEnvironment,
+    iters_len: usize,
+    locks_len: usize,
+}
+
+enum VmIter {
+    Range {
+        next: usize,
+        end: usize,
+        step: usize,
+    },
+    Items(Box<dyn PanaIterator>),
+}
+
+struct IterState {
+    iter: VmIter,
+    locks_len: usize,
+}
+
+// Maquina virtual de stack. Las variables se leen en la posicion que calculo
+// el resolver y el evaluador solo se usa para cargar los modulos
+pub struct Vm {
+    eval: Evaluator,
+    env: RcEnvironment,
+    stack: Vec<ResultObj>,
+    frames: Vec<Frame>,
+    iters: Vec<IterState>,
+    handlers: Vec<Handler>,
+    // Colecciones que estan siendo recorridas por un bucle `para ... en`
+    iter_locks: Vec<RcObject>,
+    // Frames de funciones y el maximo de llamadas anidadas
+    calls: usize,
+    max_depth: usize,
+}
+
+type VmResult = Result<(), Box<ErrorObj>>;
+
+impl Default for Vm {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Vm {
+    pub fn new() -> Self {
+        Self {
+            eval: Evaluator::new(),
+            env: Rc::new(RefCell::new(Environment::new(None))),
+            stack: Vec::new(),
+            frames: Vec::new(),
+            iters: Vec::new(),
+            handlers: Vec::new(),
+            iter_locks: Vec::new(),
+            calls: 0,
+            max_depth: MAX_DEPTH,
+        }
+    }
+
+    pub fn set_script_args(&mut self, args: Vec<String>) {
+        self.eval.set_script_args(args);
+    }
+
+    // El evaluador busca los modulos relativos al archivo principal
+    pub fn set_main_file(&mut self, path: &Path) {
+        self.env.borrow_mut().set_module(path.display().to_string());
+        self.eval.set_main_file(path);
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn run_program(&mut self, mut program: BlockStatement) -> ResultObj {
+        if let Err(err) = resolve(&mut program, &self.env) {
+            return ResultObj::Copy(Object::Error(Box::new(err)));
+        }
+        let chunk = Rc::new(compile_program(program));
+        self.stack.clear();
+        self.frames.clear();
+        self.iters.clear();
+        self.handlers.clear();
+        self.iter_locks.clear();
+        self.calls = 0;
+        let res_obj = self.execute(chunk, self.env.clone());
+        with_main_file(res_obj, &self.env)
+    }
+
+    // Ejecuta un programa o modulo hasta su `Return`, los errores que no se
+    // capturan dentro de el se retornan
+    fn execute(&mut self, chunk: Rc<Chunk>, env: RcEnvironment) -> ResultObj {
+        let boundary = self.frames.len();
+        self.frames.push(Frame {
+            chunk,
+            ip: 0,
+            base: self.stack.len(),
+            env,
+            closure_env: None,
+            iters_len: self.iters.len(),
+            locks_len: self.iter_locks.len(),
+        });
+        self.run(boundary)
+    }
+
+    fn run(&mut self, boundary: usize) -> ResultObj {
+        loop {
+            let frame = self.frames.last_mut().expect("La maquina no tiene frames");
+            let op = frame.chunk.code[frame.ip];
+            let (line, col) = frame.chunk.positions[frame.ip];
+            frame.ip += 1;
+
+            // Las instrucciones mas comunes se ejecutan aqui. Las demas, y
+            // los casos que no son de enteros o slots, pasan por `step`
+            let res = match op {
+                OpCode::Return => {
+                    let value = self.pop();
+                    let frame = self.pop_frame();
+                    self.stack.truncate(frame.base);
+                    if self.frames.len() == boundary {
+                        return value;
+                    }
+                    self.stack.push(value);
+                    Ok(())
+                }
+                OpCode::Constant(idx) => {
+                    let obj = match &frame.chunk.constants[idx] {
+                        Constant::Numeric(numeric) => {
+                            ResultObj::Copy(Object::Numeric(numeric.clone()))
+                        }
+                        // Las cadenas son mutables, cada evaluacion crea una nueva
+                        Constant::String(string) => {
+                            ResultObj::Ref(new_rc_object(Object::String(string.clone())))
+                        }
+                        Constant::Fn(_) => unreachable!("Las funciones se crean con `Closure`"),
+                    };
+                    self.stack.push(obj);
+                    Ok(())
+                }
+                OpCode::Pop => {
+                    self.pop();
+                    Ok(())
+                }
+                OpCode::GetVar(var) => {
+                    let obj = match var {
+                        Var::Local { depth, slot, .. } => frame.env.borrow().get_slot(depth, slot),
+                        Var::Global { slot, .. } => frame.env.borrow().get_global(slot),
+                        Var::Name(_) => None,
+                    };
+                    match obj {
+                        Some(obj) => {
+                            self.stack.push(obj);
+                            Ok(())
+                        }
+                        None => self.step(op, line, col),
+                    }
+                }
+                OpCode::SetVar(var) => {
+                    let value = self
+                        .stack
+                        .last()
+                        .expect("El stack de la maquina esta vacio");
+                    let mut env = frame.env.borrow_mut();
+                    match var {
+                        Var::Local { depth, slot, .. } => {
+                            env.set_slot(depth, slot, value.clone());
+                        }
+                        Var::Global { slot, .. } => {
+                            env.set_global(slot, value.clone());
+                        }
+                        Var::Name(name) => {
+                            env.update(&frame.chunk.names[name], value.clone());
+                        }
+                    }
+                    Ok(())
+                }
+                OpCode::AssertDeclared(Var::Local { depth, slot, .. })
+                    if frame.env.borrow().get_slot(depth, slot).is_some() =>
+                {
+                    Ok(())
+                }
+                OpCode::AssertDeclared(Var::Global { slot, .. })
+                    if frame.env.borrow().get_global(slot).is_some() =>
+                {
+                    Ok(())
+                }
+                OpCode::Infix(bin_op) => {
+                    let len = self.stack.len();
+                    let obj = match &self.stack[len - 2..] {
+                        [ResultObj::Copy(Object::Numeric(Numeric::Int(a))), ResultObj::Copy(Object::Numeric(Numeric::Int(b)))] => {
+                            int_infix(*a, *b, bin_op)
+                        }
+                        _ => None,
+                    };
+                    match obj {
+                        Some(obj) => {
+                            self.stack.truncate(len - 1);
+                            self.stack[len - 2] = ResultObj::Copy(obj);
+                            Ok(())
+                        }
+                        None => self.step(op, line, col),
+                    }
+                }
+                OpCode::Jump(target) => {
+                    frame.ip = target;
+                    Ok(())
+                }
+                OpCode::JumpIfFalse(target) => {
+                    if let ResultObj::Copy(Object::Boolean(false)) = self.pop() {
+                        self.jump(target);
+                    }
+                    Ok(())
+                }
+                OpCode::Truthy(exit) => {
+                    let truthy = match self.peek() {
+                        ResultObj::Copy(Object::Numeric(numeric)) => Some(!numeric.is_int_zero()),
+                        ResultObj::Copy(Object::Boolean(b)) => Some(*b),
+                        ResultObj::Copy(Object::Null) => Some(false),
+                        _ => None,
+                    };
+                    match truthy {
+                        Some(truthy) => {
+                            let top = self.stack.len() - 1;
+                            self.stack[top] = ResultObj::Copy(Object::Boolean(truthy));
+                        }
+                        // Cualquier otro valor se vuelve el resultado de la expresion
+                        None => self.jump(exit),
+                    }
+                    Ok(())
+                }
+                OpCode::PushScope => {
+                    frame.env = Rc::new(RefCell::new(Environment::new(Some(frame.env.clone()))));
+                    Ok(())
+                }
+                OpCode::PopScope => {
+                    let parent = frame.env.borrow().parent();
+                    frame.env = parent.expect("El scope tiene un environment padre");
+                    Ok(())
+                }
+                op => self.step(op, line, col),
+            };
+
+            if let Err(err) = res {
+                if let Some(err) = self.unwind(Box::new(err.with_position(line, col)), boundary) {
+                    return ResultObj::Copy(Object::Error(err));
+                }
+            }
+        }
+    }
+
+    // Sube el error hasta el `capturar` mas cercano dentro de `boundary`.
+    // Si no hay, descarta los frames y retorna el error
+    fn unwind(&mut self, mut err: Box<ErrorObj>, boundary: usize) -> Option<Box<ErrorObj>> {
+        let handler = self
+            .handlers
+            .last()
+            .filter(|handler| handler.frame >= boundary)
+            .map(|handler| handler.frame);
+        let target = handler.unwrap_or(boundary);
+        while self.frames.len() > target + 1 {
+            let frame = self.pop_frame();
+            // Los errores dentro de funciones de un modulo indican su archivo
+            if let Some(file) = frame.closure_env.and_then(|env| env.borrow().module_name()) {
+                err = Box::new(err.with_file(&file));
+            }
+        }
+
+        if handler.is_none() {
+            let frame = self.pop_frame();
+            self.stack.truncate(frame.base);
+            return Some(err);
+        }
+
+        let handler = self.handlers.pop().expect("El handler existe");
+        self.stack.truncate(handler.stack_len);
+        self.iters.truncate(handler.iters_len);
+        self.iter_locks.truncate(handler.locks_len);
+        let frame = self.frames.last_mut().expect("El frame del handler existe");
+        frame.env = handler.env;
+        frame.ip = handler.target;
+        self.stack.push(ResultObj::Copy(Object::CaughtError(err)));
+        None
+    }
+
+    fn pop_frame(&mut self) -> Frame {
+        let frame = self.frames.pop().expect("La maquina no tiene frames");
+        if frame.closure_env.is_some() {
+            self.calls -= 1;
+        }
+        self.iters.truncate(frame.iters_len);
+        self.iter_locks.truncate(frame.locks_len);
+        let frames_len = self.frames.len();
+        while self
+            .handlers
+            .last()
+            .is_some_and(|handler| handler.frame >= frames_len)
+        {
+            self.handlers.pop();
+        }
+        frame
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames.last().expect("La maquina no tiene frames")
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().expect("La maquina no tiene frames")
+    }
+
+    fn jump(&mut self, target: usize) {
+        self.frame_mut().ip = target;
+    }
+
+    fn pop(&mut self) -> ResultObj {
+        self.stack.pop().expect("El stack de la maquina esta vacio")
+    }
+
+    fn peek(&self) -> &ResultObj {
+        self.stack
+            .last()
+            .expect("El stack de la maquina esta vacio")
+    }
+
+    fn split_args(&mut self, count: usize) -> Vec<ResultObj> {
+        self.stack.split_off(self.stack.len() - count)
+    }
+
+    // Convierte un resultado del evaluador en valor del stack o error
+    fn push_result(&mut self, obj: ResultObj) -> VmResult {
+        match obj {
+            ResultObj::Copy(Object::Error(err)) => Err(err),
+            obj => {
+                self.stack.push(obj);
+                Ok(())
+            }
+        }
+    }
+
+    fn step(&mut self, op: OpCode, line: usize, col: usize) -> VmResult {
+        let chunk = Rc::clone(&self.frame().chunk);
+        match op {
+            OpCode::Null => self.stack.push(ResultObj::Copy(Object::Null)),
+            OpCode::Void => self.stack.push(ResultObj::Copy(Object::Void)),
+            OpCode::True => self.stack.push(ResultObj::Copy(Object::Boolean(true))),
+            OpCode::False => self.stack.push(ResultObj::Copy(Object::Boolean(false))),
+            OpCode::PopN(count) => {
+                self.stack.truncate(self.stack.len() - count);
+            }
+
+            OpCode::GetVar(var) => {
+                let obj = self.get_var(&chunk, var, line, col)?;
+                self.stack.push(obj);
+            }
+            OpCode::DefineVar(name) => {
+                if let ResultObj::Copy(Object::Void) = self.peek() {
+                    return Err(Box::new(create_msg_err(
+                        "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                        line,
+                        col,
+                    )));
+                }
+                let value = self.peek().clone();
+                self.frame()
+                    .env
+                    .borrow_mut()
+                    .declare(&chunk.names[name], value);
+            }
+            OpCode::Bind(name) => {
+                let value = self.pop();
+                self.frame()
+                    .env
+                    .borrow_mut()
+                    .declare(&chunk.names[name], value);
+            }
+            OpCode::AssertDeclared(var) => {
+                let env = self.frame().env.borrow();
+                let (declared, name) = match var {
+                    Var::Local { depth, slot, name } => (env.get_slot(depth, slot).is_some(), name),
+                    Var::Global { slot, name } => (env.get_global(slot).is_some(), name),
+                    Var::Name(name) => (env.exist(&chunk.names[name]), name),
+                };
+                if !declared {
+                    return Err(Box::new(create_msg_err(
+                        format!("El no existe referencias hacia `{}`", chunk.names[name]),
+                        line,
+                        col,
+                    )));
+                }
+            }
+            OpCode::AssertUndeclared(name) => {
+                let name = &chunk.names[name];
+                if self.frame().env.borrow().get(name).is_some() {
+                    return Err(Box::new(create_msg_err(
+                        format!("El identificador `{}` ya habia sido declarado", name),
+                        line,
+                        col,
+                    )));
+                }
+            }
+            OpCode::AssertNoLoopVar(name) => {
+                let name = &chunk.names[name];
+                if self.frame().env.borrow().exist(name) {
+                    return Err(Box::new(create_msg_err(
+                        format!("Ya existe referencias hacia la variable `{}`", name),
+                        line,
+                        col,
+                    )));
+                }
+            }
+
+            OpCode::Infix(op) => {
+                let right = self.pop();
+                let left = self.pop();
+                let obj = call_operator(&left, &right, &op.token(), &mut |callee, args| {
+                    self.call_function(callee, args, line, col)
+                });
+                let obj = match obj {
+                    Some(obj) => obj,
+                    None => match_infix_ops(left, right, op.token()),
+                };
+                self.push_result(obj)?;
+            }
+            OpCode::Negate => {
+                let right = self.pop();
+                self.stack.push(eval_prefix_obj(&TokenType::Minus, right));
+            }
+            OpCode::Not => {
+                let right = self.pop();
+                self.stack.push(eval_prefix_obj(&TokenType::Not, right));
+            }
+            OpCode::Logical { or, end } => {
+                let left = self.pop();
+                let left = self.logical_operand(left, or, line, col)?;
+                // El lado izquierdo decide el resultado sin evaluar el derecho
+                if left == or {
+                    self.stack.push(ResultObj::Copy(Object::Boolean(left)));
+                    self.jump(end);
+                }
+            }
+            OpCode::LogicalEnd { or } => {
+                let right = self.pop();
+                let right = self.logical_operand(right, or, line, col)?;
+                self.stack.push(ResultObj::Copy(Object::Boolean(right)));
+            }
+
+            OpCode::List(count) => {
+                let objs = self.split_args(count);
+                self.stack
+                    .push(ResultObj::Ref(new_rc_object(Object::List(objs))));
+            }
+            OpCode::Dict(count) => {
+                let objs = self.split_args(count * 2);
+                #[allow(clippy::mutable_key_type)]
+                let mut pairs = IndexMap::new();
+                let mut objs = objs.into_iter();
+                while let (Some(key), Some(value)) = (objs.next(), objs.next()) {
+                    pairs.insert(key, value);
+                }
+                self.stack
+                    .push(ResultObj::Ref(new_rc_object(Object::Dictionary(pairs))));
+            }
+            OpCode::CheckKey(index) => {
+                let key = self.peek();
+                check_dict_key(key, line, col)?;
+                // Las llaves anteriores estan intercaladas con sus valores
+                let top = self.stack.len() - 1;
+                if (1..=index).any(|i| self.stack[top - 2 * i] == *key) {
+                    return Err(Box::new(repeated_key_err(key, line, col)));
+                }
+            }
+            OpCode::ToStr => {
+                if let ResultObj::Copy(Object::Void) = self.peek() {
+                    return Err(Box::new(create_msg_err(
+                        "No se puede interpolar el tipo de dato vacio".to_owned(),
+                        line,
+                        col,
+                    )));
+                }
+                if contains_instance(self.peek()) {
+                    let obj = self.pop();
+                    let text = obj_text(&obj, line, col, &mut |callee, args| {
+                        self.call_function(callee, args, line, col)
+                    })?;
+                    self.stack
+                        .push(ResultObj::Ref(new_rc_object(Object::String(text))));
+                }
+            }
+            OpCode::Concat(count) => {
+                let string = self
+                    .split_args(count)
+                    .iter()
+                    .map(|part| part.to_string())
+                    .collect::<String>();
+                self.stack
+                    .push(ResultObj::Ref(new_rc_object(Object::String(string))));
+            }
+            OpCode::GetIndex(span) => {
+                let index = self.pop();
+                let left = self.pop();
+                let (index_line, index_col) = chunk.spans[span];
+                let obj = eval_index_obj(
+                    &self.iter_locks,
+                    left,
+                    index,
+                    None,
+                    line,
+                    col,
+                    index_line,
+                    index_col,
+                );
+                self.push_result(obj)?;
+            }
+            OpCode::SetIndex(span) => {
+                let index = self.pop();
+                let left = self.pop();
+                let value = self.pop();
+                let (index_line, index_col) = chunk.spans[span];
+                let obj = eval_index_obj(
+                    &self.iter_locks,
+                    left,
+                    index,
+                    Some(value),
+                    line,
+                    col,
+                    index_line,
+                    index_col,
+                );
+                self.push_result(obj)?;
+            }
+            OpCode::GetMember(name) => {
+                let target = self.pop();
+                let name = &chunk.names[name];
+                let obj = match target {
+                    ResultObj::Copy(Object::Module(module)) => {
+                        module_member(&module.name, &module.env, name, line, col)?
+                    }
+                    ResultObj::Copy(Object::CaughtError(err)) => {
+                        eval_error_member(&err, name, line, col)
+                    }
+                    target if is_instance(&target) => instance_member(&target, name, line, col)?,
+                    _ => {
+                        return Err(Box::new(create_msg_err(
+                            "El operador de acceso de miembro no puede operar con una expresion identificador".to_owned(),
+                            line,
+                            col,
+                        )))
+                    }
+                };
+                self.push_result(obj)?;
+            }
+            OpCode::SetMember { name, span } => {
+                let value = self.pop();
+                let target = self.pop();
+                if let ResultObj::Copy(Object::Void) = value {
+                    let (line, col) = chunk.spans[span];
+                    return Err(Box::new(create_msg_err(
+                        "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                        line,
+                        col,
+                    )));
+                }
+                let obj = set_member(&target, &chunk.names[name], value, line, col)?;
+                self.stack.push(obj);
+            }
+
+            OpCode::DefaultParam { slot, skip } => {
+                let missing = matches!(
+                    self.frame().env.borrow().get_slot(0, slot),
+                    Some(ResultObj::Copy(Object::Void))
+                );
+                if !missing {
+                    self.jump(skip);
+                }
+            }
+            OpCode::DefineParam(slot) => {
+                if let ResultObj::Copy(Object::Void) = self.peek() {
+                    return Err(Box::new(create_msg_err(
+                        "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                        line,
+                        col,
+                    )));
+                }
+                let value = self.peek().clone();
+                self.frame().env.borrow_mut().set_slot(0, slot, value);
+            }
+            OpCode::Closure(idx) => {
+                let Constant::Fn(ref proto) = chunk.constants[idx] else {
+                    unreachable!("`Closure` siempre apunta a una funcion");
+                };
+                let env = self.frame().env.clone();
+                self.stack.push(ResultObj::Copy(Object::CompiledFn(Box::new(
+                    CompiledFnObj {
+                        proto: proto.clone(),
+                        env,
+                    },
+                ))));
+            }
+            OpCode::Call(site) => {
+                let site = &chunk.sites[site];
+                let args = self.split_args(site.args.len());
+                let callee = self.pop();
+                self.call_value(callee, args, site)?;
+            }
+            OpCode::TailCall(site) => {
+                let site = &chunk.sites[site];
+                let args = self.split_args(site.args.len());
+                match self.pop() {
+                    // La funcion actual ya termino, la llamada usa su lugar
+                    // sin aumentar la profundidad
+                    ResultObj::Copy(Object::CompiledFn(func)) => {
+                        let env = self.bind_fn_args(&func, args, site)?;
+                        let frame = self.pop_frame();
+                        self.stack.truncate(frame.base);
+                        self.push_fn_frame(&func, env);
+                    }
+                    callee => self.call_value(callee, args, site)?,
+                }
+            }
+            OpCode::CallMember { name, site } => {
+                let site = &chunk.sites[site];
+                let name = &chunk.names[name];
+                let args = self.split_args(site.args.len());
+                match self.pop() {
+                    ResultObj::Copy(Object::Module(module)) => {
+                        let callee = module_member(&module.name, &module.env, name, line, col)?;
+                        self.call_value(callee, args, site)?;
+                    }
+                    target if is_instance(&target) => {
+                        let callee = instance_member(&target, name, line, col)?;
+                        self.call_value(callee, args, site)?;
+                    }
+                    _ if site.names.iter().any(Option::is_some) => {
+                        let (line, col) = site
+                            .names
+                            .iter()
+                            .zip(&site.args)
+                            .find(|(name, _)| name.is_some())
+                            .map(|(_, position)| *position)
+                            .unwrap_or(site.target);
+                        return Err(Box::new(named_arg_err(line, col)));
+                    }
+                    target => {
+                        let (target_line, target_col) = site.target;
+                        if let Some(obj) = member_lock_err(
+                            &self.iter_locks,
+                            name,
+                            &target,
+                            target_line,
+                            target_col,
+                        ) {
+                            return self.push_result(obj);
+                        }
+                        let obj = match_member_fn(
+                            &mut |callee, args, line, col| {
+                                self.call_function(callee, args, line, col)
+                            },
+                            name.clone(),
+                            args,
+                            &site.args,
+                            target,
+                            site.target.0,
+                            site.target.1,
+                        );
+                        self.push_result(obj)?;
+                    }
+                }
+            }
+            OpCode::GetSuper(name) => {
+                let parent = self.pop();
+                let instance = self.pop();
+                let obj = super_method(&parent, &instance, &chunk.names[name], line, col)?;
+                self.stack.push(obj);
+            }
+            OpCode::Class {
+                name,
+                methods,
+                parent,
+            } => {
+                let methods = self
+                    .split_args(methods)
+                    .into_iter()
+                    .map(|method| match method {
+                        ResultObj::Copy(Object::CompiledFn(ref func)) => {
+                            let name = func.proto.name.clone().expect("Los metodos tienen nombre");
+                            (name, method)
+                        }
+                        _ => unreachable!("Los metodos se crean con `Closure`"),
+                    })
+                    .collect();
+                let parent = match parent {
+                    Some(span) => {
+                        let (line, col) = chunk.spans[span];
+                        Some(parent_class(self.pop(), line, col)?)
+                    }
+                    None => None,
+                };
+                let class = ClassObj {
+                    name: chunk.names[name].clone(),
+                    parent,
+                    methods,
+                };
+                self.stack
+                    .push(ResultObj::Copy(Object::Class(Rc::new(class))));
+            }
+            OpCode::Return
+            | OpCode::Constant(_)
+            | OpCode::Pop
+            | OpCode::SetVar(_)
+            | OpCode::Jump(_)
+            | OpCode::JumpIfFalse(_)
+            | OpCode::Truthy(_)
+            | OpCode::PushScope
+            | OpCode::PopScope => unreachable!("La instruccion {:?} se ejecuta en `run`", op),
+            OpCode::AssertBool => {
+                let obj = self.peek().clone();
+                if let Err(ResultObj::Copy(Object::Error(err))) = for_condition(obj, line, col) {
+                    return Err(err);
+                }
+            }
+            OpCode::Match { pattern, next } => {
+                let Some(bindings) = match_case(&chunk.patterns[pattern], self.peek()) else {
+                    self.jump(next);
+                    return Ok(());
+                };
+                let frame = self.frame_mut();
+                let mut env = Environment::new(Some(frame.env.clone()));
+                for (name, obj) in bindings {
+                    env.declare(&name, obj);
+                }
+                frame.env = Rc::new(RefCell::new(env));
+            }
+            OpCode::MatchEnd => {
+                let result = self.pop();
+                self.pop();
+                self.stack.push(result);
+            }
+
+            OpCode::IterInit => {
+                let iterable = self.pop();
+                let Some(iter) = new_iterator(&iterable) else {
+                    return Err(Box::new(create_msg_err(
+                        format!("No se puede iterar el tipo de dato {}", iterable.get_type()),
+                        line,
+                        col,
+                    )));
+                };
+                let locks_len = self.iter_locks.len();
+                if let ResultObj::Ref(obj) = iterable {
+                    self.iter_locks.push(obj);
+                }
+                self.iters.push(IterState {
+                    iter: VmIter::Items(iter),
+                    locks_len,
+                });
+            }
+            OpCode::RangeInit { argc, span } => {
+                let args = self.split_args(argc);
+                let iter = range_iter(args, &chunk.spans[span..span + argc])?;
+                self.iters.push(IterState {
+                    iter,
+                    locks_len: self.iter_locks.len(),
+                });
+            }
+            OpCode::IterNext { exit, count } => {
+                let state = self.iters.last_mut().expect("El bucle tiene un iterador");
+                match &mut state.iter {
+                    VmIter::Range { next, end, step } => {
+                        if *next >= *end {
+                            self.jump(exit);
+                            return Ok(());
+                        }
+                        let value = *next as i64;
+                        *next += *step;
+                        self.stack
+                            .push(ResultObj::Copy(Object::Numeric(Numeric::Int(value))));
+                    }
+                    VmIter::Items(iter) => match iter.next_item() {
+                        Some(item) if count == 1 => self.stack.push(item.value),
+                        // Se declaran al sacarlas del stack, la primera queda arriba
+                        Some(item) => {
+                            self.stack.push(item.pair.1);
+                            self.stack.push(item.pair.0);
+                        }
+                        None => self.jump(exit),
+                    },
+                }
+            }
+            OpCode::IterEnd => {
+                let state = self.iters.pop().expect("El bucle tiene un iterador");
+                self.iter_locks.truncate(state.locks_len);
+            }
+
+            OpCode::TryBegin(target) => {
+                let handler = Handler {
+                    frame: self.frames.len() - 1,
+                    target,
+                    stack_len: self.stack.len(),
+                    env: self.frame().env.clone(),
+                    iters_len: self.iters.len(),
+                    locks_len: self.iter_locks.len(),
+                };
+                self.handlers.push(handler);
+            }
+            OpCode::TryEnd => {
+                self.handlers.pop();
+            }
+            OpCode::JumpIfExit(target) => {
+                if let ResultObj::Copy(Object::CaughtError(err)) = self.peek() {
+                    if err.salida.is_some() {
+                        self.jump(target);
+                    }
+                }
+            }
+            OpCode::Throw => {
+                let obj = self.pop();
+                if let ResultObj::Copy(Object::Error(err)) = throw_obj(obj, line, col) {
+                    return Err(err);
+                }
+            }
+            OpCode::Rethrow => {
+                if let ResultObj::Copy(Object::CaughtError(err)) = self.pop() {
+                    return Err(err);
+                }
+            }
+            OpCode::Raise(msg) => {
+                return Err(Box::new(create_msg_err(
+                    chunk.messages[msg].clone(),
+                    line,
+                    col,
+                )));
+            }
+
+            OpCode::LoadModule(path) => {
+                let module = match self.eval.begin_module(&chunk.names[path], line, col) {
+                    Ok(ModuleSource::Loaded(module)) => module,
+                    Ok(ModuleSource::Pending(pending, program)) => {
+                        let chunk = Rc::new(compile_program(program));
+                        let res_obj = self.execute(chunk, pending.env.clone());
+                        self.eval.end_module(pending, res_obj)
+                    }
+                    Err(err) => err,
+                };
+                self.push_result(module)?;
+            }
+            OpCode::ImportMember(name) => {
+                let ResultObj::Copy(Object::Module(module)) = self.peek() else {
+                    unreachable!("`ImportMember` siempre sigue a `LoadModule`");
+                };
+                let obj = module_member(&module.name, &module.env, &chunk.names[name], line, col)?;
+                self.stack.push(obj);
+            }
+        }
+        Ok(())
+    }
+
+    fn logical_operand(
+        &self,
+        obj: ResultObj,
+        or: bool,
+        line: usize,
+        col: usize,
+    ) -> Result<bool, Box<ErrorObj>> {
+        let operator = if or { TokenType::Or } else { TokenType::And };
+        match eval_logical_operand(obj, &operator, line, col) {
+            Ok(b) => Ok(b),
+            Err(ResultObj::Copy(Object::Error(err))) => Err(err),
+            Err(_) => unreachable!(),
+        }
+    }
+
+    // Llama a una funcion hasta que retorna, para las funciones miembro que
+    // reciben funciones. Todos los argumentos toman la posicion `line`, `col`
+    fn call_function(
+        &mut self,
+        callee: ResultObj,
+        args: Vec<ResultObj>,
+        line: usize,
+        col: usize,
+    ) -> ResultObj {
+        let site = CallSite {
+            line,
+            col,
+            target: (line, col),
+            args: vec![(line, col); args.len()],
+            names: vec![None; args.len()],
+        };
+        self.call_nested(callee, args, &site)
+    }
+
+    // Ejecuta la llamada dentro de la instruccion actual y retorna su valor
+    fn call_nested(
+        &mut self,
+        callee: ResultObj,
+        args: Vec<ResultObj>,
+        site: &CallSite,
+    ) -> ResultObj {
+        let boundary = self.frames.len();
+        if let Err(err) = self.call_value(callee, args, site) {
+            return ResultObj::Copy(Object::Error(err));
+        }
+        if self.frames.len() > boundary {
+            self.run(boundary)
+        } else {
+            self.pop()
+        }
+    }
+
+    fn call_value(&mut self, callee: ResultObj, args: Vec<ResultObj>, site: &CallSite) -> VmResult {
+        match callee {
+            ResultObj::Copy(Object::CompiledFn(func)) => {
+                if self.calls >= self.max_depth {
+                    return Err(Box::new(max_depth_err(self.max_depth, site.line, site.col)));
+                }
+                let env = self.bind_fn_args(&func, args, site)?;
+                self.push_fn_frame(&func, env);
+                Ok(())
+            }
+            // El metodo `iniciar` se ejecuta antes de retornar la instancia
+            ResultObj::Copy(Object::Class(class)) => {
+                let instance = new_instance(&class);
+                match class.find_method("iniciar") {
+                    Some((owner, init)) => {
+                        let init = bind_method(&init, &instance, &owner);
+                        if let ResultObj::Copy(Object::Error(err)) =
+                            self.call_nested(init, args, site)
+                        {
+                            return Err(err);
+                        }
+                    }
+                    None if !args.is_empty() => {
+                        return Err(Box::new(create_msg_err(
+                            format!(
+                                "Se encontro {} argumentos, se espera {}()",
+                                args.len(),
+                                class.name
+                            ),
+                            site.line,
+                            site.col,
+                        )));
+                    }
+                    None => {}
+                }
+                self.stack.push(instance);
+                Ok(())
+            }
+            ResultObj::Copy(Object::BuildinFn(func)) => {
+                let obj = call_buildin(&func, args, site, &mut |callee, args, line, col| {
+                    self.call_function(callee, args, line, col)
+                });
+                self.push_result(obj)
+            }
+            _ => Err(Box::new(create_msg_err(
+                "La operacion de llamada solo puede ser aplicada a objetos que sean funciones"
+                    .to_owned(),
+                site.line,
+                site.col,
+            ))),
+        }
+    }
+
+    // Crea el environment de la llamada con los argumentos ya asignados
+    fn bind_fn_args(
+        &mut self,
+        func: &CompiledFnObj,
+        args: Vec<ResultObj>,
+        site: &CallSite,
+    ) -> Result<RcEnvironment, Box<ErrorObj>> {
+        let mut named_args = Vec::with_capacity(args.len());
+        for ((arg, name), (line, col)) in args.into_iter().zip(&site.names).zip(&site.args) {
+            if let ResultObj::Copy(Object::Void) = arg {
+                return Err(Box::new(create_msg_err(
+                    "No se puede asignar el tipo de dato vacio a una variable".to_owned(),
+                    *line,
+                    *col,
+                )));
+            }
+            named_args.push((name.clone(), arg));
+        }
+        let params = &func.proto.params;
+        let bound = bind_args(
+            &fn_param_kinds(params),
+            named_args,
+            &|| fn_signature(func.proto.name.as_deref(), params),
+            site.line,
+            site.col,
+        )?;
+        // Cada parametro toma el slot de su posicion. Los que faltan quedan
+        // vacios hasta que `DefaultParam` les da su valor por defecto
+        let mut env = Environment::new(Some(func.env.clone()));
+        for (param, arg) in params.iter().zip(bound) {
+            let obj = match arg {
+                BoundArg::Value(obj) => obj,
+                BoundArg::Rest(objs) => ResultObj::Ref(new_rc_object(Object::List(objs))),
+                BoundArg::Missing => ResultObj::Copy(Object::Void),
+            };
+            env.declare(&param.name, obj);
+        }
+        Ok(Rc::new(RefCell::new(env)))
+    }
+
+    fn get_var(
+        &self,
+        chunk: &Chunk,
+        var: Var,
+        line: usize,
+        col: usize,
+    ) -> Result<ResultObj, Box<ErrorObj>> {
+        let not_found = |name: &str| {
+            Box::new(create_msg_err(
+                format!("El identicador `{}` no existe", name),
+                line,
+                col,
+            ))
+        };
+        let env = self.frame().env.borrow();
+        let name = match var {
+            Var::Local { depth, slot, name } => {
+                // Sin valor es una funcion llamada antes de que se declare la variable
+                return env
+                    .get_slot(depth, slot)
+                    .ok_or_else(|| not_found(&chunk.names[name]));
+            }
+            Var::Global { slot, name } => match env.get_global(slot) {
+                Some(obj) => return Ok(obj),
+                None => &chunk.names[name],
+            },
+            Var::Name(name) => {
+                let name = &chunk.names[name];
+                match env.get(name) {
+                    Some(obj) => return Ok(obj),
+                    None => name,
+                }
+            }
+        };
+        // Sin declarar aun, el nombre puede ser una funcion interna
+        get_buildin(name).ok_or_else(|| not_found(name))
+    }
+
+    fn push_fn_frame(&mut self, func: &CompiledFnObj, env: RcEnvironment) {
+        self.calls += 1;
+        self.frames.push(Frame {
+            chunk: func.proto.chunk.clone(),
+            ip: 0,
+            base: self.stack.len(),
+            env,
+            closure_env: Some(func.env.clone()),
+            iters_len: self.iters.len(),
+            locks_len: self.iter_locks.len(),
+        });
+    }
+}
+
+// Operaciones entre enteros que se hacen directo en la maquina. Las que
+// desbordan y las divisiones pasan por `match_infix_ops`
+fn int_infix(a: i64, b: i64, op: BinaryOp) -> Option<Object> {
+    let obj = match op {
+        BinaryOp::Add => Object::Numeric(Numeric::Int(a.checked_add(b)?)),
+        BinaryOp::Sub => Object::Numeric(Numeric::Int(a.checked_sub(b)?)),
+        BinaryOp::Mul => Object::Numeric(Numeric::Int(a.checked_mul(b)?)),
+        BinaryOp::Eq => Object::Boolean(a == b),
+        BinaryOp::NotEq => Object::Boolean(a != b),
+        BinaryOp::Lt => Object::Boolean(a < b),
+        BinaryOp::Gt => Object::Boolean(a > b),
+        BinaryOp::LtEq => Object::Boolean(a <= b),
+        BinaryOp::GtEq => Object::Boolean(a >= b),
+        BinaryOp::Div | BinaryOp::Mod => return None,
+    };
+    Some(obj)
+}
+
+fn module_member(
+    module: &str,
+    env: &RcEnvironment,
+    name: &String,
+    line: usize,
+    col: usize,
+) -> Result<ResultObj, Box<ErrorObj>> {
+    env.borrow().get(name).ok_or_else(|| {
+        Box::new(create_msg_err(
+            format!("El modulo \"{}\" no tiene el miembro `{}`", module, name),
+            line,
+            col,
+        ))
+    })
+}
+
+// Igual que los bucles `para ... en rango(...)` del evaluador, los errores
+// usan la posicion del primer argumento
+fn range_iter(args: Vec<ResultObj>, spans: &[(usize, usize)]) -> Result<VmIter, Box<ErrorObj>> {
+    let (line, col) = spans[0];
+    let type_err = |obj: &ResultObj, line: usize, col: usize| {
+        Box::new(create_msg_err(
+            format!(
+                "No se soporta operaciones de rango con el tipo de dato `{}`",
+                obj.get_type()
+            ),
+            line,
+            col,
+        ))
+    };
+    let int_arg = |i: usize| match &args[i] {
+        ResultObj::Copy(Object::Numeric(Numeric::Int(int))) => Ok(*int),
+        obj => Err(type_err(obj, spans[i].0, spans[i].1)),
+    };
+    let end = if args.len() > 1 { int_arg(1)? } else { 0 };
+    let step = if args.len() > 2 { int_arg(2)? } else { 1 };
+
+    if end < 0 || step < 0 {
+        return Err(Box::new(create_msg_err(
+            "No se puede hacer operaciones de rango con numeros negativos".into(),
+            line,
+            col,
+        )));
+    } else if step == 0 {
+        return Err(Box::new(create_msg_err(
+            "Los pasos del rango debe ser mayor que 0".into(),
+            line,
+            col,
+        )));
+    }
+
+    let begin = match &args[0] {
+        ResultObj::Copy(Object::Numeric(Numeric::Int(begin))) => *begin,
+        obj => return Err(type_err(obj, line, col)),
+    };
+    if begin < 0 {
+        return Err(Box::new(create_msg_err(
+            "No se puede hacer operaciones de rango con numeros negativos".into(),
+            line,
+            col,
+        )));
+    }
+    // Como en el evaluador, el rango siempre empieza en 0
+    let end = if end != 0 { end } else { begin };
+    Ok(VmIter::Range {
+        next: 0,
+        end: end as usize,
+        step: step as usize,
+    })
+}